@@ -1,6 +1,8 @@
-import React, { useState } from 'react';
+import React, { useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
 import { Outlet, useNavigate, useLocation } from 'react-router-dom';
 import {
+  Alert,
   AppBar,
   Box,
   CssBaseline,
@@ -11,6 +13,7 @@ import {
   ListItemButton,
   ListItemIcon,
   ListItemText,
+  Snackbar,
   Toolbar,
   Typography,
 } from '@mui/material';
@@ -23,6 +26,8 @@ import {
   Label as LabelIcon,
   Settings as SettingsIcon,
 } from '@mui/icons-material';
+import { getLaunchLinks, getLocationByQR } from '../utils/api';
+import type { StockAlert } from '../types';
 
 const drawerWidth = 240;
 
@@ -37,9 +42,41 @@ const menuItems = [
 
 const Layout: React.FC = () => {
   const [mobileOpen, setMobileOpen] = useState(false);
+  const [stockAlert, setStockAlert] = useState<StockAlert | null>(null);
   const navigate = useNavigate();
   const location = useLocation();
 
+  // Scanning an ics://loc/... label opens the inventory filtered to that location
+  useEffect(() => {
+    const openLinks = async (urls: string[]) => {
+      for (const url of urls) {
+        try {
+          const target = await getLocationByQR(url);
+          navigate(`/inventory?location=${target.id}`);
+        } catch (err) {
+          alert('无法打开链接: ' + err);
+        }
+      }
+    };
+
+    getLaunchLinks()
+      .then((urls) => urls && openLinks(urls))
+      .catch((err) => console.error('Failed to read launch link:', err));
+
+    const unlisten = listen<string[]>('deep-link://new-url', (event) => openLinks(event.payload));
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Items falling below their minimum while the app is open
+  useEffect(() => {
+    const unlisten = listen<StockAlert>('stock-low', (event) => setStockAlert(event.payload));
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   const handleDrawerToggle = () => {
     setMobileOpen(!mobileOpen);
   };
@@ -142,6 +179,17 @@ const Layout: React.FC = () => {
         <Toolbar />
         <Outlet />
       </Box>
+      <Snackbar
+        open={stockAlert !== null}
+        autoHideDuration={6000}
+        onClose={() => setStockAlert(null)}
+        anchorOrigin={{ vertical: 'bottom', horizontal: 'right' }}
+      >
+        <Alert severity="warning" onClose={() => setStockAlert(null)}>
+          {stockAlert &&
+            `库存不足: ${stockAlert.name} 剩余 ${stockAlert.quantity}${stockAlert.unit ?? ''}，最低库存 ${stockAlert.min_quantity}`}
+        </Alert>
+      </Snackbar>
     </Box>
   );
 };
@@ -12,9 +12,11 @@ import {
   MenuItem,
   Box,
   Stack,
+  Autocomplete,
+  Chip,
 } from '@mui/material';
-import type { Item, ItemInput, Location } from '../../types';
-import { getLocations } from '../../utils/api';
+import type { Item, ItemInput, Location, Manufacturer } from '../../types';
+import { getLocations, getManufacturers, getTags } from '../../utils/api';
 
 interface Props {
   open: boolean;
@@ -27,37 +29,52 @@ const ItemDialog: React.FC<Props> = ({ open, item, onClose, onSave }) => {
   const [name, setName] = React.useState('');
   const [category, setCategory] = React.useState('');
   const [specifications, setSpecifications] = React.useState('');
+  const [mpn, setMpn] = React.useState('');
+  const [manufacturerId, setManufacturerId] = React.useState<number | undefined>();
   const [quantity, setQuantity] = React.useState(0);
   const [unit, setUnit] = React.useState('');
   const [locationId, setLocationId] = React.useState<number | undefined>();
   const [minQuantity, setMinQuantity] = React.useState<number | undefined>();
   const [notes, setNotes] = React.useState('');
+  const [tags, setTags] = React.useState<string[]>([]);
+  const [tagOptions, setTagOptions] = React.useState<string[]>([]);
   const [locations, setLocations] = React.useState<Location[]>([]);
+  const [manufacturers, setManufacturers] = React.useState<Manufacturer[]>([]);
   const [saving, setSaving] = React.useState(false);
 
   useEffect(() => {
     if (open) {
       // Load locations
       getLocations().then(setLocations).catch(console.error);
+      getManufacturers().then(setManufacturers).catch(console.error);
+      getTags()
+        .then((all) => setTagOptions(all.map((tag) => tag.name)))
+        .catch(console.error);
 
       if (item) {
         setName(item.name);
         setCategory(item.category || '');
         setSpecifications(item.specifications || '');
+        setMpn(item.mpn || '');
+        setManufacturerId(item.manufacturer_id);
         setQuantity(item.quantity);
         setUnit(item.unit || '');
         setLocationId(item.location_id);
         setMinQuantity(item.min_quantity);
         setNotes(item.notes || '');
+        setTags(item.tags);
       } else {
         setName('');
         setCategory('');
         setSpecifications('');
+        setMpn('');
+        setManufacturerId(undefined);
         setQuantity(0);
         setUnit('');
         setLocationId(undefined);
         setMinQuantity(undefined);
         setNotes('');
+        setTags([]);
       }
     }
   }, [item, open]);
@@ -76,6 +93,9 @@ const ItemDialog: React.FC<Props> = ({ open, item, onClose, onSave }) => {
         location_id: locationId,
         min_quantity: minQuantity,
         notes: notes.trim() || undefined,
+        tags,
+        mpn: mpn.trim() || undefined,
+        manufacturer_id: manufacturerId,
       };
       await onSave(input);
       handleClose();
@@ -125,6 +145,35 @@ const ItemDialog: React.FC<Props> = ({ open, item, onClose, onSave }) => {
               disabled={saving}
               placeholder="如：5.1kΩ 1/4W"
             />
+            <Stack direction={{ xs: 'column', sm: 'row' }} spacing={2}>
+              <TextField
+                fullWidth
+                label="制造商料号 (MPN)"
+                value={mpn}
+                onChange={(e) => setMpn(e.target.value)}
+                disabled={saving}
+                placeholder="如：NE555DR"
+              />
+              <FormControl fullWidth>
+                <InputLabel>制造商</InputLabel>
+                <Select<number | string>
+                  value={manufacturerId ?? ''}
+                  label="制造商"
+                  onChange={(e) => {
+                    const value = e.target.value;
+                    setManufacturerId(value === '' ? undefined : Number(value));
+                  }}
+                  disabled={saving}
+                >
+                  <MenuItem value="">未指定</MenuItem>
+                  {manufacturers.map((manufacturer) => (
+                    <MenuItem key={manufacturer.id} value={manufacturer.id}>
+                      {manufacturer.name}
+                    </MenuItem>
+                  ))}
+                </Select>
+              </FormControl>
+            </Stack>
             <Stack direction={{ xs: 'column', sm: 'row' }} spacing={2}>
               <TextField
                 fullWidth
@@ -170,6 +219,22 @@ const ItemDialog: React.FC<Props> = ({ open, item, onClose, onSave }) => {
                 ))}
               </Select>
             </FormControl>
+            <Autocomplete
+              multiple
+              freeSolo
+              options={tagOptions}
+              value={tags}
+              onChange={(_, value) => setTags(value)}
+              disabled={saving}
+              renderTags={(value, getTagProps) =>
+                value.map((tag, index) => (
+                  <Chip {...getTagProps({ index })} key={tag} label={tag} size="small" />
+                ))
+              }
+              renderInput={(params) => (
+                <TextField {...params} label="标签" placeholder="输入后回车添加" />
+              )}
+            />
             <TextField
               fullWidth
               label="备注"
@@ -85,6 +85,9 @@ const ItemList: React.FC<ItemListProps> = ({ items, onEdit, onDelete }) => {
                     sx={{ mb: 1 }}
                   />
                 )}
+                {item.tags.map((tag) => (
+                  <Chip key={tag} label={tag} size="small" variant="outlined" sx={{ mb: 1, ml: 0.5 }} />
+                ))}
                 {item.specifications && (
                   <Typography variant="body2" color="textSecondary" gutterBottom>
                     规格: {item.specifications}
@@ -32,6 +32,7 @@ const LocationDialog: React.FC<Props> = ({
   const [name, setName] = React.useState('');
   const [locationType, setLocationType] = React.useState<'shelf' | 'box' | 'compartment'>('box');
   const [description, setDescription] = React.useState('');
+  const [reservedCode, setReservedCode] = React.useState('');
   const [saving, setSaving] = React.useState(false);
 
   useEffect(() => {
@@ -44,6 +45,7 @@ const LocationDialog: React.FC<Props> = ({
       setLocationType('box');
       setDescription('');
     }
+    setReservedCode('');
   }, [location, open]);
 
   const handleSave = async () => {
@@ -57,6 +59,7 @@ const LocationDialog: React.FC<Props> = ({
         name: name.trim(),
         location_type: locationType,
         description: description.trim() || undefined,
+        reserved_code: reservedCode.trim() || undefined,
       };
 
       // Only add parent_id if it's a valid positive number
@@ -118,6 +121,17 @@ const LocationDialog: React.FC<Props> = ({
             onChange={(e) => setDescription(e.target.value)}
             disabled={saving}
           />
+          {!location && (
+            <TextField
+              fullWidth
+              label="预印二维码（可选）"
+              helperText="扫描或输入已贴好的空白二维码，留空则生成新二维码"
+              value={reservedCode}
+              onChange={(e) => setReservedCode(e.target.value)}
+              sx={{ mt: 2 }}
+              disabled={saving}
+            />
+          )}
         </Box>
       </DialogContent>
       <DialogActions>
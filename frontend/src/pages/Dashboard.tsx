@@ -1,22 +1,46 @@
+import { useEffect, useState } from 'react';
 import {
+  Alert,
   Box,
   Paper,
   Typography,
   Card,
   CardContent,
+  List,
+  ListItem,
+  ListItemText,
 } from '@mui/material';
 import {
   LocationOn as LocationIcon,
   Inventory as InventoryIcon,
   Warning as WarningIcon,
 } from '@mui/icons-material';
+import { getDashboardStats } from '../utils/api';
+import type { DashboardStats } from '../types';
 
 const Dashboard = () => {
+  const [stats, setStats] = useState<DashboardStats | null>(null);
+  const [error, setError] = useState<string>('');
+
+  useEffect(() => {
+    getDashboardStats()
+      .then(setStats)
+      .catch((err) => setError('加载统计失败: ' + err));
+  }, []);
+
+  const added = stats?.movements.reduce((sum, point) => sum + point.added, 0) ?? 0;
+  const removed = stats?.movements.reduce((sum, point) => sum + point.removed, 0) ?? 0;
+
   return (
     <Box>
       <Typography variant="h4" gutterBottom>
         仪表盘
       </Typography>
+      {error && (
+        <Alert severity="error" sx={{ mb: 2 }} onClose={() => setError('')}>
+          {error}
+        </Alert>
+      )}
       <Box
         sx={{
           display: 'grid',
@@ -37,7 +61,7 @@ const Dashboard = () => {
                 <Typography color="textSecondary" gutterBottom variant="body2">
                   总位置数
                 </Typography>
-                <Typography variant="h3">0</Typography>
+                <Typography variant="h3">{stats?.total_locations ?? 0}</Typography>
               </Box>
             </Box>
           </CardContent>
@@ -50,7 +74,10 @@ const Dashboard = () => {
                 <Typography color="textSecondary" gutterBottom variant="body2">
                   总物品数
                 </Typography>
-                <Typography variant="h3">0</Typography>
+                <Typography variant="h3">{stats?.total_items ?? 0}</Typography>
+                <Typography variant="body2" color="textSecondary">
+                  共 {stats?.total_units ?? 0} 件
+                </Typography>
               </Box>
             </Box>
           </CardContent>
@@ -63,20 +90,60 @@ const Dashboard = () => {
                 <Typography color="textSecondary" gutterBottom variant="body2">
                   库存预警
                 </Typography>
-                <Typography variant="h3">0</Typography>
+                <Typography variant="h3">{stats?.low_stock_count ?? 0}</Typography>
               </Box>
             </Box>
           </CardContent>
         </Card>
       </Box>
-      <Box>
+      <Box
+        sx={{
+          display: 'grid',
+          gridTemplateColumns: { xs: 'repeat(1, 1fr)', md: 'repeat(3, 1fr)' },
+          gap: 3,
+        }}
+      >
+        <Paper sx={{ p: 2 }}>
+          <Typography variant="h6" gutterBottom>
+            分类
+          </Typography>
+          <List dense>
+            {stats?.categories.map((category) => (
+              <ListItem key={category.category ?? ''}>
+                <ListItemText
+                  primary={category.category || '未分类'}
+                  secondary={`${category.item_count} 种，共 ${category.total_units} 件`}
+                />
+              </ListItem>
+            ))}
+          </List>
+        </Paper>
         <Paper sx={{ p: 2 }}>
           <Typography variant="h6" gutterBottom>
-            欢迎使用物品分类管理系统
+            最近变动
           </Typography>
-          <Typography variant="body2" color="textSecondary">
-            系统正在初始化中，请从左侧菜单选择功能开始使用。
+          <Typography variant="body2" color="textSecondary" gutterBottom>
+            近 {stats?.movements.length ?? 0} 天入库 {added} 件，出库 {removed} 件
+          </Typography>
+          <List dense>
+            {stats?.recent_items.map((item) => (
+              <ListItem key={item.id}>
+                <ListItemText primary={item.name} secondary={item.updated_at} />
+              </ListItem>
+            ))}
+          </List>
+        </Paper>
+        <Paper sx={{ p: 2 }}>
+          <Typography variant="h6" gutterBottom>
+            空位置
           </Typography>
+          <List dense>
+            {stats?.empty_locations.map((location) => (
+              <ListItem key={location.location_id}>
+                <ListItemText primary={location.name} secondary={location.path} />
+              </ListItem>
+            ))}
+          </List>
         </Paper>
       </Box>
     </Box>
@@ -1,4 +1,5 @@
 import { useState, useEffect } from 'react';
+import { useSearchParams } from 'react-router-dom';
 import {
   Box,
   Typography,
@@ -25,8 +26,9 @@ import {
   Remove as RemoveIcon,
   Refresh as RefreshIcon,
   QrCodeScanner as ScanIcon,
+  SwapHoriz as TransferIcon,
 } from '@mui/icons-material';
-import { getItems, updateQuantity, getLocations, getLocationByQR } from '../utils/api';
+import { getItems, updateQuantity, getLocations, resolveQrCode, transferStock } from '../utils/api';
 import QRCodeScanner from '../components/QRCodeScanner';
 import type { Item, Location } from '../types';
 
@@ -38,10 +40,15 @@ const Inventory = () => {
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string>('');
   const [selectedItem, setSelectedItem] = useState<Item | null>(null);
-  const [operation, setOperation] = useState<'add' | 'remove'>('add');
+  const [operation, setOperation] = useState<'add' | 'remove' | 'transfer'>('add');
   const [quantity, setQuantity] = useState(1);
+  const [stockId, setStockId] = useState<number | ''>('');
+  const [targetLocation, setTargetLocation] = useState<number | ''>('');
   const [dialogOpen, setDialogOpen] = useState(false);
-  const [filterLocation, setFilterLocation] = useState<number | ''>('');
+  const [searchParams] = useSearchParams();
+  const [filterLocation, setFilterLocation] = useState<number | ''>(
+    searchParams.get('location') ? Number(searchParams.get('location')) : ''
+  );
   const [scannerOpen, setScannerOpen] = useState(false);
 
   const loadItems = async () => {
@@ -74,10 +81,20 @@ const Inventory = () => {
     loadLocations();
   }, [filterLocation]);
 
-  const handleOpenDialog = (item: Item, op: 'add' | 'remove') => {
+  // Opened from a scanned QR link while already on this page
+  useEffect(() => {
+    const location = searchParams.get('location');
+    if (location) setFilterLocation(Number(location));
+  }, [searchParams]);
+
+  const handleOpenDialog = (item: Item, op: 'add' | 'remove' | 'transfer') => {
     setSelectedItem(item);
     setOperation(op);
     setQuantity(1);
+    // Default to the stock in the filtered location
+    const stock = item.stock.find((s) => s.location_id === filterLocation) ?? item.stock[0];
+    setStockId(stock ? stock.id : '');
+    setTargetLocation('');
     setDialogOpen(true);
   };
 
@@ -91,9 +108,14 @@ const Inventory = () => {
     if (!selectedItem) return;
 
     try {
-      const change = operation === 'add' ? quantity : -quantity;
-
-      await updateQuantity(selectedItem.id, change, operation);
+      if (operation === 'transfer') {
+        const from = selectedItem.stock.find((s) => s.id === stockId);
+        if (!from) return;
+        await transferStock(selectedItem.id, from.location_id, targetLocation || undefined, quantity);
+      } else {
+        const change = operation === 'add' ? quantity : -quantity;
+        await updateQuantity(selectedItem.id, change, operation, stockId || undefined);
+      }
       await loadItems();
       handleCloseDialog();
     } catch (err) {
@@ -104,7 +126,7 @@ const Inventory = () => {
   const handleScanSuccess = async (qrCodeId: string) => {
     try {
       // Try to get location by QR code
-      const location = await getLocationByQR(qrCodeId);
+      const { location, warning } = await resolveQrCode(qrCodeId);
 
       // Filter items by this location
       const filter = { location_id: location.id };
@@ -112,7 +134,11 @@ const Inventory = () => {
       setItems(data);
       setFilterLocation(location.id);
 
-      alert(`已筛选位置: ${location.name}`);
+      alert(
+        warning
+          ? `已筛选位置: ${location.name}\n此二维码已停用，请重新打印该位置的标签`
+          : `已筛选位置: ${location.name}`
+      );
     } catch (err) {
       alert('未找到对应位置: ' + qrCodeId);
     }
@@ -202,6 +228,12 @@ const Inventory = () => {
                   <Typography variant="h5" color="primary" gutterBottom>
                     库存: {item.quantity} {item.unit || '个'}
                   </Typography>
+                  {item.stock.length > 1 &&
+                    item.stock.map((stock) => (
+                      <Typography key={stock.id} variant="body2" color="textSecondary">
+                        {stock.location_name || '未分配位置'}: {stock.quantity}
+                      </Typography>
+                    ))}
                   {item.min_quantity && item.quantity <= item.min_quantity && (
                     <Alert severity="warning" sx={{ mb: 2 }}>
                       库存不足！最小库存: {item.min_quantity}
@@ -227,6 +259,15 @@ const Inventory = () => {
                     >
                       出库
                     </Button>
+                    <Button
+                      fullWidth
+                      variant="outlined"
+                      startIcon={<TransferIcon />}
+                      onClick={() => handleOpenDialog(item, 'transfer')}
+                      disabled={item.quantity <= 0}
+                    >
+                      调拨
+                    </Button>
                   </Box>
                 </CardContent>
               </Card>
@@ -237,9 +278,43 @@ const Inventory = () => {
 
       <Dialog open={dialogOpen} onClose={handleCloseDialog} maxWidth="sm" fullWidth>
         <DialogTitle>
-          {operation === 'add' ? '入库' : '出库'} - {selectedItem?.name}
+          {{ add: '入库', remove: '出库', transfer: '调拨' }[operation]} - {selectedItem?.name}
         </DialogTitle>
         <DialogContent>
+          {selectedItem && (selectedItem.stock.length > 1 || operation === 'transfer') && (
+            <FormControl fullWidth sx={{ mt: 2 }}>
+              <InputLabel>{operation === 'transfer' ? '调出位置' : '位置'}</InputLabel>
+              <Select
+                value={stockId}
+                label={operation === 'transfer' ? '调出位置' : '位置'}
+                onChange={(e) => setStockId(e.target.value as number)}
+              >
+                {selectedItem.stock.map((stock) => (
+                  <MenuItem key={stock.id} value={stock.id}>
+                    {stock.location_name || '未分配位置'}（{stock.quantity}）
+                  </MenuItem>
+                ))}
+              </Select>
+            </FormControl>
+          )}
+          {operation === 'transfer' && (
+            <FormControl fullWidth sx={{ mt: 2 }}>
+              <InputLabel>调入位置</InputLabel>
+              <Select
+                value={targetLocation}
+                label="调入位置"
+                onChange={(e) => setTargetLocation(e.target.value as number)}
+              >
+                {locations
+                  .filter((loc) => loc.id !== selectedItem?.stock.find((s) => s.id === stockId)?.location_id)
+                  .map((loc) => (
+                    <MenuItem key={loc.id} value={loc.id}>
+                      {loc.name}
+                    </MenuItem>
+                  ))}
+              </Select>
+            </FormControl>
+          )}
           <TextField
             fullWidth
             label="数量"
@@ -252,7 +327,11 @@ const Inventory = () => {
         </DialogContent>
         <DialogActions>
           <Button onClick={handleCloseDialog}>取消</Button>
-          <Button onClick={handleSubmit} variant="contained">
+          <Button
+            onClick={handleSubmit}
+            variant="contained"
+            disabled={operation === 'transfer' && (stockId === '' || targetLocation === '')}
+          >
             确认
           </Button>
         </DialogActions>
@@ -153,23 +153,24 @@ const Labels = () => {
 
     try {
       console.log('Calling generateImageLabels with:', selectedIds, columns, rows);
-      const imageData = await generateImageLabels(selectedIds, columns, rows);
-      console.log('Image data received, length:', imageData.length);
-
-      // Remove the data URL prefix to get base64 string
-      const base64Data = imageData.split(',')[1];
-      console.log('Base64 data length:', base64Data?.length);
-
-      if (!base64Data) {
-        throw new Error('Invalid image data format');
-      }
-
-      const binaryString = atob(base64Data);
-      const bytes = new Uint8Array(binaryString.length);
-      for (let i = 0; i < binaryString.length; i++) {
-        bytes[i] = binaryString.charCodeAt(i);
-      }
-      console.log('Converted to bytes, length:', bytes.length);
+      const pages = await generateImageLabels(selectedIds, columns, rows);
+      console.log('Image pages received:', pages.length);
+
+      const pageBytes = pages.map((imageData) => {
+        // Remove the data URL prefix to get base64 string
+        const base64Data = imageData.split(',')[1];
+
+        if (!base64Data) {
+          throw new Error('Invalid image data format');
+        }
+
+        const binaryString = atob(base64Data);
+        const bytes = new Uint8Array(binaryString.length);
+        for (let i = 0; i < binaryString.length; i++) {
+          bytes[i] = binaryString.charCodeAt(i);
+        }
+        return bytes;
+      });
 
       // Open save dialog
       console.log('Opening save dialog...');
@@ -186,9 +187,15 @@ const Labels = () => {
       console.log('File path from dialog:', filePath);
 
       if (filePath) {
-        // Write the image file
-        await writeFile(filePath, bytes);
-        console.log('Image saved successfully to:', filePath);
+        // Write one file per page, numbering them when there is more than one
+        for (let i = 0; i < pageBytes.length; i++) {
+          const pagePath =
+            pageBytes.length === 1
+              ? filePath
+              : filePath.replace(/(\.png)?$/i, `_${i + 1}.png`);
+          await writeFile(pagePath, pageBytes[i]);
+          console.log('Image saved successfully to:', pagePath);
+        }
       } else {
         console.log('User cancelled save dialog');
       }
@@ -8,6 +8,7 @@ export interface Location {
   created_at: string;
   updated_at: string;
   children?: Location[];
+  tags: string[];
 }
 
 export interface Item {
@@ -24,6 +25,25 @@ export interface Item {
   created_at: string;
   updated_at: string;
   location?: Location;
+  // Where the item is stocked; quantity is the sum of these
+  stock: ItemStock[];
+  tags: string[];
+  // Set while the item is in the trash
+  deleted_at?: string;
+  // Manufacturer part number
+  mpn?: string;
+  manufacturer_id?: number;
+  manufacturer?: string;
+}
+
+export interface ItemStock {
+  id: number;
+  item_id: number;
+  item_name: string;
+  location_id?: number;
+  location_name?: string;
+  quantity: number;
+  updated_at: string;
 }
 
 export interface InventoryLog {
@@ -31,17 +51,156 @@ export interface InventoryLog {
   item_id: number;
   quantity_change: number;
   quantity_after: number;
-  operation_type: 'add' | 'remove' | 'adjust';
+  operation_type: 'add' | 'remove' | 'adjust' | 'stocktake' | 'transfer';
   source: string;
   notes?: string;
+  location_id?: number;
+  // Shared by both entries of a transfer between locations
+  transfer_id?: string;
   created_at: string;
 }
 
+export interface StockTransfer {
+  transfer_id: string;
+  item_id: number;
+  quantity: number;
+  from: ItemStock;
+  to: ItemStock;
+}
+
+export type StocktakeStatus = 'open' | 'committed' | 'abandoned';
+
+export interface Stocktake {
+  id: number;
+  location_id: number;
+  location_name: string;
+  include_sublocations: boolean;
+  status: StocktakeStatus;
+  notes?: string;
+  created_at: string;
+  finished_at?: string;
+  line_count: number;
+  counted_count: number;
+}
+
+export interface StocktakeLine {
+  stock_id: number;
+  item_id: number;
+  item_name: string;
+  unit?: string;
+  location_id?: number;
+  location_name?: string;
+  expected_quantity: number; // system quantity when the count started
+  system_quantity: number; // current system quantity
+  counted_quantity?: number;
+  variance?: number; // counted - system
+  counted_at?: string;
+}
+
+export interface StocktakeDetail {
+  stocktake: Stocktake;
+  lines: StocktakeLine[];
+}
+
+export interface StocktakeScan {
+  location: Location;
+  warning?: string;
+  lines: StocktakeLine[];
+}
+
+export interface LowStockItem {
+  item_id: number;
+  name: string;
+  category?: string;
+  specifications?: string;
+  unit?: string;
+  location_id?: number;
+  location_path?: string;
+  quantity: number;
+  min_quantity: number;
+  consumed: number; // units taken out during the report window
+  daily_consumption: number;
+  days_until_empty?: number; // unset when nothing has been consumed
+  suggested_reorder: number;
+}
+
+export interface LowStockGroup {
+  category?: string;
+  location_id?: number;
+  location_path?: string;
+  items: LowStockItem[];
+}
+
+export interface LowStockReport {
+  window_days: number;
+  cover_days: number;
+  item_count: number;
+  groups: LowStockGroup[];
+}
+
+export type AlertMode = 'immediate' | 'digest' | 'off';
+
+export interface StockAlertSettings {
+  mode: AlertMode;
+  digest_hour: number; // local hour from which the daily digest is sent
+}
+
+// Payload of the 'stock-low' event; 'stock-low-digest' carries a list of these
+export interface StockAlert {
+  item_id: number;
+  name: string;
+  quantity: number;
+  min_quantity: number;
+  unit?: string;
+}
+
+export interface CategoryStats {
+  category?: string;
+  item_count: number;
+  total_units: number;
+}
+
+// Items in a top-level location or anywhere below it
+export interface LocationStats {
+  location_id: number;
+  name: string;
+  item_count: number;
+  total_units: number;
+}
+
+export interface EmptyLocation {
+  location_id: number;
+  name: string;
+  path: string;
+}
+
+// Inventory movements on one day (UTC)
+export interface MovementPoint {
+  date: string;
+  added: number;
+  removed: number;
+  changes: number;
+}
+
+export interface DashboardStats {
+  total_locations: number;
+  total_items: number;
+  total_units: number;
+  categories: CategoryStats[];
+  top_locations: LocationStats[];
+  unassigned_items: number;
+  low_stock_count: number;
+  empty_locations: EmptyLocation[];
+  recent_items: Item[];
+  movements: MovementPoint[]; // one point per day, oldest first
+}
+
 export interface LocationInput {
   name: string;
   parent_id?: number;
   location_type: 'shelf' | 'box' | 'compartment';
   description?: string;
+  reserved_code?: string; // pre-printed code, as scanned or bare
 }
 
 export interface ItemInput {
@@ -54,12 +213,247 @@ export interface ItemInput {
   min_quantity?: number;
   notes?: string;
   image_path?: string;
+  // Replaces the item's tags when given
+  tags?: string[];
+  mpn?: string;
+  manufacturer_id?: number;
 }
 
 export interface ItemFilter {
   category?: string;
   location_id?: number;
   search?: string;
+  // List soft-deleted items instead of current ones
+  deleted?: boolean;
+  tags_any?: string[];
+  tags_all?: string[];
+  tags_none?: string[];
+}
+
+export interface Tag {
+  id: number;
+  name: string;
+  item_count: number;
+  location_count: number;
+  created_at: string;
+}
+
+export interface Project {
+  id: number;
+  name: string;
+  description?: string;
+  line_count: number;
+  created_at: string;
+  updated_at: string;
+}
+
+// How many of an item each unit of a project needs
+export interface BomLine {
+  id: number;
+  project_id: number;
+  item_id: number;
+  item_name: string;
+  unit?: string;
+  quantity_per_unit: number;
+  reference?: string;
+  notes?: string;
+}
+
+export interface BuildabilityLine {
+  item_id: number;
+  item_name: string;
+  required: number;
+  in_stock: number;
+  // Held for other builds
+  reserved: number;
+  available: number;
+  shortage: number;
+}
+
+export interface Buildability {
+  project_id: number;
+  quantity: number;
+  can_build: boolean;
+  max_buildable: number;
+  lines: BuildabilityLine[];
+}
+
+export type BuildStatus = 'reserved' | 'consumed' | 'cancelled';
+
+export interface ProjectBuild {
+  id: number;
+  project_id: number;
+  project_name: string;
+  quantity: number;
+  status: BuildStatus;
+  notes?: string;
+  created_at: string;
+  finished_at?: string;
+}
+
+export interface BulkItemResult {
+  item_id: number;
+  success: boolean;
+  error?: string;
+}
+
+export interface BulkAdjustment {
+  item_id: number;
+  change: number;
+  stock_id?: number;
+}
+
+export type BomMatchMethod = 'mpn' | 'supplier_part' | 'value_footprint' | 'fuzzy' | 'created';
+
+export interface BomMatchCandidate {
+  item_id: number;
+  item_name: string;
+  method: BomMatchMethod;
+  score: number;
+}
+
+export interface BomImportLine {
+  row: number;
+  reference?: string;
+  quantity: number;
+  value?: string;
+  footprint?: string;
+  mpn?: string;
+  manufacturer?: string;
+  supplier_part?: string;
+  supplier_part_column?: string;
+  description?: string;
+  item_id?: number;
+  item_name?: string;
+  match_method?: BomMatchMethod;
+  score: number;
+  candidates: BomMatchCandidate[];
+}
+
+export interface BomImportPreview {
+  format: 'kicad' | 'jlcpcb' | 'generic';
+  lines: BomImportLine[];
+  matched: number;
+  unmatched: number;
+}
+
+export interface Supplier {
+  id: number;
+  name: string;
+  website?: string;
+  notes?: string;
+  offer_count: number;
+  created_at: string;
+}
+
+export interface Manufacturer {
+  id: number;
+  name: string;
+  website?: string;
+  notes?: string;
+  item_count: number;
+  created_at: string;
+}
+
+export interface ItemOffer {
+  id: number;
+  item_id: number;
+  item_name: string;
+  supplier_id: number;
+  supplier_name: string;
+  sku?: string;
+  // Price of a single piece
+  unit_price?: number;
+  currency: string;
+  // Pieces per pack; orders come in multiples of it
+  pack_size: number;
+  url?: string;
+  // Used to value the item's stock
+  is_preferred: boolean;
+  notes?: string;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface ItemOfferInput {
+  item_id: number;
+  supplier_id: number;
+  sku?: string;
+  unit_price?: number;
+  // Defaults to CNY
+  currency?: string;
+  pack_size?: number;
+  url?: string;
+  is_preferred?: boolean;
+  notes?: string;
+}
+
+export interface PriceRecord {
+  id: number;
+  offer_id: number;
+  item_id: number;
+  supplier_name: string;
+  sku?: string;
+  unit_price: number;
+  currency: string;
+  recorded_at: string;
+}
+
+export interface ValuationGroup {
+  // Category or location path; missing for uncategorized items or unassigned stock
+  name?: string;
+  location_id?: number;
+  currency: string;
+  item_count: number;
+  total_units: number;
+  value: number;
+}
+
+export interface InventoryValuation {
+  // A single group for all stock, empty if nothing is priced
+  totals: ValuationGroup[];
+  by_category: ValuationGroup[];
+  by_location: ValuationGroup[];
+  // Stocked items without a priced offer in the currency, left out of the totals
+  unpriced_items: number;
+  unpriced_units: number;
+}
+
+export interface QrSettings {
+  instance_id: string;
+  signing_enabled: boolean;
+}
+
+export interface QrLookup {
+  location: Location;
+  retired: boolean; // the code was replaced and the label should be reprinted
+  warning?: string;
+}
+
+export interface RetiredCode {
+  code: string;
+  location_id: number;
+  reason?: string;
+  retired_at: string;
+}
+
+export interface ReservedCode {
+  code: string;
+  batch_id: string;
+  location_id?: number;
+  created_at: string;
+  used_at?: string;
+}
+
+export interface ReservedCodeSheet {
+  batch_id: string;
+  codes: string[];
+  pdf: string; // data URL
+}
+
+export interface CodeAssignment {
+  location_id: number;
+  code: string; // scanned payload or bare code
 }
 
 export interface QRCodeResult {
@@ -68,6 +462,151 @@ export interface QRCodeResult {
   name: string;
 }
 
+// A QR code or barcode found in a photo, resolved to a location if it is one of ours
+export interface DecodedCode {
+  text: string;
+  format: string; // e.g. 'qrcode', 'code 128', 'datamatrix'
+  points: [number, number][];
+  location?: Location;
+  warning?: string; // set for retired codes
+}
+
+export interface ImageScanResult {
+  source: string;
+  codes: DecodedCode[];
+  error?: string;
+}
+
+export type QrErrorCorrection = 'L' | 'M' | 'Q' | 'H';
+
+export type QrOutputFormat = 'png' | 'svg';
+
+export interface QrOptions {
+  error_correction?: QrErrorCorrection;
+  quiet_zone?: number;
+  module_size?: number;
+  format?: QrOutputFormat;
+  foreground?: string;
+  background?: string;
+}
+
+export type LabelField =
+  | 'name'
+  | 'category'
+  | 'specifications'
+  | 'quantity'
+  | 'location'
+  | 'notes'
+  | 'location_type'
+  | 'path'
+  | 'contents';
+
+export type Symbology = 'qr' | 'data_matrix' | 'code128' | 'ean13';
+
+export type LabelKind = 'item' | 'location';
+
+// Text wraps to `width`, shrinking down to `min_font_size` to fit in `max_lines`
+export interface LabelTextBox {
+  width?: number;
+  max_lines?: number;
+  min_font_size?: number;
+}
+
+// Positions and sizes in mm from the label's top-left corner, font sizes in pt
+export type LabelElement =
+  | ({
+      type: 'field';
+      field: LabelField;
+      x: number;
+      y: number;
+      font_size: number;
+      label?: string;
+      max_chars?: number;
+    } & LabelTextBox)
+  | ({
+      type: 'text';
+      text: string;
+      x: number;
+      y: number;
+      font_size: number;
+    } & LabelTextBox)
+  | { type: 'qr'; x: number; y: number; size: number }
+  // Encodes `field` if set, otherwise the same code as the QR code
+  | {
+      type: 'barcode';
+      symbology: Symbology;
+      field?: LabelField;
+      x: number;
+      y: number;
+      width: number;
+      height: number;
+    };
+
+export interface LabelLayout {
+  width: number;
+  height: number;
+  elements: LabelElement[];
+}
+
+export interface LabelTemplate {
+  id: number;
+  name: string;
+  description?: string;
+  layout: LabelLayout;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface LabelTemplateInput {
+  name: string;
+  description?: string;
+  layout: LabelLayout;
+}
+
+// Sizes in mm; the gutter between labels is the pitch minus the label size
+export interface LabelStock {
+  id: string;
+  name: string;
+  page_width: number;
+  page_height: number;
+  margin_left: number;
+  margin_top: number;
+  label_width: number;
+  label_height: number;
+  horizontal_pitch: number;
+  vertical_pitch: number;
+  columns: number;
+  rows: number;
+  outline: boolean;
+}
+
+export type PrinterLanguage = 'zpl' | 'tspl' | 'esc_pos';
+
+// Labels are sent as bitmaps at the printer's resolution
+export interface PrinterOptions {
+  language?: PrinterLanguage;
+  dpi?: number;
+  copies?: number;
+  gap?: number; // mm, TSPL only
+}
+
+export type PrinterTarget =
+  | { type: 'tcp'; host: string; port?: number } // port defaults to 9100
+  | { type: 'device'; path: string };
+
+export interface FontSetting {
+  path: string;
+  face_index: number;
+}
+
+export interface FontInfo {
+  path: string;
+  face_index: number;
+  family: string;
+  full_name: string;
+  supports_cjk: boolean;
+}
+
 export interface SyncConfig {
   id: number;
   sync_type: 'webdav' | 's3';
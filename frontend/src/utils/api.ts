@@ -2,10 +2,54 @@ import { invoke } from '@tauri-apps/api/core';
 import type {
   Location,
   Item,
+  ItemStock,
+  StockTransfer,
+  BulkAdjustment,
+  BulkItemResult,
+  Tag,
+  Project,
+  BomLine,
+  Buildability,
+  BuildStatus,
+  ProjectBuild,
+  BomImportLine,
+  BomImportPreview,
+  Supplier,
+  Manufacturer,
+  ItemOffer,
+  ItemOfferInput,
+  PriceRecord,
+  InventoryValuation,
   LocationInput,
   ItemInput,
   ItemFilter,
+  LowStockReport,
+  DashboardStats,
+  StockAlertSettings,
+  Stocktake,
+  StocktakeDetail,
+  StocktakeLine,
+  StocktakeScan,
+  StocktakeStatus,
   QRCodeResult,
+  QrOptions,
+  LabelLayout,
+  LabelKind,
+  LabelTemplate,
+  LabelTemplateInput,
+  LabelStock,
+  FontSetting,
+  QrSettings,
+  QrLookup,
+  RetiredCode,
+  CodeAssignment,
+  ReservedCode,
+  ReservedCodeSheet,
+  DecodedCode,
+  ImageScanResult,
+  PrinterOptions,
+  PrinterTarget,
+  FontInfo,
   SyncResult,
 } from '../types';
 
@@ -34,6 +78,11 @@ export const getLocationByQR = async (qrCodeId: string): Promise<Location> => {
   return invoke<Location>('get_location_by_qr', { qrCodeId });
 };
 
+// Like getLocationByQR, but also reports retired codes
+export const resolveQrCode = async (payload: string): Promise<QrLookup> => {
+  return invoke<QrLookup>('resolve_qr_code', { payload });
+};
+
 // Item APIs
 export const getItems = async (filter?: ItemFilter): Promise<Item[]> => {
   return invoke<Item[]>('get_items', { filter });
@@ -51,27 +100,354 @@ export const deleteItem = async (id: number): Promise<void> => {
   return invoke<void>('delete_item', { id });
 };
 
+// stockId picks the location to change; only needed when the item is stocked in several
 export const updateQuantity = async (
   itemId: number,
   change: number,
-  operationType: string
+  operationType: string,
+  stockId?: number
 ): Promise<void> => {
   return invoke<void>('update_quantity', {
     itemId,
     change,
     operationType,
+    stockId,
   });
 };
 
+export const getItemStock = async (itemId: number): Promise<ItemStock[]> => {
+  return invoke<ItemStock[]>('get_item_stock', { itemId });
+};
+
+export const getLocationStock = async (locationId: number): Promise<ItemStock[]> => {
+  return invoke<ItemStock[]>('get_location_stock', { locationId });
+};
+
+// Returns the stock ID; adds to the existing stock if the item is already in the location
+export const addItemStock = async (
+  itemId: number,
+  locationId?: number,
+  quantity?: number
+): Promise<number> => {
+  return invoke<number>('add_item_stock', { itemId, locationId, quantity });
+};
+
+// Only empty stock can be removed
+export const deleteItemStock = async (stockId: number): Promise<void> => {
+  return invoke<void>('delete_item_stock', { stockId });
+};
+
+// Leave a location ID out for unassigned stock
+export const transferStock = async (
+  itemId: number,
+  fromLocationId: number | undefined,
+  toLocationId: number | undefined,
+  quantity: number,
+  notes?: string
+): Promise<StockTransfer> => {
+  return invoke<StockTransfer>('transfer_stock', { itemId, fromLocationId, toLocationId, quantity, notes });
+};
+
+// Bulk item APIs; each runs in one transaction and reports success per item
+export const bulkMoveItems = async (itemIds: number[], locationId?: number, notes?: string): Promise<BulkItemResult[]> => {
+  return invoke<BulkItemResult[]>('bulk_move_items', { itemIds, locationId, notes });
+};
+
+export const bulkSetCategory = async (itemIds: number[], category?: string): Promise<BulkItemResult[]> => {
+  return invoke<BulkItemResult[]>('bulk_set_category', { itemIds, category });
+};
+
+export const bulkAdjustQuantities = async (
+  adjustments: BulkAdjustment[],
+  operationType: string
+): Promise<BulkItemResult[]> => {
+  return invoke<BulkItemResult[]>('bulk_adjust_quantities', { adjustments, operationType });
+};
+
+// Soft delete: items go to the trash and can be restored
+export const bulkDeleteItems = async (itemIds: number[]): Promise<BulkItemResult[]> => {
+  return invoke<BulkItemResult[]>('bulk_delete_items', { itemIds });
+};
+
+export const bulkRestoreItems = async (itemIds: number[]): Promise<BulkItemResult[]> => {
+  return invoke<BulkItemResult[]>('bulk_restore_items', { itemIds });
+};
+
+// Missing tags are created
+export const bulkTagItems = async (itemIds: number[], tags: string[]): Promise<BulkItemResult[]> => {
+  return invoke<BulkItemResult[]>('bulk_tag_items', { itemIds, tags });
+};
+
+// Tag APIs; tag names are matched case-insensitively
+export const getTags = async (): Promise<Tag[]> => {
+  return invoke<Tag[]>('get_tags');
+};
+
+export const createTag = async (name: string): Promise<number> => {
+  return invoke<number>('create_tag', { name });
+};
+
+// Fails if another tag has the name; merge them instead
+export const renameTag = async (id: number, name: string): Promise<void> => {
+  return invoke<void>('rename_tag', { id, name });
+};
+
+// Moves everything tagged with the sources to the target and deletes the sources
+export const mergeTags = async (sourceIds: number[], targetId: number): Promise<void> => {
+  return invoke<void>('merge_tags', { sourceIds, targetId });
+};
+
+export const deleteTag = async (id: number): Promise<void> => {
+  return invoke<void>('delete_tag', { id });
+};
+
+export const setItemTags = async (itemId: number, tags: string[]): Promise<void> => {
+  return invoke<void>('set_item_tags', { itemId, tags });
+};
+
+export const setLocationTags = async (locationId: number, tags: string[]): Promise<void> => {
+  return invoke<void>('set_location_tags', { locationId, tags });
+};
+
+// Project APIs
+export const getProjects = async (): Promise<Project[]> => {
+  return invoke<Project[]>('get_projects');
+};
+
+export const createProject = async (name: string, description?: string): Promise<number> => {
+  return invoke<number>('create_project', { name, description });
+};
+
+export const updateProject = async (id: number, name: string, description?: string): Promise<void> => {
+  return invoke<void>('update_project', { id, name, description });
+};
+
+export const deleteProject = async (id: number): Promise<void> => {
+  return invoke<void>('delete_project', { id });
+};
+
+export const getProjectBom = async (projectId: number): Promise<BomLine[]> => {
+  return invoke<BomLine[]>('get_project_bom', { projectId });
+};
+
+// Replaces the line if the item is already on the BOM
+export const setBomLine = async (
+  projectId: number,
+  itemId: number,
+  quantityPerUnit: number,
+  reference?: string,
+  notes?: string
+): Promise<number> => {
+  return invoke<number>('set_bom_line', { projectId, itemId, quantityPerUnit, reference, notes });
+};
+
+export const deleteBomLine = async (id: number): Promise<void> => {
+  return invoke<void>('delete_bom_line', { id });
+};
+
+export const checkBuildability = async (projectId: number, quantity?: number): Promise<Buildability> => {
+  return invoke<Buildability>('check_buildability', { projectId, quantity });
+};
+
+// Fails if any part is short; returns the build ID
+export const reserveBuild = async (projectId: number, quantity: number, notes?: string): Promise<number> => {
+  return invoke<number>('reserve_build', { projectId, quantity, notes });
+};
+
+export const getProjectBuilds = async (projectId?: number, status?: BuildStatus): Promise<ProjectBuild[]> => {
+  return invoke<ProjectBuild[]>('get_project_builds', { projectId, status });
+};
+
+export const cancelBuild = async (id: number): Promise<void> => {
+  return invoke<void>('cancel_build', { id });
+};
+
+// Deducts the reserved parts from stock
+export const consumeBuild = async (id: number): Promise<void> => {
+  return invoke<void>('consume_build', { id });
+};
+
+// BOM import APIs (KiCad / JLCPCB CSV)
+export const previewBomImport = async (content: string): Promise<BomImportPreview> => {
+  return invoke<BomImportPreview>('preview_bom_import', { content });
+};
+
+// Creates items for lines without one; returns the lines with the new items filled in.
+// With supplierId, supplier part numbers become offers from that supplier.
+export const createItemsFromBom = async (
+  lines: BomImportLine[],
+  category?: string,
+  locationId?: number,
+  supplierId?: number
+): Promise<BomImportLine[]> => {
+  return invoke<BomImportLine[]>('create_items_from_bom', { lines, category, locationId, supplierId });
+};
+
+// Returns the number of BOM lines written
+export const applyBomImport = async (projectId: number, lines: BomImportLine[], replace?: boolean): Promise<number> => {
+  return invoke<number>('apply_bom_import', { projectId, lines, replace });
+};
+
+// Supplier and manufacturer APIs
+export const getSuppliers = async (): Promise<Supplier[]> => {
+  return invoke<Supplier[]>('get_suppliers');
+};
+
+export const createSupplier = async (name: string, website?: string, notes?: string): Promise<number> => {
+  return invoke<number>('create_supplier', { name, website, notes });
+};
+
+export const updateSupplier = async (id: number, name: string, website?: string, notes?: string): Promise<void> => {
+  return invoke<void>('update_supplier', { id, name, website, notes });
+};
+
+// Also deletes the supplier's offers
+export const deleteSupplier = async (id: number): Promise<void> => {
+  return invoke<void>('delete_supplier', { id });
+};
+
+export const getManufacturers = async (): Promise<Manufacturer[]> => {
+  return invoke<Manufacturer[]>('get_manufacturers');
+};
+
+export const createManufacturer = async (name: string, website?: string, notes?: string): Promise<number> => {
+  return invoke<number>('create_manufacturer', { name, website, notes });
+};
+
+export const updateManufacturer = async (id: number, name: string, website?: string, notes?: string): Promise<void> => {
+  return invoke<void>('update_manufacturer', { id, name, website, notes });
+};
+
+export const deleteManufacturer = async (id: number): Promise<void> => {
+  return invoke<void>('delete_manufacturer', { id });
+};
+
+export const getItemOffers = async (itemId?: number, supplierId?: number): Promise<ItemOffer[]> => {
+  return invoke<ItemOffer[]>('get_item_offers', { itemId, supplierId });
+};
+
+export const createItemOffer = async (offer: ItemOfferInput): Promise<number> => {
+  return invoke<number>('create_item_offer', { offer });
+};
+
+// A changed price is added to the price history
+export const updateItemOffer = async (id: number, offer: ItemOfferInput): Promise<void> => {
+  return invoke<void>('update_item_offer', { id, offer });
+};
+
+export const deleteItemOffer = async (id: number): Promise<void> => {
+  return invoke<void>('delete_item_offer', { id });
+};
+
+export const getPriceHistory = async (itemId?: number, offerId?: number): Promise<PriceRecord[]> => {
+  return invoke<PriceRecord[]>('get_price_history', { itemId, offerId });
+};
+
+// Stocktake APIs
+export const startStocktake = async (
+  locationId: number,
+  includeSublocations?: boolean,
+  notes?: string
+): Promise<number> => {
+  return invoke<number>('start_stocktake', { locationId, includeSublocations, notes });
+};
+
+export const getStocktakes = async (status?: StocktakeStatus): Promise<Stocktake[]> => {
+  return invoke<Stocktake[]>('get_stocktakes', { status });
+};
+
+export const getStocktake = async (id: number): Promise<StocktakeDetail> => {
+  return invoke<StocktakeDetail>('get_stocktake', { id });
+};
+
+// Lists the items to count in the scanned location
+export const scanStocktakeLocation = async (stocktakeId: number, payload: string): Promise<StocktakeScan> => {
+  return invoke<StocktakeScan>('scan_stocktake_location', { stocktakeId, payload });
+};
+
+// With increment, quantity is added to the count so far (e.g. 1 per scanned unit).
+// locationId is only needed when the item is stocked in several counted locations.
+export const recordStocktakeCount = async (
+  stocktakeId: number,
+  itemId: number,
+  quantity: number,
+  increment?: boolean,
+  locationId?: number
+): Promise<StocktakeLine> => {
+  return invoke<StocktakeLine>('record_stocktake_count', { stocktakeId, itemId, locationId, quantity, increment });
+};
+
+// Without locationId, the count is cleared in every location
+export const clearStocktakeCount = async (
+  stocktakeId: number,
+  itemId: number,
+  locationId?: number
+): Promise<void> => {
+  return invoke<void>('clear_stocktake_count', { stocktakeId, itemId, locationId });
+};
+
+// Returns the number of stock rows whose quantity changed
+export const commitStocktake = async (id: number): Promise<number> => {
+  return invoke<number>('commit_stocktake', { id });
+};
+
+export const abandonStocktake = async (id: number, notes?: string): Promise<void> => {
+  return invoke<void>('abandon_stocktake', { id, notes });
+};
+
+// Report APIs
+// Consumption is averaged over windowDays (default 90); reorders cover coverDays (default 30)
+export const getLowStockReport = async (windowDays?: number, coverDays?: number): Promise<LowStockReport> => {
+  return invoke<LowStockReport>('get_low_stock_report', { windowDays, coverDays });
+};
+
+// Movements cover the last `days` days (default 30)
+export const getDashboardStats = async (days?: number, recentLimit?: number): Promise<DashboardStats> => {
+  return invoke<DashboardStats>('get_dashboard_stats', { days, recentLimit });
+};
+
+// Stock valued at each item's preferred or cheapest offer, per currency
+export const getInventoryValuation = async (currency?: string): Promise<InventoryValuation> => {
+  return invoke<InventoryValuation>('get_inventory_valuation', { currency });
+};
+
+// Stock alert APIs
+export const getStockAlertSettings = async (): Promise<StockAlertSettings> => {
+  return invoke<StockAlertSettings>('get_stock_alert_settings');
+};
+
+export const setStockAlertSettings = async (settings: StockAlertSettings): Promise<void> => {
+  return invoke<void>('set_stock_alert_settings', { settings });
+};
+
+export const setItemAlertsMuted = async (itemId: number, muted: boolean): Promise<void> => {
+  return invoke<void>('set_item_alerts_muted', { itemId, muted });
+};
+
+export const getMutedItems = async (): Promise<number[]> => {
+  return invoke<number[]>('get_muted_items');
+};
+
 // QR Code APIs
-export const generateLocationQR = async (locationId: number): Promise<string> => {
-  return invoke<string>('generate_location_qr', { locationId });
+export const generateLocationQR = async (
+  locationId: number,
+  options?: QrOptions
+): Promise<string> => {
+  return invoke<string>('generate_location_qr', { locationId, options });
+};
+
+export const exportLocationQRSvg = async (
+  locationId: number,
+  options?: QrOptions
+): Promise<string> => {
+  return invoke<string>('export_location_qr_svg', { locationId, options });
 };
 
 export const generateBatchQR = async (
-  locationIds: number[]
+  locationIds: number[],
+  options?: QrOptions
 ): Promise<QRCodeResult[]> => {
-  return invoke<QRCodeResult[]>('generate_batch_qr', { locationIds });
+  return invoke<QRCodeResult[]>('generate_batch_qr', { locationIds, options });
 };
 
 // PDF APIs
@@ -79,29 +455,214 @@ export const generatePdfLabels = async (
   itemIds: number[],
   paperSize: string,
   columns: number,
-  rows: number
+  rows: number,
+  templateId?: number,
+  qrOptions?: QrOptions,
+  stock?: LabelStock,
+  startOffset?: number,
+  locationIds?: number[],
+  includeSublocations?: boolean
 ): Promise<string> => {
   return invoke<string>('generate_pdf_labels', {
     itemIds,
+    locationIds,
+    includeSublocations,
     paperSize,
     columns,
     rows,
+    templateId,
+    qrOptions,
+    stock,
+    startOffset,
   });
 };
 
-// Image APIs
+// Image APIs (one PNG data URL per page)
 export const generateImageLabels = async (
   itemIds: number[],
   columns: number,
-  rows: number
-): Promise<string> => {
-  return invoke<string>('generate_image_labels', {
+  rows: number,
+  templateId?: number,
+  qrOptions?: QrOptions,
+  dpi?: number
+): Promise<string[]> => {
+  return invoke<string[]>('generate_image_labels', {
     itemIds,
     columns,
     rows,
+    templateId,
+    qrOptions,
+    dpi,
+  });
+};
+
+// Label template APIs
+export const getLabelTemplates = async (): Promise<LabelTemplate[]> => {
+  return invoke<LabelTemplate[]>('get_label_templates');
+};
+
+export const getDefaultLabelLayout = async (kind?: LabelKind): Promise<LabelLayout> => {
+  return invoke<LabelLayout>('get_default_label_layout', { kind });
+};
+
+export const getLabelStocks = async (): Promise<LabelStock[]> => {
+  return invoke<LabelStock[]>('get_label_stocks');
+};
+
+export const createLabelTemplate = async (
+  input: LabelTemplateInput
+): Promise<number> => {
+  return invoke<number>('create_label_template', { input });
+};
+
+export const updateLabelTemplate = async (
+  id: number,
+  input: LabelTemplateInput
+): Promise<void> => {
+  return invoke<void>('update_label_template', { id, input });
+};
+
+export const deleteLabelTemplate = async (id: number): Promise<void> => {
+  return invoke<void>('delete_label_template', { id });
+};
+
+export const previewLabelTemplate = async (
+  layout: LabelLayout,
+  itemId?: number,
+  qrOptions?: QrOptions
+): Promise<string> => {
+  return invoke<string>('preview_label_template', { layout, itemId, qrOptions });
+};
+
+// Raw printer APIs (ZPL / TSPL / ESC/POS)
+export const generatePrinterLabels = async (
+  itemIds: number[],
+  locationIds?: number[],
+  includeSublocations?: boolean,
+  templateId?: number,
+  qrOptions?: QrOptions,
+  options?: PrinterOptions
+): Promise<string> => {
+  return invoke<string>('generate_printer_labels', {
+    itemIds,
+    locationIds,
+    includeSublocations,
+    templateId,
+    qrOptions,
+    options,
   });
 };
 
+// Returns the number of bytes sent
+export const printLabels = async (
+  itemIds: number[],
+  target: PrinterTarget,
+  locationIds?: number[],
+  includeSublocations?: boolean,
+  templateId?: number,
+  qrOptions?: QrOptions,
+  options?: PrinterOptions
+): Promise<number> => {
+  return invoke<number>('print_labels', {
+    itemIds,
+    locationIds,
+    includeSublocations,
+    templateId,
+    qrOptions,
+    options,
+    target,
+  });
+};
+
+export const listFonts = async (): Promise<FontInfo[]> => {
+  return invoke<FontInfo[]>('list_fonts');
+};
+
+export const getLabelFont = async (): Promise<FontSetting | null> => {
+  return invoke<FontSetting | null>('get_label_font');
+};
+
+// Pass null to detect a system font automatically
+export const setLabelFont = async (font: FontSetting | null): Promise<void> => {
+  return invoke<void>('set_label_font', { font });
+};
+
+// QR link settings
+export const getQrSettings = async (): Promise<QrSettings> => {
+  return invoke<QrSettings>('get_qr_settings');
+};
+
+// Labels printed before changing this have to be reprinted
+export const setQrSigning = async (enabled: boolean): Promise<void> => {
+  return invoke<void>('set_qr_signing', { enabled });
+};
+
+// Issue a new code for a location; the old one keeps resolving as a retired code
+export const regenerateLocationQR = async (locationId: number, reason?: string): Promise<string> => {
+  return invoke<string>('regenerate_location_qr', { locationId, reason });
+};
+
+// `code` is a scanned payload or bare code from a pre-printed label
+export const assignLocationQR = async (
+  locationId: number,
+  code: string,
+  reason?: string
+): Promise<string> => {
+  return invoke<string>('assign_location_qr', { locationId, code, reason });
+};
+
+// All-or-nothing: fails without changes if any code is invalid or taken
+export const assignLocationCodes = async (
+  assignments: CodeAssignment[],
+  reason?: string
+): Promise<number> => {
+  return invoke<number>('assign_location_codes', { assignments, reason });
+};
+
+export const getRetiredLocationCodes = async (locationId: number): Promise<RetiredCode[]> => {
+  return invoke<RetiredCode[]>('get_retired_location_codes', { locationId });
+};
+
+// Blank QR labels to stick on boxes before creating their locations (A4 10×10 by default)
+export const generateReservedCodeSheet = async (
+  count: number,
+  qrOptions?: QrOptions,
+  stock?: LabelStock,
+  startOffset?: number
+): Promise<ReservedCodeSheet> => {
+  return invoke<ReservedCodeSheet>('generate_reserved_code_sheet', { count, qrOptions, stock, startOffset });
+};
+
+// Reprints the codes of a batch that aren't attached to a location yet
+export const reprintReservedCodeSheet = async (
+  batchId: string,
+  qrOptions?: QrOptions,
+  stock?: LabelStock,
+  startOffset?: number
+): Promise<string> => {
+  return invoke<string>('reprint_reserved_code_sheet', { batchId, qrOptions, stock, startOffset });
+};
+
+export const getReservedCodes = async (batchId?: string, unusedOnly?: boolean): Promise<ReservedCode[]> => {
+  return invoke<ReservedCode[]>('get_reserved_codes', { batchId, unusedOnly });
+};
+
+// ics:// link the app was started with, if any (deep-link plugin)
+export const getLaunchLinks = async (): Promise<string[] | null> => {
+  return invoke<string[] | null>('plugin:deep-link|get_current');
+};
+
+// Image scanning APIs
+export const decodeImageCodes = async (
+  image: { path: string } | { imageBase64: string }
+): Promise<DecodedCode[]> => {
+  return invoke<DecodedCode[]>('decode_image_codes', image);
+};
+
+export const scanImages = async (paths: string[]): Promise<ImageScanResult[]> => {
+  return invoke<ImageScanResult[]>('scan_images', { paths });
+};
+
 // Sync APIs
 export const configureWebDAV = async (
   url: string,
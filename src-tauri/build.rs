@@ -1,3 +1,13 @@
+use std::path::Path;
+
+/// Generated label font, see fonts/subset-noto-sans-sc.sh
+const BUNDLED_FONT: &str = "fonts/NotoSansSC-Regular.ttf";
+
 fn main() {
+    println!("cargo:rerun-if-changed={}", BUNDLED_FONT);
+    if !Path::new(BUNDLED_FONT).exists() {
+        panic!("{} is missing; run fonts/subset-noto-sans-sc.sh to generate it", BUNDLED_FONT);
+    }
+
     tauri_build::build()
 }
@@ -0,0 +1,37 @@
+#!/bin/sh
+# 生成内置标签字体 NotoSansSC-Regular.ttf：
+# 取 Noto Sans SC 常规字重，只保留拉丁字母、常用符号和 GB2312 汉字，控制安装包大小。
+# 需要 curl、python3 和 fonttools（pip install fonttools）。
+set -eu
+
+cd "$(dirname "$0")"
+SOURCE="https://github.com/google/fonts/raw/main/ofl/notosanssc"
+WORK="$(mktemp -d)"
+trap 'rm -rf "$WORK"' EXIT
+
+curl -fL -o "$WORK/variable.ttf" "$SOURCE/NotoSansSC%5Bwght%5D.ttf"
+curl -fL -o LICENSE-NotoSansSC.txt "$SOURCE/OFL.txt"
+
+# 可变字体固定为 400 字重，rusttype 和 PDF 嵌入都只需要静态字体
+fonttools varLib.instancer "$WORK/variable.ttf" wght=400 -o "$WORK/regular.ttf"
+
+# GB2312 一级、二级汉字（区 16-87）
+python3 - > "$WORK/hanzi.txt" <<'PY'
+import sys
+chars = []
+for row in range(0xB0, 0xF8):
+    for cell in range(0xA1, 0xFF):
+        try:
+            chars.append(bytes([row, cell]).decode("gb2312"))
+        except UnicodeDecodeError:
+            pass
+sys.stdout.write("".join(chars))
+PY
+
+# 拉丁字母、标点、单位符号（°、µ、Ω、℃）、箭头、数学符号、全角字符和中文标点
+pyftsubset "$WORK/regular.ttf" \
+    --text-file="$WORK/hanzi.txt" \
+    --unicodes="U+0020-007E,U+00A0-017F,U+2010-205E,U+2100-215F,U+2190-21FF,U+2200-22FF,U+2460-24FF,U+25A0-25FF,U+3000-303F,U+FF00-FFEF" \
+    --layout-features='kern' \
+    --no-hinting \
+    --output-file=NotoSansSC-Regular.ttf
@@ -0,0 +1,11 @@
+-- 位置标签（与物品共用标签）
+CREATE TABLE IF NOT EXISTS location_tags (
+    location_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (location_id, tag_id),
+    FOREIGN KEY (location_id) REFERENCES locations(id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_location_tags_tag ON location_tags(tag_id);
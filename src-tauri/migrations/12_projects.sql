@@ -0,0 +1,47 @@
+-- 项目（如一块 PCB）及其物料清单
+CREATE TABLE IF NOT EXISTS projects (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 每件成品所需的物品数量
+CREATE TABLE IF NOT EXISTS project_bom_lines (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL,
+    item_id INTEGER NOT NULL,
+    quantity_per_unit INTEGER NOT NULL,
+    reference TEXT,
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (project_id, item_id),
+    FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+    FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE
+);
+
+-- 生产批次：reserved 预留中，consumed 已扣减库存，cancelled 已取消
+CREATE TABLE IF NOT EXISTS project_builds (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'reserved',
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    finished_at DATETIME,
+    FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
+
+-- 批次预留的物品数量（预留时按物料清单计算）
+CREATE TABLE IF NOT EXISTS build_reservations (
+    build_id INTEGER NOT NULL,
+    item_id INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    PRIMARY KEY (build_id, item_id),
+    FOREIGN KEY (build_id) REFERENCES project_builds(id) ON DELETE CASCADE,
+    FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_project_bom_lines_project ON project_bom_lines(project_id);
+CREATE INDEX IF NOT EXISTS idx_build_reservations_item ON build_reservations(item_id);
@@ -0,0 +1,49 @@
+-- 制造商（物品的 manufacturer_id 指向此表）
+CREATE TABLE IF NOT EXISTS manufacturers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    website TEXT,
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 供应商（如 LCSC、Digi-Key）
+CREATE TABLE IF NOT EXISTS suppliers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    website TEXT,
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 供应商报价：单价按单件计，pack_size 为每包数量（订购须为其倍数）
+CREATE TABLE IF NOT EXISTS item_offers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL,
+    supplier_id INTEGER NOT NULL,
+    sku TEXT,
+    unit_price REAL,
+    currency TEXT NOT NULL DEFAULT 'CNY',
+    pack_size INTEGER NOT NULL DEFAULT 1,
+    url TEXT,
+    is_preferred INTEGER NOT NULL DEFAULT 0,
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE,
+    FOREIGN KEY (supplier_id) REFERENCES suppliers(id) ON DELETE CASCADE
+);
+
+-- 报价的历史价格，每次价格变化记录一条
+CREATE TABLE IF NOT EXISTS price_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    offer_id INTEGER NOT NULL,
+    unit_price REAL NOT NULL,
+    currency TEXT NOT NULL,
+    recorded_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (offer_id) REFERENCES item_offers(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_item_offers_item ON item_offers(item_id);
+CREATE INDEX IF NOT EXISTS idx_item_offers_supplier ON item_offers(supplier_id, sku);
+CREATE INDEX IF NOT EXISTS idx_price_history_offer ON price_history(offer_id, recorded_at);
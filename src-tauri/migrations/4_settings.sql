@@ -0,0 +1,6 @@
+-- 应用设置（键值对，值为 JSON 或纯文本）
+CREATE TABLE IF NOT EXISTS settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
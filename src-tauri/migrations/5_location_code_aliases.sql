@@ -0,0 +1,10 @@
+-- 位置的停用二维码（重新生成或更换后，旧标签仍可识别）
+CREATE TABLE IF NOT EXISTS location_code_aliases (
+    code TEXT PRIMARY KEY,
+    location_id INTEGER NOT NULL,
+    reason TEXT,
+    retired_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (location_id) REFERENCES locations(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_location_code_aliases_location ON location_code_aliases(location_id);
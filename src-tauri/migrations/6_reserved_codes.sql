@@ -0,0 +1,11 @@
+-- 预留的二维码（提前打印的空白标签，创建位置时绑定）
+CREATE TABLE IF NOT EXISTS reserved_codes (
+    code TEXT PRIMARY KEY,
+    batch_id TEXT NOT NULL,
+    location_id INTEGER,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    used_at DATETIME,
+    FOREIGN KEY (location_id) REFERENCES locations(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_reserved_codes_batch ON reserved_codes(batch_id);
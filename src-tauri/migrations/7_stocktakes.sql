@@ -0,0 +1,15 @@
+-- 盘点（对一个位置及其子位置清点库存）
+CREATE TABLE IF NOT EXISTS stocktakes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    location_id INTEGER NOT NULL,
+    include_sublocations BOOLEAN DEFAULT 1,
+    status TEXT NOT NULL DEFAULT 'open',
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    finished_at DATETIME,
+    FOREIGN KEY (location_id) REFERENCES locations(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_stocktakes_status ON stocktakes(status);
+
+-- 盘点明细按库存记录，见 9_item_stock.sql 的 stocktake_entries
@@ -0,0 +1,33 @@
+-- 库存表（同一物品可存放在多个位置，items.quantity 为各位置数量之和）
+CREATE TABLE IF NOT EXISTS item_stock (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL,
+    location_id INTEGER,
+    quantity INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (item_id, location_id),
+    FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE,
+    FOREIGN KEY (location_id) REFERENCES locations(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_item_stock_item ON item_stock(item_id);
+CREATE INDEX IF NOT EXISTS idx_item_stock_location ON item_stock(location_id);
+
+-- UNIQUE (item_id, location_id) 不约束 NULL，未分配位置的库存每个物品最多一条
+CREATE UNIQUE INDEX IF NOT EXISTS idx_item_stock_unassigned ON item_stock(item_id) WHERE location_id IS NULL;
+
+-- 盘点明细（按库存记录，开始时的系统数量与清点数量）
+CREATE TABLE IF NOT EXISTS stocktake_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    stocktake_id INTEGER NOT NULL,
+    stock_id INTEGER NOT NULL,
+    expected_quantity INTEGER NOT NULL,
+    counted_quantity INTEGER,
+    counted_at DATETIME,
+    UNIQUE (stocktake_id, stock_id),
+    FOREIGN KEY (stocktake_id) REFERENCES stocktakes(id) ON DELETE CASCADE,
+    FOREIGN KEY (stock_id) REFERENCES item_stock(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_stocktake_entries_stocktake ON stocktake_entries(stocktake_id);
@@ -0,0 +1,190 @@
+use crate::database::{get_pool, get_setting, models::{AlertMode, StockAlert, StockAlertSettings}, query_all, query_one, execute, set_setting, DbPool};
+use chrono::Timelike;
+use sqlx::Row;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+/// Event emitted with a `StockAlert` when an item falls to or below its minimum quantity
+pub const STOCK_LOW_EVENT: &str = "stock-low";
+
+/// Event emitted with the list of `StockAlert`s included in a daily digest
+pub const STOCK_DIGEST_EVENT: &str = "stock-low-digest";
+
+/// Settings key holding `StockAlertSettings` as JSON
+pub const SETTINGS_KEY: &str = "stock_alerts";
+
+/// Settings key holding the local date the last digest was sent
+const LAST_DIGEST_KEY: &str = "stock_alert_last_digest";
+
+/// How often the background task checks whether the digest is due
+const DIGEST_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Most item names listed in a digest notification
+const DIGEST_MAX_NAMES: usize = 5;
+
+pub async fn load_settings(db: &DbPool) -> Result<StockAlertSettings, String> {
+    match get_setting(db, SETTINGS_KEY).await.map_err(|e| e.to_string())? {
+        Some(value) => serde_json::from_str(&value).map_err(|e| format!("Invalid stock alert settings: {}", e)),
+        None => Ok(StockAlertSettings::default()),
+    }
+}
+
+/// Store new alert settings. Alerts queued for the digest are dropped when digest mode
+/// is turned off, so switching back later doesn't report stale ones.
+pub async fn save_settings(db: &DbPool, settings: &StockAlertSettings) -> Result<(), String> {
+    let value = serde_json::to_string(settings).map_err(|e| e.to_string())?;
+    set_setting(db, SETTINGS_KEY, &value).await.map_err(|e| e.to_string())?;
+
+    if settings.mode != AlertMode::Digest {
+        execute(db, "DELETE FROM pending_stock_alerts", vec![])
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn notify(app: &AppHandle, title: &str, body: &str) {
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        eprintln!("Failed to show notification: {}", e);
+    }
+}
+
+fn describe(alert: &StockAlert) -> String {
+    format!(
+        "{} 剩余 {}{}，最低库存 {}",
+        alert.name,
+        alert.quantity,
+        alert.unit.as_deref().unwrap_or(""),
+        alert.min_quantity
+    )
+}
+
+/// Alert if an item's quantity just went from above its minimum to at or below it.
+/// `previous_quantity` is the quantity before the change. Muted items are ignored.
+pub async fn check_item(app: &AppHandle, db: &DbPool, item_id: i32, previous_quantity: i32) -> Result<(), String> {
+    let result = query_one(
+        db,
+        "SELECT i.name, i.quantity, i.min_quantity, i.unit, EXISTS(SELECT 1 FROM muted_items m WHERE m.item_id = i.id) AS muted
+         FROM items i WHERE i.id = ?1 AND i.deleted_at IS NULL",
+        vec![item_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let Some(row) = result else {
+        return Ok(());
+    };
+    let Some(min_quantity) = row.try_get::<Option<i32>, _>("min_quantity").ok().flatten() else {
+        return Ok(());
+    };
+    let quantity: i32 = row.get("quantity");
+    let muted: bool = row.get("muted");
+
+    if muted || previous_quantity <= min_quantity || quantity > min_quantity {
+        return Ok(());
+    }
+
+    let alert = StockAlert {
+        item_id,
+        name: row.get("name"),
+        quantity,
+        min_quantity,
+        unit: row.try_get("unit").ok().flatten(),
+    };
+
+    if let Err(e) = app.emit(STOCK_LOW_EVENT, alert.clone()) {
+        eprintln!("Failed to emit {}: {}", STOCK_LOW_EVENT, e);
+    }
+
+    match load_settings(db).await?.mode {
+        AlertMode::Immediate => notify(app, "库存不足", &describe(&alert)),
+        AlertMode::Digest => {
+            execute(
+                db,
+                "INSERT INTO pending_stock_alerts (item_id, quantity, min_quantity) VALUES (?1, ?2, ?3)",
+                vec![item_id.to_string(), quantity.to_string(), min_quantity.to_string()],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+        AlertMode::Off => {}
+    }
+
+    Ok(())
+}
+
+/// Send the daily digest if digest mode is on, the digest hour has passed and no digest
+/// was sent today. Items that were restocked or muted in the meantime are left out.
+pub async fn send_digest_if_due(app: &AppHandle, db: &DbPool) -> Result<(), String> {
+    let settings = load_settings(db).await?;
+    if settings.mode != AlertMode::Digest {
+        return Ok(());
+    }
+
+    let now = chrono::Local::now();
+    let today = now.format("%Y-%m-%d").to_string();
+    if now.hour() < settings.digest_hour {
+        return Ok(());
+    }
+    if get_setting(db, LAST_DIGEST_KEY).await.map_err(|e| e.to_string())?.as_deref() == Some(today.as_str()) {
+        return Ok(());
+    }
+
+    let result = query_all(
+        db,
+        "SELECT DISTINCT i.id, i.name, i.quantity, i.min_quantity, i.unit
+         FROM pending_stock_alerts p JOIN items i ON i.id = p.item_id
+         WHERE i.min_quantity IS NOT NULL AND i.quantity <= i.min_quantity AND i.deleted_at IS NULL
+         AND NOT EXISTS(SELECT 1 FROM muted_items m WHERE m.item_id = i.id)
+         ORDER BY i.name",
+        vec![],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let alerts: Vec<StockAlert> = result
+        .iter()
+        .map(|row| StockAlert {
+            item_id: row.get("id"),
+            name: row.get("name"),
+            quantity: row.get("quantity"),
+            min_quantity: row.get("min_quantity"),
+            unit: row.try_get("unit").ok().flatten(),
+        })
+        .collect();
+
+    if !alerts.is_empty() {
+        let mut names: Vec<&str> = alerts.iter().take(DIGEST_MAX_NAMES).map(|alert| alert.name.as_str()).collect();
+        if alerts.len() > DIGEST_MAX_NAMES {
+            names.push("等");
+        }
+        notify(app, &format!("{} 种物品库存不足", alerts.len()), &names.join("、"));
+
+        if let Err(e) = app.emit(STOCK_DIGEST_EVENT, &alerts) {
+            eprintln!("Failed to emit {}: {}", STOCK_DIGEST_EVENT, e);
+        }
+    }
+
+    execute(db, "DELETE FROM pending_stock_alerts", vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+    set_setting(db, LAST_DIGEST_KEY, &today).await.map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Periodically send the daily digest in the background
+pub fn spawn_digest_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Some(db) = get_pool(&app).await {
+                if let Err(e) = send_digest_if_due(&app, &db).await {
+                    eprintln!("Failed to send stock digest: {}", e);
+                }
+            }
+            tokio::time::sleep(DIGEST_CHECK_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,644 @@
+use crate::database::models::{BomImportLine, BomMatchCandidate};
+use std::collections::HashSet;
+
+/// Lowest fuzzy score at which a line is matched to an item without an exact match. Fuzzy
+/// scores run up to 0.8, so this needs about three quarters of the line's words.
+const FUZZY_THRESHOLD: f64 = 0.6;
+
+/// Candidates reported per line
+const MAX_CANDIDATES: usize = 3;
+
+/// Most parts a single designator range such as `R1-R100` may stand for
+const MAX_DESIGNATOR_RANGE: i32 = 10_000;
+
+/// Imperial chip sizes, as used in footprint names like `R_0805_2012Metric`
+const CHIP_SIZES: &[&str] = &["01005", "0201", "0402", "0603", "0805", "1008", "1206", "1210", "1812", "2010", "2512"];
+
+/// Package families that are followed by a pin count or size, e.g. `SOT-23`, `SOIC-8`
+const PACKAGE_PREFIXES: &[&str] = &[
+    "SOT", "SOD", "SOIC", "SOP", "SSOP", "TSSOP", "MSOP", "QFN", "DFN", "QFP", "LQFP", "TQFP", "BGA", "TO", "DIP", "SMA", "SMB", "SMC",
+];
+
+/// Split CSV text into records. Handles quoted fields, doubled quotes, CRLF line endings and a
+/// byte order mark; the delimiter (comma, semicolon or tab) is guessed from the first lines.
+pub fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let text = text.trim_start_matches('\u{feff}');
+    let delimiter = detect_delimiter(text);
+
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c != '"' {
+                field.push(c);
+            } else if chars.peek() == Some(&'"') {
+                field.push('"');
+                chars.next();
+            } else {
+                in_quotes = false;
+            }
+        } else if c == '"' {
+            in_quotes = true;
+        } else if c == delimiter {
+            row.push(std::mem::take(&mut field));
+        } else if c == '\n' || c == '\r' {
+            if c == '\r' && chars.peek() == Some(&'\n') {
+                chars.next();
+            }
+            row.push(std::mem::take(&mut field));
+            rows.push(std::mem::take(&mut row));
+        } else {
+            field.push(c);
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows
+}
+
+fn detect_delimiter(text: &str) -> char {
+    let sample: Vec<&str> = text.lines().take(20).collect();
+    [',', ';', '\t']
+        .into_iter()
+        .max_by_key(|delimiter| {
+            sample
+                .iter()
+                .map(|line| line.split('"').step_by(2).map(|part| part.matches(*delimiter).count()).sum::<usize>())
+                .max()
+                .unwrap_or(0)
+        })
+        .unwrap_or(',')
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Column {
+    Reference,
+    Quantity,
+    Value,
+    Footprint,
+    Mpn,
+    Manufacturer,
+    SupplierPart,
+    Description,
+    Dnp,
+}
+
+/// Column for a header as written by KiCad, JLCPCB and most distributor BOM tools
+fn column_for(header: &str) -> Option<Column> {
+    let key: String = header.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+    let column = match key.as_str() {
+        "reference" | "references" | "ref" | "refs" | "refdes" | "designator" | "designators" => Column::Reference,
+        "qty" | "quantity" | "quantityperpcb" | "qnty" | "count" => Column::Quantity,
+        "value" | "val" | "comment" => Column::Value,
+        "footprint" | "package" | "pcbfootprint" | "jlcpcbfootprint" => Column::Footprint,
+        "mpn" | "manufacturerpartnumber" | "manufacturerpart" | "mfrpartnumber" | "mfrpart" | "mfrpn" | "partnumber" => Column::Mpn,
+        "manufacturer" | "manufacturername" | "mfr" | "mfg" => Column::Manufacturer,
+        "lcsc" | "lcscpart" | "lcscpartnumber" | "jlcpcbpart" | "jlcpcbpartnumber" | "supplierpart" | "supplierpartnumber" | "sku" => Column::SupplierPart,
+        "description" | "desc" => Column::Description,
+        "dnp" | "donotpopulate" => Column::Dnp,
+        _ => return None,
+    };
+    Some(column)
+}
+
+/// A BOM file as read, before matching
+pub struct ParsedBom {
+    /// "kicad", "jlcpcb" or "generic", from the column names
+    pub format: String,
+    pub lines: Vec<BomImportLine>,
+}
+
+/// Read a BOM exported as CSV. Lines before the header row, such as the summary KiCad's
+/// legacy exporter writes, are skipped, as are empty and do-not-populate lines.
+pub fn parse_bom(text: &str) -> Result<ParsedBom, String> {
+    let rows = parse_csv(text);
+
+    let (header_index, columns) = rows
+        .iter()
+        .enumerate()
+        .take(30)
+        .map(|(index, row)| (index, row.iter().map(|header| column_for(header)).collect::<Vec<_>>()))
+        .find(|(_, columns)| {
+            let has = |column| columns.contains(&Some(column));
+            columns.iter().flatten().count() >= 2 && (has(Column::Reference) || has(Column::Value) || has(Column::Mpn))
+        })
+        .ok_or_else(|| "No BOM header found; expected columns such as Reference, Value, Footprint or Qty".to_string())?;
+
+    let headers: Vec<String> = rows[header_index].iter().map(|header| header.trim().to_lowercase()).collect();
+    let supplier_part_column = columns
+        .iter()
+        .position(|c| *c == Some(Column::SupplierPart))
+        .map(|position| rows[header_index][position].trim().to_string());
+    let format = if headers.iter().any(|h| h == "comment" || h.starts_with("lcsc") || h.starts_with("jlcpcb")) {
+        "jlcpcb"
+    } else if headers.iter().any(|h| h == "reference" || h == "references") {
+        "kicad"
+    } else {
+        "generic"
+    };
+
+    let mut lines = Vec::new();
+    for (index, row) in rows.iter().enumerate().skip(header_index + 1) {
+        let field = |column: Column| {
+            columns
+                .iter()
+                .position(|c| *c == Some(column))
+                .and_then(|position| row.get(position))
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        if row.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+        if field(Column::Dnp).is_some_and(|dnp| !matches!(dnp.to_lowercase().as_str(), "0" | "no" | "false" | "n")) {
+            continue;
+        }
+
+        let reference = field(Column::Reference);
+        let designator_count = reference
+            .as_deref()
+            .map(designator_count)
+            .transpose()
+            .map_err(|e| format!("Row {}: {}", index + 1, e))?
+            .unwrap_or(0);
+        let quantity = field(Column::Quantity)
+            .and_then(|qty| qty.parse::<f64>().ok())
+            .map(|qty| qty.round() as i32)
+            .filter(|qty| *qty > 0)
+            .unwrap_or(designator_count.max(1));
+
+        lines.push(BomImportLine {
+            row: index as i32 + 1,
+            reference,
+            quantity,
+            value: field(Column::Value),
+            footprint: field(Column::Footprint),
+            mpn: field(Column::Mpn),
+            manufacturer: field(Column::Manufacturer),
+            supplier_part: field(Column::SupplierPart),
+            supplier_part_column: supplier_part_column.clone(),
+            description: field(Column::Description),
+            item_id: None,
+            item_name: None,
+            match_method: None,
+            score: 0.0,
+            candidates: Vec::new(),
+        });
+    }
+
+    if lines.is_empty() {
+        return Err("BOM has no lines".to_string());
+    }
+
+    Ok(ParsedBom {
+        format: format.to_string(),
+        lines,
+    })
+}
+
+/// Number of parts a reference field lists, counting ranges such as `R1-R4` or `R1-4` in full
+fn designator_count(references: &str) -> Result<i32, String> {
+    references
+        .split([',', ';', ' '])
+        .filter(|reference| reference.chars().any(|c| c.is_ascii_alphanumeric()))
+        .try_fold(0i32, |count, reference| {
+            let size = designator_range(reference)?.unwrap_or(1);
+            count.checked_add(size).ok_or_else(|| "Too many designators".to_string())
+        })
+}
+
+/// Size of a designator range like `R1-R4` or `R1-4`, `None` if `reference` isn't one
+fn designator_range(reference: &str) -> Result<Option<i32>, String> {
+    let split = |designator: &str| {
+        let digits = designator.find(|c: char| c.is_ascii_digit())?;
+        let (prefix, number) = designator.split_at(digits);
+        Some((prefix.to_string(), number.parse::<i64>().ok()?))
+    };
+
+    let range = reference.split_once('-').and_then(|(first, last)| Some((split(first)?, split(last)?)));
+    let Some(((prefix, start), (last_prefix, end))) = range else {
+        return Ok(None);
+    };
+    if (!last_prefix.is_empty() && last_prefix != prefix) || end < start {
+        return Ok(None);
+    }
+
+    let size = end - start + 1;
+    if size > MAX_DESIGNATOR_RANGE as i64 {
+        return Err(format!("Designator range {} covers more than {} parts", reference, MAX_DESIGNATOR_RANGE));
+    }
+    Ok(Some(size as i32))
+}
+
+/// Words of a text in any script, keeping values like `4.7k` and packages like `SOT-23` together
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '.' || c == '-'))
+        .map(|word| word.trim_matches(|c| c == '.' || c == '-'))
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_string())
+        .collect()
+}
+
+/// Part numbers and words compared without case or dashes
+fn normalize(word: &str) -> String {
+    word.chars().filter(|c| *c != '-' && !c.is_whitespace()).collect::<String>().to_uppercase()
+}
+
+/// Package of a word such as `0805`, `SOT-23-3` or `SOIC-8`, e.g. "0805", "SOT23", "SOIC8"
+fn package_key(word: &str) -> Option<String> {
+    let upper = word.to_uppercase();
+    if CHIP_SIZES.contains(&upper.as_str()) {
+        return Some(upper);
+    }
+
+    let prefix = PACKAGE_PREFIXES
+        .iter()
+        .filter(|prefix| upper.starts_with(*prefix))
+        .max_by_key(|prefix| prefix.len())?;
+    let rest = upper[prefix.len()..].trim_start_matches('-');
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.is_empty() {
+        return None;
+    }
+    Some(format!("{}{}", prefix, digits))
+}
+
+/// Electrical value such as `4k7`, `100nF`, `4R7`, `10 µH` or `2.2uF`, as the value in base
+/// units and the unit if known ('F', 'H' or 'Ω')
+fn parse_value(word: &str) -> Option<(f64, Option<char>)> {
+    let mut text = word.replace('µ', "u");
+    let mut unit = None;
+    for (suffix, suffix_unit) in [("ohms", 'Ω'), ("ohm", 'Ω'), ("Ω", 'Ω'), ("F", 'F'), ("f", 'F'), ("H", 'H'), ("h", 'H')] {
+        if text.len() > suffix.len() && text.ends_with(suffix) {
+            text.truncate(text.len() - suffix.len());
+            unit = Some(suffix_unit);
+            break;
+        }
+    }
+
+    // Chip sizes such as 0805 are not values
+    if text.len() > 1 && text.starts_with('0') && text.chars().nth(1).is_some_and(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let number: String = text.chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+    if number.is_empty() || !number.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let rest = &text[number.len()..];
+    let mut rest_chars = rest.chars();
+    let (multiplier, prefix_unit) = match rest_chars.next() {
+        None => return number.parse().ok().map(|value| (value, unit)),
+        Some('p') => (1e-12, None),
+        Some('n') => (1e-9, None),
+        Some('u') => (1e-6, None),
+        Some('m') => (1e-3, None),
+        Some('k') | Some('K') => (1e3, None),
+        Some('M') => (1e6, None),
+        Some('G') => (1e9, None),
+        Some('R') | Some('r') => (1.0, Some('Ω')),
+        Some(_) => return None,
+    };
+
+    // Digits after the prefix continue the number, as in 4k7 = 4.7k
+    let fraction: String = rest_chars.collect();
+    if !fraction.chars().all(|c| c.is_ascii_digit()) || (!fraction.is_empty() && number.contains('.')) {
+        return None;
+    }
+    let value: f64 = if fraction.is_empty() {
+        number.parse().ok()?
+    } else {
+        format!("{}.{}", number, fraction).parse().ok()?
+    };
+
+    Some((value * multiplier, unit.or(prefix_unit)))
+}
+
+fn same_value(a: (f64, Option<char>), b: (f64, Option<char>)) -> bool {
+    let units_match = a.1.is_none() || b.1.is_none() || a.1 == b.1;
+    units_match && (a.0 - b.0).abs() <= 1e-9 * a.0.abs().max(b.0.abs())
+}
+
+/// Comparable form of a word: its value with unit, its package, or the word itself
+fn match_token(word: &str) -> String {
+    if let Some((value, unit)) = parse_value(word) {
+        return format!("{:e}{}", value, unit.map(String::from).unwrap_or_default());
+    }
+    package_key(word).unwrap_or_else(|| normalize(word))
+}
+
+/// An item as seen by the matcher
+pub struct CatalogItem {
+    pub id: i32,
+    pub name: String,
+    /// Manufacturer part number, normalized
+    mpn: Option<String>,
+    /// Part numbers of the item's supplier offers, normalized
+    skus: HashSet<String>,
+    words: HashSet<String>,
+    values: Vec<(f64, Option<char>)>,
+    packages: HashSet<String>,
+    tokens: HashSet<String>,
+}
+
+impl CatalogItem {
+    /// `mpn` and `skus` are compared as a whole; the other texts are searched word by word
+    pub fn new(id: i32, name: &str, texts: &[Option<String>], mpn: Option<&str>, skus: &[String]) -> Self {
+        let text = std::iter::once(name.to_string())
+            .chain(texts.iter().flatten().cloned())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text_words = words(&text);
+
+        Self {
+            id,
+            name: name.to_string(),
+            mpn: mpn.map(normalize).filter(|mpn| !mpn.is_empty()),
+            skus: skus.iter().map(|sku| normalize(sku)).filter(|sku| !sku.is_empty()).collect(),
+            words: text_words.iter().map(|word| normalize(word)).collect(),
+            values: text_words.iter().filter_map(|word| parse_value(word)).collect(),
+            packages: text_words.iter().filter_map(|word| package_key(word)).collect(),
+            tokens: text_words.iter().map(|word| match_token(word)).collect(),
+        }
+    }
+
+    /// Whether the part number is the item's own, or failing that appears in its name or texts
+    fn has_part_number(&self, part: &str, own: impl Fn(&str) -> bool) -> bool {
+        let part = normalize(part);
+        !part.is_empty() && (own(&part) || normalize(&self.name) == part || self.words.contains(&part))
+    }
+
+    fn has_value(&self, value: &str) -> bool {
+        match parse_value(value) {
+            Some(parsed) => self.values.iter().any(|own| same_value(*own, parsed)),
+            None => {
+                let value = normalize(value);
+                normalize(&self.name) == value || self.words.contains(&value)
+            }
+        }
+    }
+}
+
+/// Package named by a footprint such as `Resistor_SMD:R_0805_2012Metric` or `SOT-23-3`
+fn footprint_package(footprint: &str) -> Option<String> {
+    let name = footprint.rsplit(':').next().unwrap_or(footprint);
+    name.split('_').flat_map(words).find_map(|word| package_key(&word))
+}
+
+/// How well an item matches a line: part numbers first, then value and package, then the
+/// share of the line's words found on the item
+fn score(line: &BomImportLine, item: &CatalogItem) -> Option<(&'static str, f64)> {
+    if line.mpn.as_deref().is_some_and(|mpn| item.has_part_number(mpn, |part| item.mpn.as_deref() == Some(part))) {
+        return Some(("mpn", 1.0));
+    }
+    if line.supplier_part.as_deref().is_some_and(|part| item.has_part_number(part, |part| item.skus.contains(part))) {
+        return Some(("supplier_part", 1.0));
+    }
+
+    let package = line.footprint.as_deref().and_then(footprint_package);
+    if let (Some(value), Some(package)) = (line.value.as_deref(), &package) {
+        if item.has_value(value) && item.packages.contains(package) {
+            return Some(("value_footprint", 0.9));
+        }
+    }
+
+    let line_tokens: HashSet<String> = [&line.value, &line.description]
+        .into_iter()
+        .flatten()
+        .flat_map(|text| words(text))
+        .map(|word| match_token(&word))
+        .chain(package)
+        .collect();
+    if line_tokens.is_empty() {
+        return None;
+    }
+    let found = line_tokens.iter().filter(|token| item.tokens.contains(*token)).count();
+    let share = found as f64 / line_tokens.len() as f64;
+    // Kept below the exact matches even when every word is found
+    (found > 0).then_some(("fuzzy", share * 0.8))
+}
+
+/// Match every line to the best item. Part number and value + footprint matches are taken
+/// when they point to a single item; fuzzy matches need `FUZZY_THRESHOLD` and a clear lead.
+/// The best candidates are kept on every line so unclear matches can be resolved by hand.
+pub fn match_lines(lines: &mut [BomImportLine], catalog: &[CatalogItem]) {
+    for line in lines.iter_mut() {
+        let mut candidates: Vec<BomMatchCandidate> = catalog
+            .iter()
+            .filter_map(|item| {
+                score(line, item).map(|(method, score)| BomMatchCandidate {
+                    item_id: item.id,
+                    item_name: item.name.clone(),
+                    method: method.to_string(),
+                    score,
+                })
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.item_name.cmp(&b.item_name)));
+        candidates.truncate(MAX_CANDIDATES);
+
+        let best = candidates.first();
+        let clear_lead = candidates.len() < 2 || best.is_some_and(|best| best.score > candidates[1].score);
+        let accepted = best.filter(|best| clear_lead && (best.method != "fuzzy" || best.score >= FUZZY_THRESHOLD));
+
+        if let Some(best) = accepted {
+            line.item_id = Some(best.item_id);
+            line.item_name = Some(best.item_name.clone());
+            line.match_method = Some(best.method.clone());
+            line.score = best.score;
+        }
+        line.candidates = candidates;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(value: Option<&str>, footprint: Option<&str>, mpn: Option<&str>, description: Option<&str>) -> BomImportLine {
+        BomImportLine {
+            row: 2,
+            reference: None,
+            quantity: 1,
+            value: value.map(String::from),
+            footprint: footprint.map(String::from),
+            mpn: mpn.map(String::from),
+            manufacturer: None,
+            supplier_part: None,
+            supplier_part_column: None,
+            description: description.map(String::from),
+            item_id: None,
+            item_name: None,
+            match_method: None,
+            score: 0.0,
+            candidates: Vec::new(),
+        }
+    }
+
+    fn item(id: i32, name: &str, specifications: Option<&str>, mpn: Option<&str>) -> CatalogItem {
+        CatalogItem::new(id, name, &[specifications.map(String::from)], mpn, &[])
+    }
+
+    fn assert_value(word: &str, value: f64, unit: Option<char>) {
+        let parsed = parse_value(word).unwrap_or_else(|| panic!("{} is not a value", word));
+        assert!(same_value(parsed, (value, unit)), "{} parsed as {:?}", word, parsed);
+        assert_eq!(parsed.1, unit, "unit of {}", word);
+    }
+
+    #[test]
+    fn parses_kicad_export_with_bom_marker_and_quotes() {
+        let text = "\u{feff}\"Reference\",\"Value\",\"Footprint\",\"Qty\",\"DNP\"\r\n\
+                    \"R1,R2\",\"4k7\",\"Resistor_SMD:R_0805_2012Metric\",\"2\",\"\"\r\n\
+                    \"C1\",\"100nF\",\"Capacitor_SMD:C_0603_1608Metric\",\"1\",\"DNP\"\r\n\
+                    \"U1\",\"NE555, timer\",\"Package_SO:SOIC-8\",\"1\",\"\"\r\n";
+        let bom = parse_bom(text).unwrap();
+
+        assert_eq!(bom.format, "kicad");
+        assert_eq!(bom.lines.len(), 2);
+        assert_eq!(bom.lines[0].reference.as_deref(), Some("R1,R2"));
+        assert_eq!(bom.lines[0].quantity, 2);
+        assert_eq!(bom.lines[0].value.as_deref(), Some("4k7"));
+        assert_eq!(bom.lines[1].value.as_deref(), Some("NE555, timer"));
+        assert_eq!(bom.lines[1].row, 4);
+    }
+
+    #[test]
+    fn parses_jlcpcb_export_with_semicolons() {
+        let text = "Comment;Designator;Footprint;LCSC\n10uF;C1,C2,C3;0805;C15850\n\"1k; 1%\";R7;0402;C11702\n";
+        let bom = parse_bom(text).unwrap();
+
+        assert_eq!(bom.format, "jlcpcb");
+        assert_eq!(bom.lines.len(), 2);
+        assert_eq!(bom.lines[0].value.as_deref(), Some("10uF"));
+        assert_eq!(bom.lines[0].quantity, 3);
+        assert_eq!(bom.lines[0].supplier_part.as_deref(), Some("C15850"));
+        assert_eq!(bom.lines[1].value.as_deref(), Some("1k; 1%"));
+        assert_eq!(bom.lines[1].footprint.as_deref(), Some("0402"));
+    }
+
+    #[test]
+    fn skips_summary_lines_before_the_header() {
+        let text = "\"Source:\",\"board.kicad_sch\"\n\"Date:\",\"2024-05-01\"\n\n\"Ref\",\"Qnty\",\"Value\"\n\"D1 D2\",\"2\",\"LED\"\n";
+        let bom = parse_bom(text).unwrap();
+
+        assert_eq!(bom.format, "generic");
+        assert_eq!(bom.lines.len(), 1);
+        assert_eq!(bom.lines[0].reference.as_deref(), Some("D1 D2"));
+        assert_eq!(bom.lines[0].quantity, 2);
+    }
+
+    #[test]
+    fn counts_designator_ranges() {
+        let bom = parse_bom("Reference,Value\nR1-R4,10k\n\"R5-8, R10\",1k\nR9,100R\n").unwrap();
+        let quantities: Vec<i32> = bom.lines.iter().map(|line| line.quantity).collect();
+        assert_eq!(quantities, vec![4, 5, 1]);
+
+        // A quantity column wins over the designators
+        let bom = parse_bom("Reference,Value,Qty\nR1-R4,10k,3\n").unwrap();
+        assert_eq!(bom.lines[0].quantity, 3);
+    }
+
+    #[test]
+    fn rejects_absurd_designator_ranges() {
+        let err = parse_bom("Reference,Value\nR1,10k\nR0-R2147483647,1k\n").err().unwrap();
+        assert!(err.starts_with("Row 3:"), "{}", err);
+        assert!(parse_bom("Reference,Value\nR1-R99999999999999999999,1k\n").is_ok());
+        assert_eq!(designator_count("R1-R10000"), Ok(10_000));
+        assert!(designator_count("R1-R10000 C1-C10000 D1-D10000").is_ok());
+    }
+
+    #[test]
+    fn rejects_files_without_a_header() {
+        assert!(parse_bom("just,some\nplain,text\n").is_err());
+        assert!(parse_bom("Reference,Value\n").is_err());
+    }
+
+    #[test]
+    fn parses_values() {
+        assert_value("4k7", 4700.0, None);
+        assert_value("100nF", 100e-9, Some('F'));
+        assert_value("4R7", 4.7, Some('Ω'));
+        assert_value("2.2uF", 2.2e-6, Some('F'));
+        assert_value("10µH", 10e-6, Some('H'));
+        assert_value("1M", 1e6, None);
+        assert_value("10kohm", 10e3, Some('Ω'));
+
+        assert_eq!(parse_value("0805"), None);
+        assert_eq!(parse_value("4k7k"), None);
+        assert_eq!(parse_value("LED"), None);
+        assert!(same_value(parse_value("4.7k").unwrap(), parse_value("4k7").unwrap()));
+    }
+
+    #[test]
+    fn matches_part_numbers_and_value_with_footprint() {
+        let catalog = [
+            item(1, "555 timer", None, Some("NE555-DR")),
+            item(2, "4.7kΩ resistor", Some("0805, 1%"), None),
+            item(3, "4.7kΩ resistor", Some("0603, 1%"), None),
+        ];
+        let mut lines = [
+            line(Some("NE555"), None, Some("ne555dr"), None),
+            line(Some("4k7"), Some("Resistor_SMD:R_0805_2012Metric"), None, None),
+        ];
+        match_lines(&mut lines, &catalog);
+
+        assert_eq!(lines[0].item_id, Some(1));
+        assert_eq!(lines[0].match_method.as_deref(), Some("mpn"));
+        assert_eq!(lines[1].item_id, Some(2));
+        assert_eq!(lines[1].match_method.as_deref(), Some("value_footprint"));
+    }
+
+    #[test]
+    fn fuzzy_matches_need_the_threshold() {
+        let catalog = [item(1, "red LED", Some("0603"), None)];
+
+        // Every word found: 0.8
+        let mut lines = [line(Some("LED"), None, None, Some("red 0603"))];
+        match_lines(&mut lines, &catalog);
+        assert_eq!(lines[0].item_id, Some(1));
+        assert_eq!(lines[0].match_method.as_deref(), Some("fuzzy"));
+
+        // Two of three words found: about 0.53, kept only as a candidate
+        let mut lines = [line(Some("LED"), None, None, Some("green 0603"))];
+        match_lines(&mut lines, &catalog);
+        assert_eq!(lines[0].item_id, None);
+        assert_eq!(lines[0].candidates.len(), 1);
+        assert!(lines[0].candidates[0].score < FUZZY_THRESHOLD);
+    }
+
+    #[test]
+    fn fuzzy_matches_chinese_names() {
+        let catalog = [
+            item(1, "贴片电阻", Some("4.7k，0805"), None),
+            item(2, "贴片电容", Some("100nF，0805"), None),
+        ];
+        let mut lines = [line(Some("4k7"), None, None, Some("贴片电阻 0805"))];
+        match_lines(&mut lines, &catalog);
+
+        assert_eq!(lines[0].item_id, Some(1));
+        assert_eq!(lines[0].match_method.as_deref(), Some("fuzzy"));
+    }
+
+    #[test]
+    fn tied_candidates_are_left_for_the_user() {
+        let catalog = [
+            item(1, "red LED", Some("0603"), None),
+            item(2, "red LED", Some("0603, spare reel"), None),
+            item(3, "red LED", Some("0805"), None),
+        ];
+        let mut lines = [line(Some("LED"), None, None, Some("red 0603"))];
+        match_lines(&mut lines, &catalog);
+
+        assert_eq!(lines[0].item_id, None);
+        let ids: Vec<i32> = lines[0].candidates.iter().map(|candidate| candidate.item_id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+}
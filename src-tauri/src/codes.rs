@@ -0,0 +1,396 @@
+use crate::database::{begin, delete_setting, get_or_init_setting, get_setting, set_setting, tx_execute, tx_query_one, DbPool, DbTransaction};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::Row;
+
+/// URI scheme of QR payloads, registered with the OS so scanning a label opens the app
+pub const SCHEME: &str = "ics";
+
+/// Current payload format: `ics://loc/<code>?v=1&db=<instance-id>[&sig=<hmac>]`, with the
+/// code percent-encoded
+const PAYLOAD_VERSION: u32 = 1;
+
+const INSTANCE_ID_KEY: &str = "instance_id";
+const SIGNING_KEY_KEY: &str = "qr_signing_key";
+
+/// Most codes that can be reserved at once, a few sheets of small labels
+pub const MAX_RESERVED_BATCH: u32 = 1000;
+
+/// Signatures are truncated to 64 bits to keep the QR codes small
+const SIGNATURE_HEX_LEN: usize = 16;
+
+/// Generate a code for a new location
+pub fn new_code_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// A scanned QR payload, either a deep link or a bare code from an older label
+pub struct ParsedCode {
+    pub code_id: String,
+    pub version: u32,
+    pub instance_id: Option<String>,
+    pub signature: Option<String>,
+}
+
+/// Parse a scanned payload. Anything that isn't an `ics://` link is taken as a bare
+/// code such as `LOC-1a2b3c4d`, as printed before deep links were introduced.
+pub fn parse(text: &str) -> Result<ParsedCode, String> {
+    let text = text.trim();
+    let prefix = format!("{}://", SCHEME);
+    let Some(rest) = text.strip_prefix(&prefix) else {
+        return Ok(ParsedCode {
+            code_id: text.to_string(),
+            version: 0,
+            instance_id: None,
+            signature: None,
+        });
+    };
+
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let code_id = path
+        .strip_prefix("loc/")
+        .filter(|code| !code.is_empty())
+        .ok_or_else(|| format!("Unsupported QR link: {}", text))?;
+
+    let mut parsed = ParsedCode {
+        code_id: percent_decode(code_id.trim_end_matches('/'))?,
+        version: PAYLOAD_VERSION,
+        instance_id: None,
+        signature: None,
+    };
+
+    for pair in query.split('&') {
+        match pair.split_once('=') {
+            Some(("v", value)) => parsed.version = value.parse().map_err(|_| format!("Invalid QR link version: {}", value))?,
+            Some(("db", value)) => parsed.instance_id = Some(value.to_string()),
+            Some(("sig", value)) => parsed.signature = Some(value.to_lowercase()),
+            _ => {}
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Give this installation its instance ID on first start. The ID is part of every QR
+/// payload, so it never changes afterwards.
+pub async fn init_instance_id(db: &DbPool) -> Result<(), String> {
+    let new_id = uuid::Uuid::new_v4().simple().to_string()[..16].to_string();
+    get_or_init_setting(db, INSTANCE_ID_KEY, &new_id)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Instance identity and signing key used to build and check QR payloads
+pub struct CodeContext {
+    pub instance_id: String,
+    signing_key: Option<Vec<u8>>,
+}
+
+impl CodeContext {
+    /// Load the instance ID and the signing key if enabled
+    pub async fn load(db: &DbPool) -> Result<Self, String> {
+        let instance_id = get_setting(db, INSTANCE_ID_KEY)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Instance ID has not been initialized".to_string())?;
+
+        let signing_key = get_setting(db, SIGNING_KEY_KEY)
+            .await
+            .map_err(|e| e.to_string())?
+            .map(|key| decode_hex(&key))
+            .transpose()?;
+
+        Ok(Self { instance_id, signing_key })
+    }
+
+    pub fn signing_enabled(&self) -> bool {
+        self.signing_key.is_some()
+    }
+
+    fn mac(key: &[u8], code_id: &str, instance_id: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(format!("v{}:{}:{}", PAYLOAD_VERSION, code_id, instance_id).as_bytes());
+        mac
+    }
+
+    fn sign(&self, key: &[u8], code_id: &str, instance_id: &str) -> String {
+        encode_hex(&Self::mac(key, code_id, instance_id).finalize().into_bytes())[..SIGNATURE_HEX_LEN].to_string()
+    }
+
+    /// Compare a signature in constant time
+    fn verify(key: &[u8], code_id: &str, instance_id: &str, signature: Option<&str>) -> bool {
+        let Some(signature) = signature.filter(|signature| signature.len() == SIGNATURE_HEX_LEN) else {
+            return false;
+        };
+        match decode_hex(signature) {
+            Ok(tag) => Self::mac(key, code_id, instance_id).verify_truncated_left(&tag).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// QR payload printed on a label for a location code
+    pub fn payload(&self, code_id: &str) -> String {
+        let mut payload = format!("{}://loc/{}?v={}&db={}", SCHEME, percent_encode(code_id), PAYLOAD_VERSION, self.instance_id);
+        if let Some(key) = &self.signing_key {
+            payload.push_str("&sig=");
+            payload.push_str(&self.sign(key, code_id, &self.instance_id));
+        }
+        payload
+    }
+
+    /// Check a scanned payload and return the location code it refers to.
+    /// Links from another installation are rejected. While signing is enabled, so are bare
+    /// codes from older labels, links without an instance ID and unsigned or forged links.
+    pub fn resolve(&self, text: &str) -> Result<String, String> {
+        let parsed = parse(text)?;
+        if parsed.version == 0 {
+            if self.signing_enabled() {
+                return Err("Labels without a signed QR link are not accepted while signing is enabled; reprint the label".to_string());
+            }
+            return Ok(parsed.code_id);
+        }
+
+        if parsed.version > PAYLOAD_VERSION {
+            return Err(format!("QR code version {} is not supported by this version of the app", parsed.version));
+        }
+
+        let instance_id = match parsed.instance_id.as_deref() {
+            Some(instance_id) => instance_id,
+            None if self.signing_enabled() => return Err("QR link has no instance ID".to_string()),
+            None => &self.instance_id,
+        };
+        if instance_id != self.instance_id {
+            return Err(format!("This QR code belongs to another installation ({})", instance_id));
+        }
+
+        if let Some(key) = &self.signing_key {
+            if !Self::verify(key, &parsed.code_id, instance_id, parsed.signature.as_deref()) {
+                return Err("QR code signature is missing or invalid".to_string());
+            }
+        }
+
+        Ok(parsed.code_id)
+    }
+}
+
+/// Turn QR payload signing on with a fresh random key, or off. Labels printed with
+/// the previous key no longer resolve and have to be reprinted.
+pub async fn set_signing(db: &DbPool, enabled: bool) -> Result<(), String> {
+    if enabled {
+        let key = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+        set_setting(db, SIGNING_KEY_KEY, &key).await.map_err(|e| e.to_string())
+    } else {
+        delete_setting(db, SIGNING_KEY_KEY).await.map_err(|e| e.to_string())
+    }
+}
+
+/// Reserve `count` new codes for pre-printed labels. Returns the batch ID and the codes.
+pub async fn reserve_batch(db: &DbPool, count: u32) -> Result<(String, Vec<String>), String> {
+    if count == 0 || count > MAX_RESERVED_BATCH {
+        return Err(format!("Number of codes must be between 1 and {}", MAX_RESERVED_BATCH));
+    }
+
+    let batch_id = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+    let codes: Vec<String> = (0..count).map(|_| new_code_id()).collect();
+
+    let mut tx = begin(db).await.map_err(|e| e.to_string())?;
+    for code in &codes {
+        tx_execute(
+            &mut tx,
+            "INSERT INTO reserved_codes (code, batch_id) VALUES (?1, ?2)",
+            vec![Some(code.clone()), Some(batch_id.clone())],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok((batch_id, codes))
+}
+
+/// Mark a reserved code as used by a location. Fails if the code was never reserved
+/// or already belongs to another location.
+pub async fn claim_reserved_code(tx: &mut DbTransaction, code_id: &str, location_id: i32) -> Result<(), String> {
+    let row = tx_query_one(tx, "SELECT location_id, used_at FROM reserved_codes WHERE code = ?1", vec![code_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Code {} is not a reserved code", code_id))?;
+
+    let used_at: Option<String> = row.try_get("used_at").ok().flatten();
+    let owner: Option<i32> = row.try_get("location_id").ok().flatten();
+    if used_at.is_some() && owner != Some(location_id) {
+        return Err(format!("Reserved code {} has already been used", code_id));
+    }
+
+    tx_execute(
+        tx,
+        "UPDATE reserved_codes SET location_id = ?1, used_at = COALESCE(used_at, CURRENT_TIMESTAMP) WHERE code = ?2",
+        vec![Some(location_id.to_string()), Some(code_id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Make `code_id` the current code of a location. The location's previous code is kept
+/// as a retired alias so old labels keep resolving. Fails if the code already identifies
+/// another location, currently or as a retired code.
+pub async fn bind_code(
+    tx: &mut DbTransaction,
+    location_id: i32,
+    code_id: &str,
+    reason: Option<String>,
+) -> Result<(), String> {
+    let code_id = code_id.trim();
+    if code_id.is_empty() {
+        return Err("Code is empty".to_string());
+    }
+
+    let current = tx_query_one(tx, "SELECT qr_code_id FROM locations WHERE id = ?1", vec![location_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Location {} not found", location_id))?;
+    let current: Option<String> = current.try_get("qr_code_id").ok();
+
+    if current.as_deref() == Some(code_id) {
+        return Ok(());
+    }
+
+    let owner = tx_query_one(tx, "SELECT name FROM locations WHERE qr_code_id = ?1", vec![code_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(owner) = owner {
+        return Err(format!("Code {} is already used by location {}", code_id, owner.get::<String, _>("name")));
+    }
+
+    let alias = tx_query_one(
+        tx,
+        "SELECT a.location_id, l.name FROM location_code_aliases a JOIN locations l ON l.id = a.location_id WHERE a.code = ?1",
+        vec![code_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    if let Some(alias) = alias {
+        if alias.get::<i32, _>("location_id") != location_id {
+            return Err(format!("Code {} is a retired code of location {}", code_id, alias.get::<String, _>("name")));
+        }
+        // Reactivating one of the location's own retired codes
+        tx_execute(tx, "DELETE FROM location_code_aliases WHERE code = ?1", vec![Some(code_id.to_string())])
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    if let Some(current) = current {
+        tx_execute(
+            tx,
+            "INSERT INTO location_code_aliases (code, location_id, reason) VALUES (?1, ?2, ?3)",
+            vec![Some(current), Some(location_id.to_string()), reason],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx_execute(
+        tx,
+        "UPDATE locations SET qr_code_id = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        vec![Some(code_id.to_string()), Some(location_id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // A pre-printed code from a reserved batch is now taken
+    tx_execute(
+        tx,
+        "UPDATE reserved_codes SET location_id = ?1, used_at = CURRENT_TIMESTAMP WHERE code = ?2 AND used_at IS NULL",
+        vec![Some(location_id.to_string()), Some(code_id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Escape everything but unreserved URI characters, so codes containing `?`, `&`, `/` or
+/// `#` stay in the path of a QR link
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> Result<String, String> {
+    let invalid = || format!("Invalid QR link code: {}", text);
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = text.get(i + 1..i + 3).filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit())).ok_or_else(invalid)?;
+            decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| invalid())
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return Err("Invalid QR signing key".to_string());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| "Invalid QR signing key".to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(signed: bool) -> CodeContext {
+        CodeContext {
+            instance_id: "0123456789abcdef".to_string(),
+            signing_key: signed.then(|| vec![7; 32]),
+        }
+    }
+
+    #[test]
+    fn codes_with_reserved_characters_round_trip() {
+        for signed in [false, true] {
+            let codes = context(signed);
+            for code in ["LOC-1a2b3c4d", "a?b&c=d", "shelf/2#3", "货架 A%"] {
+                let payload = codes.payload(code);
+                assert_eq!(payload.matches('?').count(), 1, "{}", payload);
+                assert!(!payload.contains('#'), "{}", payload);
+                assert_eq!(codes.resolve(&payload), Ok(code.to_string()));
+            }
+        }
+    }
+
+    #[test]
+    fn forged_or_malformed_signatures_are_rejected() {
+        let codes = context(true);
+        let payload = codes.payload("LOC-1");
+        let (unsigned, signature) = payload.split_once("&sig=").unwrap();
+
+        assert!(codes.resolve(&payload.replace(signature, &signature.to_uppercase())).is_ok());
+        assert!(codes.resolve(unsigned).is_err());
+        assert!(codes.resolve(&format!("{}&sig={}", unsigned, &signature[..8])).is_err());
+        assert!(codes.resolve(&format!("{}&sig=zz{}", unsigned, &signature[2..])).is_err());
+        assert!(codes.resolve(&payload.replace("LOC-1", "LOC-2")).is_err());
+        assert!(context(false).resolve("ics://loc/a%2?v=1").is_err());
+        assert!(context(false).resolve("ics://loc/a%+1?v=1").is_err());
+    }
+}
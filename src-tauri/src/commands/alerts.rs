@@ -0,0 +1,56 @@
+use crate::alerts;
+use crate::database::{models::StockAlertSettings, query_all, execute};
+use crate::database::DbPool;
+use tauri::State;
+use sqlx::Row;
+
+#[tauri::command]
+pub async fn get_stock_alert_settings(
+    db: State<'_, DbPool>,
+) -> Result<StockAlertSettings, String> {
+    alerts::load_settings(&db).await
+}
+
+#[tauri::command]
+pub async fn set_stock_alert_settings(
+    db: State<'_, DbPool>,
+    settings: StockAlertSettings,
+) -> Result<(), String> {
+    if settings.digest_hour > 23 {
+        return Err("Digest hour must be between 0 and 23".to_string());
+    }
+
+    alerts::save_settings(&db, &settings).await
+}
+
+/// Stop or resume low-stock alerts for an item
+#[tauri::command]
+pub async fn set_item_alerts_muted(
+    db: State<'_, DbPool>,
+    item_id: i32,
+    muted: bool,
+) -> Result<(), String> {
+    let sql = if muted {
+        "INSERT OR IGNORE INTO muted_items (item_id) VALUES (?1)"
+    } else {
+        "DELETE FROM muted_items WHERE item_id = ?1"
+    };
+
+    execute(&db, sql, vec![item_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// IDs of items whose low-stock alerts are muted
+#[tauri::command]
+pub async fn get_muted_items(
+    db: State<'_, DbPool>,
+) -> Result<Vec<i32>, String> {
+    let result = query_all(&db, "SELECT item_id FROM muted_items ORDER BY item_id", vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(result.iter().map(|row| row.get("item_id")).collect())
+}
@@ -0,0 +1,254 @@
+use crate::bom::{self, CatalogItem};
+use crate::database::{begin, models::{BomImportLine, BomImportPreview}, query_all, tx_execute, tx_query_one};
+use crate::database::DbPool;
+use crate::stock;
+use crate::suppliers;
+use tauri::State;
+use sqlx::Row;
+use std::collections::HashMap;
+
+async fn load_catalog(db: &DbPool) -> Result<Vec<CatalogItem>, String> {
+    let rows = query_all(
+        db,
+        "SELECT i.id, i.name, i.category, i.specifications, i.mpn, m.name AS manufacturer
+         FROM items i LEFT JOIN manufacturers m ON m.id = i.manufacturer_id
+         WHERE i.deleted_at IS NULL ORDER BY i.name",
+        vec![],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let sku_rows = query_all(db, "SELECT item_id, sku FROM item_offers WHERE sku IS NOT NULL", vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut skus: HashMap<i32, Vec<String>> = HashMap::new();
+    for row in &sku_rows {
+        skus.entry(row.get("item_id")).or_default().push(row.get("sku"));
+    }
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let id: i32 = row.get("id");
+            let name: String = row.get("name");
+            let mpn: Option<String> = row.try_get("mpn").ok().flatten();
+            let texts = [
+                row.try_get("category").ok().flatten(),
+                row.try_get("specifications").ok().flatten(),
+                row.try_get("manufacturer").ok().flatten(),
+            ];
+            CatalogItem::new(id, &name, &texts, mpn.as_deref(), skus.get(&id).map(Vec::as_slice).unwrap_or_default())
+        })
+        .collect())
+}
+
+/// Read a KiCad or JLCPCB BOM (CSV) and match its lines to items without changing anything
+#[tauri::command]
+pub async fn preview_bom_import(
+    db: State<'_, DbPool>,
+    content: String,
+) -> Result<BomImportPreview, String> {
+    let mut parsed = bom::parse_bom(&content)?;
+    let catalog = load_catalog(&db).await?;
+    bom::match_lines(&mut parsed.lines, &catalog);
+
+    let matched = parsed.lines.iter().filter(|line| line.item_id.is_some()).count() as i32;
+    Ok(BomImportPreview {
+        format: parsed.format,
+        unmatched: parsed.lines.len() as i32 - matched,
+        matched,
+        lines: parsed.lines,
+    })
+}
+
+/// Name and specifications of an item created from a BOM line. The supplier part number
+/// goes into the specifications, labelled with its column, unless it becomes an offer.
+fn item_details(line: &BomImportLine, with_supplier_part: bool) -> (String, Option<String>) {
+    let name = [&line.value, &line.mpn, &line.description]
+        .into_iter()
+        .flatten()
+        .next()
+        .cloned()
+        .unwrap_or_else(|| format!("BOM line {}", line.row));
+
+    let specifications: Vec<String> = [
+        line.footprint.as_ref().map(|footprint| footprint.rsplit(':').next().unwrap_or(footprint).to_string()),
+        line.description.clone().filter(|description| *description != name),
+        line.supplier_part.as_ref().filter(|_| with_supplier_part).map(|part| match &line.supplier_part_column {
+            Some(column) => format!("{}: {}", column, part),
+            None => part.clone(),
+        }),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    (name, (!specifications.is_empty()).then(|| specifications.join(", ")))
+}
+
+/// Create an item, without stock, for every line that has none. Lines describing the same
+/// part share one new item, which gets the line's MPN and manufacturer. With `supplier_id`,
+/// supplier part numbers (such as LCSC codes) become offers from that supplier. Returns the
+/// lines with the new items filled in.
+#[tauri::command]
+pub async fn create_items_from_bom(
+    db: State<'_, DbPool>,
+    lines: Vec<BomImportLine>,
+    category: Option<String>,
+    location_id: Option<i32>,
+    supplier_id: Option<i32>,
+) -> Result<Vec<BomImportLine>, String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    if let Some(supplier_id) = supplier_id {
+        tx_query_one(&mut tx, "SELECT id FROM suppliers WHERE id = ?1", vec![supplier_id.to_string()])
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Supplier not found".to_string())?;
+    }
+
+    if let Some(location_id) = location_id {
+        tx_query_one(&mut tx, "SELECT id FROM locations WHERE id = ?1", vec![location_id.to_string()])
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Location not found".to_string())?;
+    }
+
+    let mut created: HashMap<(String, Option<String>, Option<String>), i32> = HashMap::new();
+    let mut result = Vec::with_capacity(lines.len());
+    for mut line in lines {
+        if line.item_id.is_some() {
+            result.push(line);
+            continue;
+        }
+
+        let (name, specifications) = item_details(&line, supplier_id.is_none());
+        let key = (name.clone(), specifications.clone(), line.mpn.clone());
+        let item_id = match created.get(&key) {
+            Some(id) => *id,
+            None => {
+                let manufacturer_id = match &line.manufacturer {
+                    Some(manufacturer) => Some(suppliers::ensure_manufacturer(&mut tx, manufacturer).await?),
+                    None => None,
+                };
+                tx_execute(
+                    &mut tx,
+                    "INSERT INTO items (name, category, specifications, quantity, location_id, mpn, manufacturer_id) VALUES (?1, ?2, ?3, 0, ?4, ?5, ?6)",
+                    vec![
+                        Some(name.clone()),
+                        category.clone(),
+                        specifications,
+                        location_id.map(|id| id.to_string()),
+                        line.mpn.clone(),
+                        manufacturer_id.map(|id| id.to_string()),
+                    ],
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+
+                let id: i32 = tx_query_one(&mut tx, "SELECT last_insert_rowid() as id", vec![])
+                    .await
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| "Failed to get inserted ID".to_string())?
+                    .get("id");
+                stock::find_or_create(&mut tx, id, location_id).await?;
+                if let (Some(supplier_id), Some(sku)) = (supplier_id, &line.supplier_part) {
+                    tx_execute(
+                        &mut tx,
+                        "INSERT INTO item_offers (item_id, supplier_id, sku, currency) VALUES (?1, ?2, ?3, ?4)",
+                        vec![
+                            Some(id.to_string()),
+                            Some(supplier_id.to_string()),
+                            Some(sku.clone()),
+                            Some(suppliers::DEFAULT_CURRENCY.to_string()),
+                        ],
+                    )
+                    .await
+                    .map_err(|e| e.to_string())?;
+                }
+                created.insert(key, id);
+                id
+            }
+        };
+
+        line.item_id = Some(item_id);
+        line.item_name = Some(name);
+        line.match_method = Some("created".to_string());
+        line.score = 1.0;
+        result.push(line);
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(result)
+}
+
+/// Add matched BOM lines to a project's BOM. Lines for the same item are combined; lines
+/// without an item are skipped. With `replace`, the project's existing lines are removed
+/// first. Returns the number of BOM lines written.
+#[tauri::command]
+pub async fn apply_bom_import(
+    db: State<'_, DbPool>,
+    project_id: i32,
+    lines: Vec<BomImportLine>,
+    replace: Option<bool>,
+) -> Result<i32, String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    tx_query_one(&mut tx, "SELECT id FROM projects WHERE id = ?1", vec![project_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Project not found".to_string())?;
+
+    // Quantity and references per item, in file order
+    let mut merged: Vec<(i32, i32, Vec<String>)> = Vec::new();
+    for line in &lines {
+        let Some(item_id) = line.item_id else { continue };
+        if line.quantity <= 0 {
+            return Err(format!("Line {} has no quantity", line.row));
+        }
+        let position = match merged.iter().position(|(id, _, _)| *id == item_id) {
+            Some(position) => position,
+            None => {
+                merged.push((item_id, 0, Vec::new()));
+                merged.len() - 1
+            }
+        };
+        merged[position].1 = merged[position].1
+            .checked_add(line.quantity)
+            .ok_or_else(|| format!("Line {}: quantity is too large", line.row))?;
+        merged[position].2.extend(line.reference.clone());
+    }
+
+    if replace.unwrap_or(false) {
+        tx_execute(&mut tx, "DELETE FROM project_bom_lines WHERE project_id = ?1", vec![Some(project_id.to_string())])
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    for (item_id, quantity, references) in &merged {
+        tx_query_one(&mut tx, "SELECT id FROM items WHERE id = ?1 AND deleted_at IS NULL", vec![item_id.to_string()])
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Item {} not found", item_id))?;
+
+        tx_execute(
+            &mut tx,
+            "INSERT INTO project_bom_lines (project_id, item_id, quantity_per_unit, reference) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (project_id, item_id) DO UPDATE SET quantity_per_unit = excluded.quantity_per_unit,
+                reference = excluded.reference",
+            vec![
+                Some(project_id.to_string()),
+                Some(item_id.to_string()),
+                Some(quantity.to_string()),
+                (!references.is_empty()).then(|| references.join(", ")),
+            ],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(merged.len() as i32)
+}
@@ -0,0 +1,272 @@
+use crate::alerts;
+use crate::database::{begin, models::{BulkAdjustment, BulkItemResult}, tx_execute, tx_query_one, DbTransaction};
+use crate::database::DbPool;
+use crate::stock;
+use crate::tags;
+use tauri::{AppHandle, State};
+use std::collections::HashMap;
+
+// Each item runs inside a savepoint, so a failing item is rolled back on its own while the
+// others are committed together at the end.
+
+async fn begin_item(tx: &mut DbTransaction) -> Result<(), String> {
+    tx_execute(tx, "SAVEPOINT bulk_item", vec![])
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+async fn finish_item(tx: &mut DbTransaction, item_id: i32, outcome: Result<(), String>) -> Result<BulkItemResult, String> {
+    if outcome.is_err() {
+        tx_execute(tx, "ROLLBACK TO bulk_item", vec![])
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    tx_execute(tx, "RELEASE bulk_item", vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(BulkItemResult {
+        item_id,
+        success: outcome.is_ok(),
+        error: outcome.err(),
+    })
+}
+
+/// Fails unless the item exists and isn't deleted
+async fn check_item(tx: &mut DbTransaction, item_id: i32) -> Result<(), String> {
+    tx_query_one(tx, "SELECT id FROM items WHERE id = ?1 AND deleted_at IS NULL", vec![item_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|_| ())
+        .ok_or_else(|| "Item not found".to_string())
+}
+
+/// Run an update that should change exactly one item, failing with `not_found` otherwise
+async fn execute_item_update(tx: &mut DbTransaction, sql: &str, params: Vec<Option<String>>, not_found: &str) -> Result<(), String> {
+    let affected = tx_execute(tx, sql, params)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if affected == 0 {
+        return Err(not_found.to_string());
+    }
+    Ok(())
+}
+
+/// Move all stock of an item into one location, logged as transfers with `notes`
+async fn move_item(tx: &mut DbTransaction, item_id: i32, location_id: Option<i32>, notes: Option<String>) -> Result<(), String> {
+    check_item(tx, item_id).await?;
+
+    // Moved stock rows are deleted, which would drop their counts from an open stocktake
+    let rows = stock::item_rows(tx, item_id).await?;
+    for row in &rows {
+        stock::check_not_counted(tx, row.id).await?;
+    }
+    if rows.is_empty() {
+        stock::find_or_create(tx, item_id, location_id).await?;
+    }
+    for row in rows.iter().filter(|row| row.location_id != location_id) {
+        if row.quantity > 0 {
+            stock::transfer(tx, row.id, location_id, row.quantity, notes.clone()).await?;
+        }
+        tx_execute(tx, "DELETE FROM item_stock WHERE id = ?1", vec![Some(row.id.to_string())])
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    execute_item_update(
+        tx,
+        "UPDATE items SET location_id = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+        vec![Some(item_id.to_string()), location_id.map(|id| id.to_string())],
+        "Item not found",
+    )
+    .await
+}
+
+/// Move every item, with all of its stock, to `location_id` (`None` to unassign). `notes`
+/// is recorded on the transfers.
+#[tauri::command]
+pub async fn bulk_move_items(
+    db: State<'_, DbPool>,
+    item_ids: Vec<i32>,
+    location_id: Option<i32>,
+    notes: Option<String>,
+) -> Result<Vec<BulkItemResult>, String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    if let Some(location_id) = location_id {
+        tx_query_one(&mut tx, "SELECT id FROM locations WHERE id = ?1", vec![location_id.to_string()])
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Location not found".to_string())?;
+    }
+
+    let mut results = Vec::new();
+    for item_id in item_ids {
+        begin_item(&mut tx).await?;
+        let outcome = move_item(&mut tx, item_id, location_id, notes.clone()).await;
+        results.push(finish_item(&mut tx, item_id, outcome).await?);
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn bulk_set_category(
+    db: State<'_, DbPool>,
+    item_ids: Vec<i32>,
+    category: Option<String>,
+) -> Result<Vec<BulkItemResult>, String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for item_id in item_ids {
+        begin_item(&mut tx).await?;
+        let outcome = execute_item_update(
+            &mut tx,
+            "UPDATE items SET category = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?1 AND deleted_at IS NULL",
+            vec![Some(item_id.to_string()), category.clone()],
+            "Item not found",
+        )
+        .await;
+        results.push(finish_item(&mut tx, item_id, outcome).await?);
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(results)
+}
+
+/// Change the quantities of many items, logged like `update_quantity`. An item that would
+/// go negative fails on its own without affecting the others.
+#[tauri::command]
+pub async fn bulk_adjust_quantities(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    adjustments: Vec<BulkAdjustment>,
+    operation_type: String,
+) -> Result<Vec<BulkItemResult>, String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    // Item totals before their first change, for the stock alerts
+    let mut previous_totals: HashMap<i32, i32> = HashMap::new();
+    for adjustment in &adjustments {
+        begin_item(&mut tx).await?;
+        let outcome = async {
+            check_item(&mut tx, adjustment.item_id).await?;
+            let stock_id = stock::resolve(&mut tx, adjustment.item_id, adjustment.stock_id).await?;
+            stock::adjust(&mut tx, stock_id, adjustment.change, &operation_type, "manual", None).await
+        }
+        .await;
+        if let Ok(change) = &outcome {
+            previous_totals.entry(change.item_id).or_insert(change.previous_total);
+        }
+        results.push(finish_item(&mut tx, adjustment.item_id, outcome.map(|_| ())).await?);
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    for (item_id, previous_total) in previous_totals {
+        if let Err(e) = alerts::check_item(&app, &db, item_id, previous_total).await {
+            eprintln!("Failed to check stock alert for item {}: {}", item_id, e);
+        }
+    }
+
+    Ok(results)
+}
+
+/// Move items to the trash. They disappear from lists, reports and stocktakes but keep
+/// their stock and history until restored.
+#[tauri::command]
+pub async fn bulk_delete_items(
+    db: State<'_, DbPool>,
+    item_ids: Vec<i32>,
+) -> Result<Vec<BulkItemResult>, String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for item_id in item_ids {
+        begin_item(&mut tx).await?;
+        let outcome = execute_item_update(
+            &mut tx,
+            "UPDATE items SET deleted_at = CURRENT_TIMESTAMP WHERE id = ?1 AND deleted_at IS NULL",
+            vec![Some(item_id.to_string())],
+            "Item not found or already deleted",
+        )
+        .await;
+        results.push(finish_item(&mut tx, item_id, outcome).await?);
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn bulk_restore_items(
+    db: State<'_, DbPool>,
+    item_ids: Vec<i32>,
+) -> Result<Vec<BulkItemResult>, String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for item_id in item_ids {
+        begin_item(&mut tx).await?;
+        let outcome = execute_item_update(
+            &mut tx,
+            "UPDATE items SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            vec![Some(item_id.to_string())],
+            "Item not found or not deleted",
+        )
+        .await;
+        results.push(finish_item(&mut tx, item_id, outcome).await?);
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(results)
+}
+
+/// Add tags to every item, creating tags that don't exist yet. Tag names are matched
+/// case-insensitively.
+#[tauri::command]
+pub async fn bulk_tag_items(
+    db: State<'_, DbPool>,
+    item_ids: Vec<i32>,
+    tags: Vec<String>,
+) -> Result<Vec<BulkItemResult>, String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    let tag_ids = tags::ensure(&mut tx, &tags).await?;
+    if tag_ids.is_empty() {
+        return Err("No tags given".to_string());
+    }
+
+    let mut results = Vec::new();
+    for item_id in item_ids {
+        begin_item(&mut tx).await?;
+        let outcome = async {
+            check_item(&mut tx, item_id).await?;
+            for tag_id in &tag_ids {
+                tx_execute(
+                    &mut tx,
+                    "INSERT OR IGNORE INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+                    vec![Some(item_id.to_string()), Some(tag_id.to_string())],
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+            }
+            Ok(())
+        }
+        .await;
+        results.push(finish_item(&mut tx, item_id, outcome).await?);
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(results)
+}
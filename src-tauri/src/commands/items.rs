@@ -1,19 +1,90 @@
-use crate::database::{models::{Item, ItemFilter, ItemInput}, query_all, query_one, execute, execute_with_optional};
+use crate::database::{begin, models::{Item, ItemFilter, ItemInput, ItemStock, StockTransfer}, query_all, execute, tx_execute, tx_query_all, tx_query_one};
+use crate::alerts;
 use crate::database::DbPool;
-use tauri::State;
+use crate::stock::{self, STOCK_SELECT};
+use crate::tags;
+use tauri::{AppHandle, State};
 use sqlx::Row;
+use std::collections::HashMap;
+
+/// Item columns, including the manufacturer's name
+pub const ITEM_SELECT: &str = "SELECT id, name, category, specifications, quantity, unit, location_id, min_quantity, notes, image_path, created_at, updated_at, deleted_at, mpn, manufacturer_id,
+        (SELECT m.name FROM manufacturers m WHERE m.id = items.manufacturer_id) AS manufacturer
+     FROM items";
+
+/// Item from a row selected with `ITEM_SELECT`, without its stock and tags
+pub fn row_to_item(row: &sqlx::sqlite::SqliteRow) -> Item {
+    Item {
+        id: row.get("id"),
+        name: row.get("name"),
+        category: row.try_get("category").ok(),
+        specifications: row.try_get("specifications").ok(),
+        quantity: row.get("quantity"),
+        unit: row.try_get("unit").ok(),
+        location_id: row.try_get("location_id").ok(),
+        min_quantity: row.try_get("min_quantity").ok(),
+        notes: row.try_get("notes").ok(),
+        image_path: row.try_get("image_path").ok(),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        stock: Vec::new(),
+        tags: Vec::new(),
+        deleted_at: row.try_get("deleted_at").ok().flatten(),
+        mpn: row.try_get("mpn").ok().flatten(),
+        manufacturer_id: row.try_get("manufacturer_id").ok().flatten(),
+        manufacturer: row.try_get("manufacturer").ok().flatten(),
+    }
+}
+
+/// Run a query built on `ITEM_SELECT` and fill in each item's stock and tags
+pub async fn load_items(db: &DbPool, sql: &str, params: Vec<String>) -> Result<Vec<Item>, String> {
+    let result = query_all(db, sql, params)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let stock_rows = query_all(db, &format!("{} ORDER BY l.name, s.id", STOCK_SELECT), vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut stock_by_item: HashMap<i32, Vec<ItemStock>> = HashMap::new();
+    for row in &stock_rows {
+        let stock = stock::row_to_stock(row);
+        stock_by_item.entry(stock.item_id).or_default().push(stock);
+    }
+
+    let tag_rows = query_all(db, "SELECT it.item_id, t.name FROM item_tags it JOIN tags t ON t.id = it.tag_id ORDER BY t.name", vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut tags_by_item: HashMap<i32, Vec<String>> = HashMap::new();
+    for row in &tag_rows {
+        tags_by_item.entry(row.get("item_id")).or_default().push(row.get("name"));
+    }
+
+    Ok(result
+        .iter()
+        .map(|row| {
+            let mut item = row_to_item(row);
+            item.stock = stock_by_item.remove(&item.id).unwrap_or_default();
+            item.tags = tags_by_item.remove(&item.id).unwrap_or_default();
+            item
+        })
+        .collect())
+}
 
 #[tauri::command]
 pub async fn get_items(
     db: State<'_, DbPool>,
     filter: Option<ItemFilter>,
 ) -> Result<Vec<Item>, String> {
-    let mut query = String::from(
-        "SELECT id, name, category, specifications, quantity, unit, location_id, min_quantity, notes, image_path, created_at, updated_at FROM items WHERE 1=1"
-    );
+    let mut query = format!("{} WHERE 1=1", ITEM_SELECT);
     let mut params: Vec<String> = vec![];
     let mut param_count = 0;
 
+    if filter.as_ref().and_then(|f| f.deleted).unwrap_or(false) {
+        query.push_str(" AND deleted_at IS NOT NULL");
+    } else {
+        query.push_str(" AND deleted_at IS NULL");
+    }
+
     if let Some(f) = &filter {
         if let Some(category) = &f.category {
             param_count += 1;
@@ -21,70 +92,85 @@ pub async fn get_items(
             params.push(category.clone());
         }
         if let Some(location_id) = f.location_id {
+            // Items with any stock in the location
             param_count += 1;
-            query.push_str(&format!(" AND location_id = ?{}", param_count));
+            query.push_str(&format!(" AND id IN (SELECT item_id FROM item_stock WHERE location_id = ?{})", param_count));
             params.push(location_id.to_string());
         }
         if let Some(search) = &f.search {
             param_count += 1;
-            query.push_str(&format!(" AND (name LIKE ?{} OR specifications LIKE ?{})", param_count, param_count));
+            query.push_str(&format!(" AND (name LIKE ?{0} OR specifications LIKE ?{0} OR mpn LIKE ?{0})", param_count));
             params.push(format!("%{}%", search));
         }
+        // Tag names are matched case-insensitively through the column's collation
+        for (names, condition) in [(&f.tags_any, "any"), (&f.tags_all, "all"), (&f.tags_none, "none")] {
+            let names = tags::normalize(names.as_deref().unwrap_or_default());
+            if names.is_empty() {
+                continue;
+            }
+            let placeholders: Vec<String> = names
+                .iter()
+                .map(|_| {
+                    param_count += 1;
+                    format!("?{}", param_count)
+                })
+                .collect();
+            let tagged = format!(
+                "SELECT it.item_id FROM item_tags it JOIN tags t ON t.id = it.tag_id WHERE t.name IN ({})",
+                placeholders.join(", ")
+            );
+            match condition {
+                "any" => query.push_str(&format!(" AND id IN ({})", tagged)),
+                "all" => query.push_str(&format!(
+                    " AND id IN ({} GROUP BY it.item_id HAVING COUNT(DISTINCT it.tag_id) = {})",
+                    tagged,
+                    names.len()
+                )),
+                _ => query.push_str(&format!(" AND id NOT IN ({})", tagged)),
+            }
+            params.extend(names);
+        }
     }
 
     query.push_str(" ORDER BY name");
 
-    let result = query_all(&db, &query, params)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let items: Vec<Item> = result
-        .iter()
-        .map(|row| Item {
-            id: row.get("id"),
-            name: row.get("name"),
-            category: row.try_get("category").ok(),
-            specifications: row.try_get("specifications").ok(),
-            quantity: row.get("quantity"),
-            unit: row.try_get("unit").ok(),
-            location_id: row.try_get("location_id").ok(),
-            min_quantity: row.try_get("min_quantity").ok(),
-            notes: row.try_get("notes").ok(),
-            image_path: row.try_get("image_path").ok(),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
-        })
-        .collect();
-
-    Ok(items)
+    load_items(&db, &query, params).await
 }
 
+/// Create an item with its initial quantity stocked in `location_id`
 #[tauri::command]
 pub async fn create_item(
     db: State<'_, DbPool>,
     item: ItemInput,
 ) -> Result<i32, String> {
-    let _result = execute_with_optional(
-        &db,
-        "INSERT INTO items (name, category, specifications, quantity, unit, location_id, min_quantity, notes, image_path) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    if item.quantity < 0 {
+        return Err("Quantity cannot be negative".to_string());
+    }
+
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    tx_execute(
+        &mut tx,
+        "INSERT INTO items (name, category, specifications, quantity, unit, location_id, min_quantity, notes, image_path, mpn, manufacturer_id) VALUES (?1, ?2, ?3, 0, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
         vec![
             Some(item.name.clone()),
             item.category.clone(),
             item.specifications.clone(),
-            Some(item.quantity.to_string()),
             item.unit.clone(),
             item.location_id.map(|v| v.to_string()),
             item.min_quantity.map(|v| v.to_string()),
             item.notes.clone(),
             item.image_path.clone(),
+            item.mpn.clone(),
+            item.manufacturer_id.map(|v| v.to_string()),
         ],
     )
     .await
     .map_err(|e| e.to_string())?;
 
     // Get the last inserted id
-    let result = query_one(
-        &db,
+    let result = tx_query_one(
+        &mut tx,
         "SELECT last_insert_rowid() as id",
         vec![],
     )
@@ -96,118 +182,288 @@ pub async fn create_item(
         None => return Err("Failed to get inserted ID".to_string()),
     };
 
-    // Log initial quantity
-    execute(
-        &db,
-        "INSERT INTO inventory_log (item_id, quantity_change, quantity_after, operation_type, source) VALUES (?1, ?2, ?3, ?4, ?5)",
-        vec![
-            id.to_string(),
-            item.quantity.to_string(),
-            item.quantity.to_string(),
-            "add".to_string(),
-            "manual".to_string(),
-        ],
-    )
-    .await
-    .map_err(|e| e.to_string())?;
+    // Stock the initial quantity, which also logs it
+    let stock_id = stock::find_or_create(&mut tx, id, item.location_id).await?;
+    stock::adjust(&mut tx, stock_id, item.quantity, "add", "manual", None).await?;
+
+    if let Some(names) = &item.tags {
+        tags::set_item_tags(&mut tx, id, names).await?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
 
     Ok(id)
 }
 
+/// Update an item's details. `location_id` is the item's main location. While the item is
+/// stocked in a single place, its stock follows `location_id` and `quantity`; once it is
+/// stocked in several locations, quantities have to be changed per location.
 #[tauri::command]
 pub async fn update_item(
+    app: AppHandle,
     db: State<'_, DbPool>,
     id: i32,
     item: ItemInput,
 ) -> Result<(), String> {
-    execute_with_optional(
-        &db,
-        "UPDATE items SET name = ?1, category = ?2, specifications = ?3, quantity = ?4, unit = ?5, location_id = ?6, min_quantity = ?7, notes = ?8, image_path = ?9, updated_at = CURRENT_TIMESTAMP WHERE id = ?10",
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    // Stock row whose quantity the form edits, with its current quantity
+    let rows = stock::item_rows(&mut tx, id).await?;
+    let edited = match rows.as_slice() {
+        [] => Some((stock::find_or_create(&mut tx, id, item.location_id).await?, 0)),
+        [row] => {
+            if row.location_id != item.location_id {
+                stock::check_not_counted(&mut tx, row.id).await?;
+            }
+            tx_execute(
+                &mut tx,
+                "UPDATE item_stock SET location_id = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                vec![item.location_id.map(|v| v.to_string()), Some(row.id.to_string())],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            Some((row.id, row.quantity))
+        }
+        rows => {
+            let total: i32 = rows.iter().map(|row| row.quantity).sum();
+            if item.quantity != total {
+                return Err(format!(
+                    "Item is stocked in {} locations; change the quantity of each location instead",
+                    rows.len()
+                ));
+            }
+            None
+        }
+    };
+
+    // A quantity typed into the form is logged like any other stock change
+    let mut previous_total = None;
+    if let Some((stock_id, quantity)) = edited {
+        if item.quantity != quantity {
+            let change = stock::adjust(&mut tx, stock_id, item.quantity - quantity, "adjust", "edit", None).await?;
+            previous_total = Some(change.previous_total);
+        }
+    }
+
+    tx_execute(
+        &mut tx,
+        "UPDATE items SET name = ?1, category = ?2, specifications = ?3, unit = ?4, location_id = ?5, min_quantity = ?6, notes = ?7, image_path = ?8, mpn = ?9, manufacturer_id = ?10, updated_at = CURRENT_TIMESTAMP WHERE id = ?11",
         vec![
             Some(item.name),
             item.category,
             item.specifications,
-            Some(item.quantity.to_string()),
             item.unit,
             item.location_id.map(|v| v.to_string()),
             item.min_quantity.map(|v| v.to_string()),
             item.notes,
             item.image_path,
+            item.mpn,
+            item.manufacturer_id.map(|v| v.to_string()),
             Some(id.to_string()),
         ],
     )
     .await
     .map_err(|e| e.to_string())?;
 
+    stock::sync_total(&mut tx, id).await?;
+
+    if let Some(names) = &item.tags {
+        tags::set_item_tags(&mut tx, id, names).await?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    if let Some(previous_total) = previous_total {
+        if let Err(e) = alerts::check_item(&app, &db, id, previous_total).await {
+            eprintln!("Failed to check stock alert for item {}: {}", id, e);
+        }
+    }
+
     Ok(())
 }
 
+/// Move an item to the trash. Its stock and history are kept so it can be restored.
 #[tauri::command]
 pub async fn delete_item(
     db: State<'_, DbPool>,
     id: i32,
 ) -> Result<(), String> {
-    execute(
+    let affected = execute(
         &db,
-        "DELETE FROM items WHERE id = ?1",
+        "UPDATE items SET deleted_at = CURRENT_TIMESTAMP WHERE id = ?1 AND deleted_at IS NULL",
         vec![id.to_string()],
     )
     .await
     .map_err(|e| e.to_string())?;
 
+    if affected == 0 {
+        return Err("Item not found or already deleted".to_string());
+    }
     Ok(())
 }
 
+/// Change the quantity of an item in one location. `stock_id` selects the stock row; it
+/// can be left out while the item is stocked in a single location.
 #[tauri::command]
 pub async fn update_quantity(
+    app: AppHandle,
     db: State<'_, DbPool>,
     item_id: i32,
     change: i32,
     operation_type: String,
+    stock_id: Option<i32>,
 ) -> Result<(), String> {
-    // Get current quantity
-    let result = query_one(
-        &db,
-        "SELECT quantity FROM items WHERE id = ?1",
-        vec![item_id.to_string()],
-    )
-    .await
-    .map_err(|e| e.to_string())?;
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
 
-    let current_quantity: i32 = match result {
-        Some(row) => row.get("quantity"),
-        None => return Err("Item not found".to_string()),
-    };
+    let stock_id = stock::resolve(&mut tx, item_id, stock_id).await?;
 
-    let new_quantity = current_quantity + change;
+    let change = stock::adjust(&mut tx, stock_id, change, &operation_type, "manual", None).await?;
 
-    if new_quantity < 0 {
-        return Err("Insufficient quantity".to_string());
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    // The change itself succeeded even if the alert can't be raised
+    if let Err(e) = alerts::check_item(&app, &db, item_id, change.previous_total).await {
+        eprintln!("Failed to check stock alert for item {}: {}", item_id, e);
     }
 
-    // Update quantity
-    execute(
+    Ok(())
+}
+
+/// Where an item is stocked and how many in each location
+#[tauri::command]
+pub async fn get_item_stock(
+    db: State<'_, DbPool>,
+    item_id: i32,
+) -> Result<Vec<ItemStock>, String> {
+    let result = query_all(
         &db,
-        "UPDATE items SET quantity = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
-        vec![new_quantity.to_string(), item_id.to_string()],
+        &format!("{} WHERE s.item_id = ?1 ORDER BY l.name, s.id", STOCK_SELECT),
+        vec![item_id.to_string()],
     )
     .await
     .map_err(|e| e.to_string())?;
 
-    // Log the change
-    execute(
+    Ok(result.iter().map(stock::row_to_stock).collect())
+}
+
+/// Everything stocked directly in a location
+#[tauri::command]
+pub async fn get_location_stock(
+    db: State<'_, DbPool>,
+    location_id: i32,
+) -> Result<Vec<ItemStock>, String> {
+    let result = query_all(
         &db,
-        "INSERT INTO inventory_log (item_id, quantity_change, quantity_after, operation_type, source) VALUES (?1, ?2, ?3, ?4, ?5)",
-        vec![
-            item_id.to_string(),
-            change.to_string(),
-            new_quantity.to_string(),
-            operation_type.clone(),
-            "manual".to_string(),
-        ],
+        &format!("{} WHERE s.location_id = ?1 AND i.deleted_at IS NULL ORDER BY i.name", STOCK_SELECT),
+        vec![location_id.to_string()],
     )
     .await
     .map_err(|e| e.to_string())?;
 
+    Ok(result.iter().map(stock::row_to_stock).collect())
+}
+
+/// Stock an item in another location, optionally with an initial quantity.
+/// Returns the ID of the stock row; adds to it if the item is already stocked there.
+#[tauri::command]
+pub async fn add_item_stock(
+    db: State<'_, DbPool>,
+    item_id: i32,
+    location_id: Option<i32>,
+    quantity: Option<i32>,
+) -> Result<i32, String> {
+    let quantity = quantity.unwrap_or(0);
+    if quantity < 0 {
+        return Err("Quantity cannot be negative".to_string());
+    }
+
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    let stock_id = stock::find_or_create(&mut tx, item_id, location_id).await?;
+    if quantity > 0 {
+        stock::adjust(&mut tx, stock_id, quantity, "add", "manual", None).await?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(stock_id)
+}
+
+/// Remove an empty stock row, e.g. when a box no longer holds an item
+#[tauri::command]
+pub async fn delete_item_stock(
+    db: State<'_, DbPool>,
+    stock_id: i32,
+) -> Result<(), String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    let row = tx_query_one(&mut tx, "SELECT item_id, quantity FROM item_stock WHERE id = ?1", vec![stock_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Stock not found".to_string())?;
+
+    if row.get::<i32, _>("quantity") != 0 {
+        return Err("Only empty stock can be removed; move or remove its quantity first".to_string());
+    }
+
+    tx_execute(&mut tx, "DELETE FROM item_stock WHERE id = ?1", vec![Some(stock_id.to_string())])
+        .await
+        .map_err(|e| e.to_string())?;
+    stock::sync_total(&mut tx, row.get("item_id")).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
     Ok(())
 }
+
+/// Move `quantity` of an item from one location to another (`None` for unassigned stock) in
+/// a single transaction. The two `inventory_log` entries share the returned transfer ID.
+#[tauri::command]
+pub async fn transfer_stock(
+    db: State<'_, DbPool>,
+    item_id: i32,
+    from_location_id: Option<i32>,
+    to_location_id: Option<i32>,
+    quantity: i32,
+    notes: Option<String>,
+) -> Result<StockTransfer, String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    let from_stock_id = stock::item_rows(&mut tx, item_id)
+        .await?
+        .iter()
+        .find(|row| row.location_id == from_location_id)
+        .map(|row| row.id)
+        .ok_or_else(|| "Item is not stocked in the source location".to_string())?;
+
+    let (transfer_id, to_stock_id) = stock::transfer(&mut tx, from_stock_id, to_location_id, quantity, notes).await?;
+
+    let rows = tx_query_all(
+        &mut tx,
+        &format!("{} WHERE s.id IN (?1, ?2)", STOCK_SELECT),
+        vec![from_stock_id.to_string(), to_stock_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    let mut from = None;
+    let mut to = None;
+    for row in &rows {
+        let stock = stock::row_to_stock(row);
+        if stock.id == from_stock_id {
+            from = Some(stock);
+        } else {
+            to = Some(stock);
+        }
+    }
+
+    Ok(StockTransfer {
+        transfer_id,
+        item_id,
+        quantity,
+        from: from.ok_or_else(|| "Stock not found".to_string())?,
+        to: to.ok_or_else(|| "Stock not found".to_string())?,
+    })
+}
@@ -0,0 +1,188 @@
+use crate::database::{models::{FontInfo, FontSetting, LabelKind, LabelLayout, LabelStock, LabelTemplate, LabelTemplateInput, QrOptions}, query_all, query_one, execute, execute_with_optional, set_setting, delete_setting};
+use crate::database::DbPool;
+use crate::labels::{self, stock, template, LabelData};
+use crate::render::font;
+use base64::Engine;
+use image::{Rgb, RgbImage};
+use tauri::State;
+use sqlx::Row;
+use std::io::Cursor;
+
+fn template_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<LabelTemplate, String> {
+    let layout: String = row.get("layout");
+    Ok(LabelTemplate {
+        id: row.get("id"),
+        name: row.get("name"),
+        description: row.try_get("description").ok(),
+        layout: serde_json::from_str(&layout)
+            .map_err(|e| format!("Invalid label template layout: {}", e))?,
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+#[tauri::command]
+pub async fn get_label_templates(
+    db: State<'_, DbPool>,
+) -> Result<Vec<LabelTemplate>, String> {
+    let result = query_all(
+        &db,
+        "SELECT id, name, description, layout, created_at, updated_at FROM label_templates ORDER BY name",
+        vec![],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    result.iter().map(template_from_row).collect()
+}
+
+/// The built-in item or location layout, as a starting point for new templates
+#[tauri::command]
+pub async fn get_default_label_layout(
+    kind: Option<LabelKind>,
+) -> Result<LabelLayout, String> {
+    Ok(template::default_layout(kind.unwrap_or(LabelKind::Item)))
+}
+
+/// Built-in sticker sheet and thermal roll definitions
+#[tauri::command]
+pub async fn get_label_stocks() -> Result<Vec<LabelStock>, String> {
+    Ok(stock::presets())
+}
+
+#[tauri::command]
+pub async fn create_label_template(
+    db: State<'_, DbPool>,
+    input: LabelTemplateInput,
+) -> Result<i32, String> {
+    template::validate_layout(&input.layout)?;
+    let layout = serde_json::to_string(&input.layout).map_err(|e| e.to_string())?;
+
+    execute_with_optional(
+        &db,
+        "INSERT INTO label_templates (name, description, layout) VALUES (?1, ?2, ?3)",
+        vec![Some(input.name), input.description, Some(layout)],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // Get the last inserted id
+    let result = query_one(
+        &db,
+        "SELECT last_insert_rowid() as id",
+        vec![],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match result {
+        Some(row) => Ok(row.get("id")),
+        None => Err("Failed to get inserted ID".to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn update_label_template(
+    db: State<'_, DbPool>,
+    id: i32,
+    input: LabelTemplateInput,
+) -> Result<(), String> {
+    template::validate_layout(&input.layout)?;
+    let layout = serde_json::to_string(&input.layout).map_err(|e| e.to_string())?;
+
+    execute_with_optional(
+        &db,
+        "UPDATE label_templates SET name = ?1, description = ?2, layout = ?3, updated_at = CURRENT_TIMESTAMP WHERE id = ?4",
+        vec![Some(input.name), input.description, Some(layout), Some(id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_label_template(
+    db: State<'_, DbPool>,
+    id: i32,
+) -> Result<(), String> {
+    execute(
+        &db,
+        "DELETE FROM label_templates WHERE id = ?1",
+        vec![id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Render a single label as PNG so a layout can be checked before saving it.
+/// Uses the given item's data, or sample data if no item is given.
+#[tauri::command]
+pub async fn preview_label_template(
+    db: State<'_, DbPool>,
+    layout: LabelLayout,
+    item_id: Option<i32>,
+    qr_options: Option<QrOptions>,
+) -> Result<String, String> {
+    template::validate_layout(&layout)?;
+    let qr_options = qr_options.unwrap_or_default();
+
+    let data = match item_id {
+        Some(item_id) => labels::load_item_labels(&db, &[item_id])
+            .await?
+            .pop()
+            .ok_or("Item not found")?,
+        None => LabelData::sample(),
+    };
+
+    let font = labels::load_font(&db).await?.metrics()?;
+
+    // 10 px per mm is enough to judge the layout on screen
+    let px_per_mm = 10.0;
+    let width = (layout.width * px_per_mm) as u32;
+    let height = (layout.height * px_per_mm) as u32;
+    let mut img = RgbImage::from_pixel(width, height, Rgb([255, 255, 255]));
+
+    template::render_image(&mut img, &font, &layout, &data, &qr_options, (0, 0), (width, height))?;
+
+    let mut png_bytes = Vec::new();
+    img.write_to(&mut Cursor::new(&mut png_bytes), image::ImageFormat::Png)
+        .map_err(|e| format!("PNG encoding failed: {}", e))?;
+
+    let base64_string = base64::engine::general_purpose::STANDARD.encode(&png_bytes);
+    Ok(format!("data:image/png;base64,{}", base64_string))
+}
+
+/// Fonts installed on this system, for choosing the label font
+#[tauri::command]
+pub async fn list_fonts() -> Result<Vec<FontInfo>, String> {
+    tauri::async_runtime::spawn_blocking(font::scan_fonts)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_label_font(
+    db: State<'_, DbPool>,
+) -> Result<Option<FontSetting>, String> {
+    labels::font_setting(&db).await
+}
+
+/// Set the font used for labels, or clear it to detect a system font automatically
+#[tauri::command]
+pub async fn set_label_font(
+    db: State<'_, DbPool>,
+    font: Option<FontSetting>,
+) -> Result<(), String> {
+    match font {
+        Some(setting) => {
+            // Make sure the face can actually be used before saving it
+            font::load_face(std::path::Path::new(&setting.path), setting.face_index)?.metrics()?;
+            let value = serde_json::to_string(&setting).map_err(|e| e.to_string())?;
+            set_setting(&db, labels::FONT_SETTING_KEY, &value).await.map_err(|e| e.to_string())
+        }
+        None => delete_setting(&db, labels::FONT_SETTING_KEY).await.map_err(|e| e.to_string()),
+    }
+}
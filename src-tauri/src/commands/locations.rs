@@ -1,7 +1,10 @@
-use crate::database::{models::{Location, LocationInput}, query_all, query_one, execute, execute_with_optional};
+use crate::database::{models::{Location, LocationInput, QrLookup}, begin, query_all, query_one, execute_with_optional, tx_execute, tx_query_all, tx_query_one};
+use crate::labels::expand_location_ids;
+use crate::codes::{self, CodeContext};
 use crate::database::DbPool;
 use tauri::State;
 use sqlx::Row;
+use std::collections::HashMap;
 
 #[tauri::command]
 pub async fn get_locations(
@@ -17,15 +20,24 @@ pub async fn get_locations(
 
     eprintln!("Query returned {} rows", result.len());
 
+    let tag_rows = query_all(&db, "SELECT lt.location_id, t.name FROM location_tags lt JOIN tags t ON t.id = lt.tag_id ORDER BY t.name", vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut tags_by_location: HashMap<i32, Vec<String>> = HashMap::new();
+    for row in &tag_rows {
+        tags_by_location.entry(row.get("location_id")).or_default().push(row.get("name"));
+    }
+
     let locations: Vec<Location> = result
         .iter()
         .map(|row| {
             // Handle NULL parent_id properly - convert 0 to None
             let parent_id: Option<i32> = row.try_get("parent_id").ok();
             let parent_id = parent_id.and_then(|v| if v == 0 { None } else { Some(v) });
+            let id: i32 = row.get("id");
 
             Location {
-                id: row.get("id"),
+                id,
                 name: row.get("name"),
                 parent_id,
                 location_type: row.get("location_type"),
@@ -33,6 +45,7 @@ pub async fn get_locations(
                 qr_code_id: row.try_get("qr_code_id").ok(),
                 created_at: row.get("created_at"),
                 updated_at: row.get("updated_at"),
+                tags: tags_by_location.remove(&id).unwrap_or_default(),
             }
         })
         .collect();
@@ -42,18 +55,24 @@ pub async fn get_locations(
     Ok(locations)
 }
 
+/// Create a location with a new code, or with a reserved code from a pre-printed label
 #[tauri::command]
 pub async fn create_location(
     db: State<'_, DbPool>,
     input: LocationInput,
 ) -> Result<i32, String> {
-    let qr_code_id = format!("LOC-{}", uuid::Uuid::new_v4().to_string().split('-').next().unwrap());
+    let qr_code_id = match &input.reserved_code {
+        Some(code) => CodeContext::load(&db).await?.resolve(code)?,
+        None => codes::new_code_id(),
+    };
 
     // Convert parent_id: Some(0) to None for root locations
     let parent_id = input.parent_id.and_then(|v| if v == 0 { None } else { Some(v) });
 
-    let _result = execute_with_optional(
-        &db,
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    tx_execute(
+        &mut tx,
         "INSERT INTO locations (name, parent_id, location_type, description, qr_code_id) VALUES (?1, ?2, ?3, ?4, ?5)",
         vec![
             Some(input.name),
@@ -67,18 +86,26 @@ pub async fn create_location(
     .map_err(|e| e.to_string())?;
 
     // Get the last inserted id
-    let result = query_one(
-        &db,
+    let result = tx_query_one(
+        &mut tx,
         "SELECT last_insert_rowid() as id",
         vec![],
     )
     .await
     .map_err(|e| e.to_string())?;
 
-    match result {
-        Some(row) => Ok(row.get("id")),
-        None => Err("Failed to get inserted ID".to_string()),
+    let id: i32 = match result {
+        Some(row) => row.get("id"),
+        None => return Err("Failed to get inserted ID".to_string()),
+    };
+
+    if input.reserved_code.is_some() {
+        codes::claim_reserved_code(&mut tx, &qr_code_id, id).await?;
     }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(id)
 }
 
 #[tauri::command]
@@ -108,47 +135,162 @@ pub async fn delete_location(
     db: State<'_, DbPool>,
     id: i32,
 ) -> Result<(), String> {
-    execute(
-        &db,
-        "DELETE FROM locations WHERE id = ?1",
-        vec![id.to_string()],
-    )
-    .await
-    .map_err(|e| e.to_string())?;
+    // Sublocations are deleted along with the location
+    let location_ids = expand_location_ids(&db, &[id]).await?;
+
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    // Stock kept there becomes unassigned. An item has at most one unassigned stock row,
+    // so stock is added to an existing one, which takes over its stocktake counts.
+    for location_id in &location_ids {
+        let rows = tx_query_all(
+            &mut tx,
+            "SELECT id, item_id, quantity FROM item_stock WHERE location_id = ?1",
+            vec![location_id.to_string()],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+        for row in &rows {
+            let stock_id: i32 = row.get("id");
+            let item_id: i32 = row.get("item_id");
+            let unassigned = tx_query_one(
+                &mut tx,
+                "SELECT id FROM item_stock WHERE item_id = ?1 AND location_id IS NULL",
+                vec![item_id.to_string()],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+            let Some(unassigned) = unassigned else {
+                tx_execute(&mut tx, "UPDATE item_stock SET location_id = NULL WHERE id = ?1", vec![Some(stock_id.to_string())])
+                    .await
+                    .map_err(|e| e.to_string())?;
+                continue;
+            };
+            let target: i32 = unassigned.get("id");
+            let quantity: i32 = row.get("quantity");
+
+            tx_execute(
+                &mut tx,
+                "UPDATE item_stock SET quantity = quantity + ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+                vec![Some(quantity.to_string()), Some(target.to_string())],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            tx_execute(
+                &mut tx,
+                "UPDATE OR IGNORE stocktake_entries SET stock_id = ?1 WHERE stock_id = ?2",
+                vec![Some(target.to_string()), Some(stock_id.to_string())],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            tx_execute(&mut tx, "DELETE FROM item_stock WHERE id = ?1", vec![Some(stock_id.to_string())])
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx_execute(&mut tx, "DELETE FROM locations WHERE id = ?1", vec![Some(id.to_string())])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
 
     Ok(())
 }
 
-#[tauri::command]
-pub async fn get_location_by_qr(
-    db: State<'_, DbPool>,
-    qr_code_id: String,
-) -> Result<Location, String> {
+fn row_to_location(row: &sqlx::sqlite::SqliteRow) -> Location {
+    // Handle NULL parent_id properly - convert 0 to None
+    let parent_id: Option<i32> = row.try_get("parent_id").ok();
+    let parent_id = parent_id.and_then(|v| if v == 0 { None } else { Some(v) });
+
+    Location {
+        id: row.get("id"),
+        name: row.get("name"),
+        parent_id,
+        location_type: row.get("location_type"),
+        description: row.try_get("description").ok(),
+        qr_code_id: row.try_get("qr_code_id").ok(),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        tags: Vec::new(),
+    }
+}
+
+/// Look up the location a scanned QR payload belongs to. Accepts `ics://loc/...` links
+/// as well as bare codes from older labels. Retired codes still resolve to their location,
+/// with a warning that the label should be replaced.
+pub async fn find_location_by_qr(
+    db: &DbPool,
+    payload: &str,
+) -> Result<Option<QrLookup>, String> {
+    let qr_code_id = CodeContext::load(db).await?.resolve(payload)?;
+
     let result = query_one(
-        &db,
+        db,
         "SELECT id, name, parent_id, location_type, description, qr_code_id, created_at, updated_at FROM locations WHERE qr_code_id = ?1",
+        vec![qr_code_id.clone()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if let Some(row) = result {
+        return Ok(Some(QrLookup {
+            location: row_to_location(&row),
+            retired: false,
+            warning: None,
+        }));
+    }
+
+    let result = query_one(
+        db,
+        "SELECT l.id, l.name, l.parent_id, l.location_type, l.description, l.qr_code_id, l.created_at, l.updated_at, a.retired_at
+         FROM location_code_aliases a JOIN locations l ON l.id = a.location_id
+         WHERE a.code = ?1",
         vec![qr_code_id],
     )
     .await
     .map_err(|e| e.to_string())?;
 
-    match result {
-        Some(row) => {
-            // Handle NULL parent_id properly - convert 0 to None
-            let parent_id: Option<i32> = row.try_get("parent_id").ok();
-            let parent_id = parent_id.and_then(|v| if v == 0 { None } else { Some(v) });
+    Ok(result.map(|row| {
+        let location = row_to_location(&row);
+        let retired_at: String = row.get("retired_at");
+        let warning = format!(
+            "This QR code was retired on {}; please replace the label on {} with its current code",
+            retired_at, location.name
+        );
+        QrLookup {
+            location,
+            retired: true,
+            warning: Some(warning),
+        }
+    }))
+}
 
-            Ok(Location {
-                id: row.get("id"),
-                name: row.get("name"),
-                parent_id,
-                location_type: row.get("location_type"),
-                description: row.try_get("description").ok(),
-                qr_code_id: row.try_get("qr_code_id").ok(),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            })
-        },
-        None => Err("Location not found".to_string()),
+#[tauri::command]
+pub async fn get_location_by_qr(
+    db: State<'_, DbPool>,
+    qr_code_id: String,
+) -> Result<Location, String> {
+    let lookup = find_location_by_qr(&db, &qr_code_id)
+        .await?
+        .ok_or_else(|| "Location not found".to_string())?;
+
+    if let Some(warning) = &lookup.warning {
+        eprintln!("{}", warning);
     }
+
+    Ok(lookup.location)
+}
+
+/// Like `get_location_by_qr`, but also reports whether the scanned code has been retired
+#[tauri::command]
+pub async fn resolve_qr_code(
+    db: State<'_, DbPool>,
+    payload: String,
+) -> Result<QrLookup, String> {
+    find_location_by_qr(&db, &payload)
+        .await?
+        .ok_or_else(|| "Location not found".to_string())
 }
@@ -2,4 +2,15 @@ pub mod locations;
 pub mod items;
 pub mod qrcode;
 pub mod pdf;
+pub mod labels;
+pub mod printer;
+pub mod scan;
+pub mod stocktake;
+pub mod reports;
+pub mod alerts;
+pub mod bulk;
+pub mod tags;
+pub mod projects;
+pub mod bom;
+pub mod suppliers;
 pub mod sync;
@@ -0,0 +1,95 @@
+use crate::database::models::{PrinterOptions, PrinterTarget, QrOptions};
+use crate::database::DbPool;
+use crate::labels::{self, printer};
+use base64::Engine;
+use tauri::State;
+
+/// Render item and location labels into a raw printer job
+async fn render_job(
+    db: &DbPool,
+    item_ids: &[i32],
+    location_ids: &[i32],
+    include_sublocations: bool,
+    template_id: Option<i32>,
+    qr_options: &QrOptions,
+    options: &PrinterOptions,
+) -> Result<Vec<u8>, String> {
+    printer::validate(options)?;
+
+    let layouts = labels::Layouts::load(db, template_id).await?;
+    let labels_data = labels::load_labels(db, item_ids, location_ids, include_sublocations).await?;
+    let font = labels::load_font(db).await?.metrics()?;
+
+    let mut bitmaps = Vec::new();
+    for data in &labels_data {
+        let layout = layouts.for_label(data);
+        bitmaps.push((printer::render_bitmap(&font, layout, data, qr_options, options.dpi)?, layout));
+    }
+
+    let job = printer::encode(&bitmaps, options);
+    eprintln!("Printer job generated: {} labels, {:?}, {} bytes", bitmaps.len(), options.language, job.len());
+
+    Ok(job)
+}
+
+/// Generate a ZPL, TSPL or ESC/POS job for saving to a file or sending manually
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn generate_printer_labels(
+    db: State<'_, DbPool>,
+    item_ids: Vec<i32>,
+    location_ids: Option<Vec<i32>>,
+    include_sublocations: Option<bool>,
+    template_id: Option<i32>,
+    qr_options: Option<QrOptions>,
+    options: Option<PrinterOptions>,
+) -> Result<String, String> {
+    let job = render_job(
+        &db,
+        &item_ids,
+        &location_ids.unwrap_or_default(),
+        include_sublocations.unwrap_or(false),
+        template_id,
+        &qr_options.unwrap_or_default(),
+        &options.unwrap_or_default(),
+    )
+    .await?;
+
+    let base64_string = base64::engine::general_purpose::STANDARD.encode(&job);
+    Ok(format!("data:application/octet-stream;base64,{}", base64_string))
+}
+
+/// Print labels directly on a label printer over a raw TCP port or a device file.
+/// Returns the number of bytes sent.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn print_labels(
+    db: State<'_, DbPool>,
+    item_ids: Vec<i32>,
+    location_ids: Option<Vec<i32>>,
+    include_sublocations: Option<bool>,
+    template_id: Option<i32>,
+    qr_options: Option<QrOptions>,
+    options: Option<PrinterOptions>,
+    target: PrinterTarget,
+) -> Result<usize, String> {
+    let job = render_job(
+        &db,
+        &item_ids,
+        &location_ids.unwrap_or_default(),
+        include_sublocations.unwrap_or(false),
+        template_id,
+        &qr_options.unwrap_or_default(),
+        &options.unwrap_or_default(),
+    )
+    .await?;
+
+    let size = job.len();
+    tauri::async_runtime::spawn_blocking(move || printer::send(&target, &job))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    eprintln!("Sent {} bytes to printer", size);
+
+    Ok(size)
+}
@@ -0,0 +1,462 @@
+use crate::alerts;
+use crate::database::{begin, models::{BomLine, Buildability, BuildabilityLine, Project, ProjectBuild}, query_all, query_one, execute, execute_with_optional, tx_execute, tx_query_all, tx_query_one, DbTransaction};
+use crate::database::DbPool;
+use crate::stock;
+use tauri::{AppHandle, State};
+use sqlx::Row;
+
+const PROJECT_SELECT: &str = "SELECT p.id, p.name, p.description, p.created_at, p.updated_at,
+    (SELECT COUNT(*) FROM project_bom_lines b WHERE b.project_id = p.id) AS line_count
+    FROM projects p";
+
+const BOM_SELECT: &str = "SELECT b.id, b.project_id, b.item_id, i.name AS item_name, i.unit, b.quantity_per_unit, b.reference, b.notes
+    FROM project_bom_lines b JOIN items i ON i.id = b.item_id";
+
+const BUILD_SELECT: &str = "SELECT pb.id, pb.project_id, p.name AS project_name, pb.quantity, pb.status, pb.notes, pb.created_at, pb.finished_at
+    FROM project_builds pb JOIN projects p ON p.id = pb.project_id";
+
+fn row_to_project(row: &sqlx::sqlite::SqliteRow) -> Project {
+    Project {
+        id: row.get("id"),
+        name: row.get("name"),
+        description: row.try_get("description").ok().flatten(),
+        line_count: row.get("line_count"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+fn row_to_bom_line(row: &sqlx::sqlite::SqliteRow) -> BomLine {
+    BomLine {
+        id: row.get("id"),
+        project_id: row.get("project_id"),
+        item_id: row.get("item_id"),
+        item_name: row.get("item_name"),
+        unit: row.try_get("unit").ok().flatten(),
+        quantity_per_unit: row.get("quantity_per_unit"),
+        reference: row.try_get("reference").ok().flatten(),
+        notes: row.try_get("notes").ok().flatten(),
+    }
+}
+
+fn row_to_build(row: &sqlx::sqlite::SqliteRow) -> ProjectBuild {
+    ProjectBuild {
+        id: row.get("id"),
+        project_id: row.get("project_id"),
+        project_name: row.get("project_name"),
+        quantity: row.get("quantity"),
+        status: row.get("status"),
+        notes: row.try_get("notes").ok().flatten(),
+        created_at: row.get("created_at"),
+        finished_at: row.try_get("finished_at").ok().flatten(),
+    }
+}
+
+/// Compare a project's BOM for `quantity` units against stock that isn't reserved for
+/// other builds. Stock of deleted items doesn't count.
+async fn check_stock(tx: &mut DbTransaction, project_id: i32, quantity: i32) -> Result<Buildability, String> {
+    if quantity <= 0 {
+        return Err("Build quantity must be positive".to_string());
+    }
+
+    let rows = tx_query_all(
+        tx,
+        "SELECT b.item_id, i.name AS item_name, b.quantity_per_unit,
+            CASE WHEN i.deleted_at IS NULL THEN i.quantity ELSE 0 END AS in_stock,
+            COALESCE((SELECT SUM(r.quantity) FROM build_reservations r JOIN project_builds pb ON pb.id = r.build_id
+                WHERE pb.status = 'reserved' AND r.item_id = b.item_id), 0) AS reserved
+         FROM project_bom_lines b JOIN items i ON i.id = b.item_id
+         WHERE b.project_id = ?1 ORDER BY i.name",
+        vec![project_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut max_buildable: Option<i32> = None;
+    let lines: Vec<BuildabilityLine> = rows
+        .iter()
+        .map(|row| {
+            let per_unit: i32 = row.get("quantity_per_unit");
+            let in_stock: i32 = row.get("in_stock");
+            let reserved: i32 = row.get("reserved");
+            let available = (in_stock - reserved).max(0);
+            let required = per_unit
+                .checked_mul(quantity)
+                .ok_or_else(|| format!("Build quantity {} is too large", quantity))?;
+
+            let buildable = available / per_unit.max(1);
+            max_buildable = Some(max_buildable.map_or(buildable, |max| max.min(buildable)));
+
+            Ok(BuildabilityLine {
+                item_id: row.get("item_id"),
+                item_name: row.get("item_name"),
+                required,
+                in_stock,
+                reserved,
+                available,
+                shortage: (required - available).max(0),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(Buildability {
+        project_id,
+        quantity,
+        can_build: !lines.is_empty() && lines.iter().all(|line| line.shortage == 0),
+        max_buildable: max_buildable.unwrap_or(0),
+        lines,
+    })
+}
+
+async fn load_build(db: &DbPool, id: i32) -> Result<ProjectBuild, String> {
+    let result = query_one(db, &format!("{} WHERE pb.id = ?1", BUILD_SELECT), vec![id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    result
+        .map(|row| row_to_build(&row))
+        .ok_or_else(|| "Build not found".to_string())
+}
+
+async fn load_reserved_build(db: &DbPool, id: i32) -> Result<ProjectBuild, String> {
+    let build = load_build(db, id).await?;
+    if build.status != "reserved" {
+        return Err(format!("Build {} is already {}", id, build.status));
+    }
+    Ok(build)
+}
+
+#[tauri::command]
+pub async fn get_projects(
+    db: State<'_, DbPool>,
+) -> Result<Vec<Project>, String> {
+    let result = query_all(&db, &format!("{} ORDER BY p.name", PROJECT_SELECT), vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(result.iter().map(row_to_project).collect())
+}
+
+#[tauri::command]
+pub async fn create_project(
+    db: State<'_, DbPool>,
+    name: String,
+    description: Option<String>,
+) -> Result<i32, String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    tx_execute(
+        &mut tx,
+        "INSERT INTO projects (name, description) VALUES (?1, ?2)",
+        vec![Some(name), description],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let result = tx_query_one(&mut tx, "SELECT last_insert_rowid() as id", vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let id: i32 = match result {
+        Some(row) => row.get("id"),
+        None => return Err("Failed to get inserted ID".to_string()),
+    };
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn update_project(
+    db: State<'_, DbPool>,
+    id: i32,
+    name: String,
+    description: Option<String>,
+) -> Result<(), String> {
+    execute_with_optional(
+        &db,
+        "UPDATE projects SET name = ?1, description = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?3",
+        vec![Some(name), description, Some(id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Delete a project with its BOM and builds, releasing any reserved parts
+#[tauri::command]
+pub async fn delete_project(
+    db: State<'_, DbPool>,
+    id: i32,
+) -> Result<(), String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    for sql in [
+        "DELETE FROM build_reservations WHERE build_id IN (SELECT id FROM project_builds WHERE project_id = ?1)",
+        "DELETE FROM project_builds WHERE project_id = ?1",
+        "DELETE FROM project_bom_lines WHERE project_id = ?1",
+        "DELETE FROM projects WHERE id = ?1",
+    ] {
+        tx_execute(&mut tx, sql, vec![Some(id.to_string())])
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_project_bom(
+    db: State<'_, DbPool>,
+    project_id: i32,
+) -> Result<Vec<BomLine>, String> {
+    let result = query_all(
+        &db,
+        &format!("{} WHERE b.project_id = ?1 ORDER BY b.reference, i.name", BOM_SELECT),
+        vec![project_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(result.iter().map(row_to_bom_line).collect())
+}
+
+/// Add an item to a project's BOM, or replace its line if the item is already on it
+#[tauri::command]
+pub async fn set_bom_line(
+    db: State<'_, DbPool>,
+    project_id: i32,
+    item_id: i32,
+    quantity_per_unit: i32,
+    reference: Option<String>,
+    notes: Option<String>,
+) -> Result<i32, String> {
+    if quantity_per_unit <= 0 {
+        return Err("Quantity per unit must be positive".to_string());
+    }
+
+    execute_with_optional(
+        &db,
+        "INSERT INTO project_bom_lines (project_id, item_id, quantity_per_unit, reference, notes) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (project_id, item_id) DO UPDATE SET quantity_per_unit = excluded.quantity_per_unit,
+            reference = excluded.reference, notes = excluded.notes",
+        vec![
+            Some(project_id.to_string()),
+            Some(item_id.to_string()),
+            Some(quantity_per_unit.to_string()),
+            reference,
+            notes,
+        ],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let row = query_one(
+        &db,
+        "SELECT id FROM project_bom_lines WHERE project_id = ?1 AND item_id = ?2",
+        vec![project_id.to_string(), item_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "Failed to get BOM line".to_string())?;
+
+    Ok(row.get("id"))
+}
+
+#[tauri::command]
+pub async fn delete_bom_line(
+    db: State<'_, DbPool>,
+    id: i32,
+) -> Result<(), String> {
+    execute(&db, "DELETE FROM project_bom_lines WHERE id = ?1", vec![id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Whether `quantity` units of a project can be built from stock not reserved for other
+/// builds, with the shortage of each part
+#[tauri::command]
+pub async fn check_buildability(
+    db: State<'_, DbPool>,
+    project_id: i32,
+    quantity: Option<i32>,
+) -> Result<Buildability, String> {
+    let quantity = quantity.unwrap_or(1);
+
+    // The transaction only gives the check a consistent snapshot; nothing is written
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+    let buildability = check_stock(&mut tx, project_id, quantity).await;
+    tx.rollback().await.map_err(|e| e.to_string())?;
+
+    buildability
+}
+
+/// Reserve the parts for `quantity` units of a project. Fails if any part is short.
+#[tauri::command]
+pub async fn reserve_build(
+    db: State<'_, DbPool>,
+    project_id: i32,
+    quantity: i32,
+    notes: Option<String>,
+) -> Result<i32, String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    let buildability = check_stock(&mut tx, project_id, quantity).await?;
+    if buildability.lines.is_empty() {
+        return Err("Project has no BOM lines".to_string());
+    }
+    let short: Vec<String> = buildability
+        .lines
+        .iter()
+        .filter(|line| line.shortage > 0)
+        .map(|line| format!("{} (short {})", line.item_name, line.shortage))
+        .collect();
+    if !short.is_empty() {
+        return Err(format!("Not enough stock: {}", short.join(", ")));
+    }
+
+    tx_execute(
+        &mut tx,
+        "INSERT INTO project_builds (project_id, quantity, notes) VALUES (?1, ?2, ?3)",
+        vec![Some(project_id.to_string()), Some(quantity.to_string()), notes],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let result = tx_query_one(&mut tx, "SELECT last_insert_rowid() as id", vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let id: i32 = match result {
+        Some(row) => row.get("id"),
+        None => return Err("Failed to get inserted ID".to_string()),
+    };
+
+    for line in &buildability.lines {
+        tx_execute(
+            &mut tx,
+            "INSERT INTO build_reservations (build_id, item_id, quantity) VALUES (?1, ?2, ?3)",
+            vec![Some(id.to_string()), Some(line.item_id.to_string()), Some(line.required.to_string())],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn get_project_builds(
+    db: State<'_, DbPool>,
+    project_id: Option<i32>,
+    status: Option<String>,
+) -> Result<Vec<ProjectBuild>, String> {
+    let mut query = format!("{} WHERE 1=1", BUILD_SELECT);
+    let mut params = vec![];
+
+    if let Some(project_id) = project_id {
+        params.push(project_id.to_string());
+        query.push_str(&format!(" AND pb.project_id = ?{}", params.len()));
+    }
+    if let Some(status) = status {
+        params.push(status);
+        query.push_str(&format!(" AND pb.status = ?{}", params.len()));
+    }
+    query.push_str(" ORDER BY pb.created_at DESC, pb.id DESC");
+
+    let result = query_all(&db, &query, params)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(result.iter().map(row_to_build).collect())
+}
+
+/// Release the parts reserved for a build
+#[tauri::command]
+pub async fn cancel_build(
+    db: State<'_, DbPool>,
+    id: i32,
+) -> Result<(), String> {
+    load_reserved_build(&db, id).await?;
+
+    let affected = execute(
+        &db,
+        "UPDATE project_builds SET status = 'cancelled', finished_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'reserved'",
+        vec![id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if affected != 1 {
+        return Err(format!("Build {} is no longer reserved", id));
+    }
+    Ok(())
+}
+
+/// Deduct every part reserved for a build from stock, logged with source `project`.
+/// Nothing is deducted if any part has run short since it was reserved.
+#[tauri::command]
+pub async fn consume_build(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    id: i32,
+) -> Result<(), String> {
+    let build = load_reserved_build(&db, id).await?;
+    let notes = format!("项目 {} ×{} (#{})", build.project_name, build.quantity, id);
+
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    // Claim the build before touching stock, so it can't be consumed twice or consumed
+    // after being cancelled
+    let claimed = tx_execute(
+        &mut tx,
+        "UPDATE project_builds SET status = 'consumed', finished_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'reserved'",
+        vec![Some(id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    if claimed != 1 {
+        return Err(format!("Build {} is no longer reserved", id));
+    }
+
+    let reservations = tx_query_all(
+        &mut tx,
+        "SELECT item_id, quantity FROM build_reservations WHERE build_id = ?1 AND quantity > 0",
+        vec![id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut previous_totals = Vec::new();
+    for reservation in &reservations {
+        let item_id: i32 = reservation.get("item_id");
+        let previous_total = stock::remove_from_item(
+            &mut tx,
+            item_id,
+            reservation.get("quantity"),
+            "remove",
+            "project",
+            Some(notes.clone()),
+        )
+        .await?;
+        previous_totals.push((item_id, previous_total));
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    for (item_id, previous_total) in previous_totals {
+        if let Err(e) = alerts::check_item(&app, &db, item_id, previous_total).await {
+            eprintln!("Failed to check stock alert for item {}: {}", item_id, e);
+        }
+    }
+
+    Ok(())
+}
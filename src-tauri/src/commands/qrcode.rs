@@ -1,7 +1,7 @@
-use crate::database::{models::QRCodeResult, query_one};
+use crate::codes::{self, CodeContext};
+use crate::database::{begin, models::{CodeAssignment, QRCodeResult, QrOptions, QrSettings, ReservedCode, RetiredCode}, query_all, query_one};
 use crate::database::DbPool;
-use base64::Engine;
-use qrcode::QrCode;
+use crate::render::qr;
 use tauri::State;
 use sqlx::Row;
 
@@ -9,6 +9,7 @@ use sqlx::Row;
 pub async fn generate_location_qr(
     db: State<'_, DbPool>,
     location_id: i32,
+    options: Option<QrOptions>,
 ) -> Result<String, String> {
     // Get location details
     let result = query_one(
@@ -19,71 +20,49 @@ pub async fn generate_location_qr(
     .await
     .map_err(|e| e.to_string())?;
 
-    let (_id, _name, qr_code_id) = match result {
-        Some(row) => (
-            row.get::<i32, _>("id"),
-            row.get::<String, _>("name"),
-            row.get::<String, _>("qr_code_id"),
-        ),
+    let qr_code_id = match result {
+        Some(row) => row.get::<String, _>("qr_code_id"),
         None => return Err("Location not found".to_string()),
     };
 
-    // Generate QR code
-    let qr_code = QrCode::new(qr_code_id.clone()).map_err(|e: qrcode::types::QrError| e.to_string())?;
-
-    // Scale factor for larger, clearer QR codes
-    let scale = 10u32;
-    let margin = 4u32;
-    let qr_size = qr_code.width() as u32;
-    let image_size = (qr_size + 2 * margin) * scale;
-
-    // Create a larger image buffer with margin
-    let mut image_data = vec![255u8; (image_size * image_size) as usize];
-
-    // Render QR code with scaling and margin
-    for y in 0..qr_size {
-        for x in 0..qr_size {
-            let module = qr_code[(x as usize, y as usize)];
-            let color = if module == qrcode::types::Color::Dark {
-                0 // Black
-            } else {
-                255 // White
-            };
-
-            // Fill the scaled region
-            for py in 0..scale {
-                for px in 0..scale {
-                    let image_x = (margin + x) * scale + px;
-                    let image_y = (margin + y) * scale + py;
-                    if image_x < image_size && image_y < image_size {
-                        image_data[(image_y * image_size + image_x) as usize] = color;
-                    }
-                }
-            }
-        }
-    }
-
-    // Create image from buffer
-    let image = image::GrayImage::from_raw(image_size, image_size, image_data)
-        .unwrap();
+    let payload = CodeContext::load(&db).await?.payload(&qr_code_id);
+    qr::render_data_url(&payload, &options.unwrap_or_default())
+}
 
-    // Convert to PNG
-    let mut buffer = vec![];
-    let mut cursor = std::io::Cursor::new(&mut buffer);
-    image.write_to(&mut cursor, image::ImageFormat::Png)
-        .map_err(|e: image::error::ImageError| e.to_string())?;
+/// Export a location's QR code as a standalone SVG document for saving to disk
+#[tauri::command]
+pub async fn export_location_qr_svg(
+    db: State<'_, DbPool>,
+    location_id: i32,
+    options: Option<QrOptions>,
+) -> Result<String, String> {
+    let result = query_one(
+        &db,
+        "SELECT qr_code_id FROM locations WHERE id = ?1",
+        vec![location_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
 
-    // Encode to base64
-    let base64_string = base64::engine::general_purpose::STANDARD.encode(&buffer);
+    let qr_code_id = match result {
+        Some(row) => row.get::<String, _>("qr_code_id"),
+        None => return Err("Location not found".to_string()),
+    };
 
-    Ok(format!("data:image/png;base64,{}", base64_string))
+    let options = options.unwrap_or_default();
+    let payload = CodeContext::load(&db).await?.payload(&qr_code_id);
+    let matrix = qr::QrMatrix::encode(&payload, options.error_correction)?;
+    qr::render_svg(&matrix, &options)
 }
 
 #[tauri::command]
 pub async fn generate_batch_qr(
     db: State<'_, DbPool>,
     location_ids: Vec<i32>,
+    options: Option<QrOptions>,
 ) -> Result<Vec<QRCodeResult>, String> {
+    let options = options.unwrap_or_default();
+    let context = CodeContext::load(&db).await?;
     let mut results = vec![];
 
     for location_id in location_ids {
@@ -100,57 +79,9 @@ pub async fn generate_batch_qr(
             let name: String = row.get("name");
             let qr_code_id: String = row.get("qr_code_id");
 
-            // Generate QR code
-            let qr_code = QrCode::new(qr_code_id.clone()).map_err(|e: qrcode::types::QrError| e.to_string())?;
-
-            // Scale factor for larger, clearer QR codes
-            let scale = 10u32;
-            let margin = 4u32;
-            let qr_size = qr_code.width() as u32;
-            let image_size = (qr_size + 2 * margin) * scale;
-
-            // Create a larger image buffer with margin
-            let mut image_data = vec![255u8; (image_size * image_size) as usize];
-
-            // Render QR code with scaling and margin
-            for y in 0..qr_size {
-                for x in 0..qr_size {
-                    let module = qr_code[(x as usize, y as usize)];
-                    let color = if module == qrcode::types::Color::Dark {
-                        0 // Black
-                    } else {
-                        255 // White
-                    };
-
-                    // Fill the scaled region
-                    for py in 0..scale {
-                        for px in 0..scale {
-                            let image_x = (margin + x) * scale + px;
-                            let image_y = (margin + y) * scale + py;
-                            if image_x < image_size && image_y < image_size {
-                                image_data[(image_y * image_size + image_x) as usize] = color;
-                            }
-                        }
-                    }
-                }
-            }
-
-            // Create image from buffer
-            let image = image::GrayImage::from_raw(image_size, image_size, image_data)
-                .unwrap();
-
-            // Convert to PNG
-            let mut buffer = vec![];
-            let mut cursor = std::io::Cursor::new(&mut buffer);
-            image.write_to(&mut cursor, image::ImageFormat::Png)
-                .map_err(|e: image::error::ImageError| e.to_string())?;
-
-            // Encode to base64
-            let base64_string = base64::engine::general_purpose::STANDARD.encode(&buffer);
-
             results.push(QRCodeResult {
                 id,
-                qr_data: format!("data:image/png;base64,{}", base64_string),
+                qr_data: qr::render_data_url(&context.payload(&qr_code_id), &options)?,
                 name,
             });
         }
@@ -158,3 +89,143 @@ pub async fn generate_batch_qr(
 
     Ok(results)
 }
+
+/// Instance ID embedded in QR links and whether they are signed
+#[tauri::command]
+pub async fn get_qr_settings(
+    db: State<'_, DbPool>,
+) -> Result<QrSettings, String> {
+    let context = CodeContext::load(&db).await?;
+    Ok(QrSettings {
+        instance_id: context.instance_id.clone(),
+        signing_enabled: context.signing_enabled(),
+    })
+}
+
+/// Sign QR links with a new random key, or stop signing them.
+/// Labels printed with a previous key have to be reprinted.
+#[tauri::command]
+pub async fn set_qr_signing(
+    db: State<'_, DbPool>,
+    enabled: bool,
+) -> Result<(), String> {
+    codes::set_signing(&db, enabled).await
+}
+
+/// Issue a new code for a location, e.g. when its label is damaged or lost. The old code
+/// is retired but still resolves, with a warning. Returns the new code.
+#[tauri::command]
+pub async fn regenerate_location_qr(
+    db: State<'_, DbPool>,
+    location_id: i32,
+    reason: Option<String>,
+) -> Result<String, String> {
+    let code_id = codes::new_code_id();
+
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+    codes::bind_code(&mut tx, location_id, &code_id, reason).await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(code_id)
+}
+
+/// Attach a pre-printed code to a location. `code` is the scanned payload or the bare code;
+/// the location's previous code is retired.
+#[tauri::command]
+pub async fn assign_location_qr(
+    db: State<'_, DbPool>,
+    location_id: i32,
+    code: String,
+    reason: Option<String>,
+) -> Result<String, String> {
+    let code_id = CodeContext::load(&db).await?.resolve(&code)?;
+
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+    codes::bind_code(&mut tx, location_id, &code_id, reason).await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(code_id)
+}
+
+/// Bind a batch of pre-printed codes to locations. Either every assignment is applied or,
+/// if any code is invalid or already taken, none are.
+#[tauri::command]
+pub async fn assign_location_codes(
+    db: State<'_, DbPool>,
+    assignments: Vec<CodeAssignment>,
+    reason: Option<String>,
+) -> Result<usize, String> {
+    let context = CodeContext::load(&db).await?;
+
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+    for assignment in &assignments {
+        let code_id = context
+            .resolve(&assignment.code)
+            .map_err(|e| format!("{}: {}", assignment.code, e))?;
+        codes::bind_code(&mut tx, assignment.location_id, &code_id, reason.clone())
+            .await
+            .map_err(|e| format!("Location {}: {}", assignment.location_id, e))?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(assignments.len())
+}
+
+/// Codes a location used before, newest first
+#[tauri::command]
+pub async fn get_retired_location_codes(
+    db: State<'_, DbPool>,
+    location_id: i32,
+) -> Result<Vec<RetiredCode>, String> {
+    let rows = query_all(
+        &db,
+        "SELECT code, location_id, reason, retired_at FROM location_code_aliases WHERE location_id = ?1 ORDER BY retired_at DESC",
+        vec![location_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .iter()
+        .map(|row| RetiredCode {
+            code: row.get("code"),
+            location_id: row.get("location_id"),
+            reason: row.try_get("reason").ok(),
+            retired_at: row.get("retired_at"),
+        })
+        .collect())
+}
+
+/// Reserved codes for pre-printed labels, optionally of one batch only or only those
+/// not yet attached to a location
+#[tauri::command]
+pub async fn get_reserved_codes(
+    db: State<'_, DbPool>,
+    batch_id: Option<String>,
+    unused_only: Option<bool>,
+) -> Result<Vec<ReservedCode>, String> {
+    let mut sql = "SELECT code, batch_id, location_id, created_at, used_at FROM reserved_codes WHERE 1=1".to_string();
+    let mut params = vec![];
+
+    if let Some(batch_id) = batch_id {
+        sql.push_str(" AND batch_id = ?1");
+        params.push(batch_id);
+    }
+    if unused_only.unwrap_or(false) {
+        sql.push_str(" AND used_at IS NULL");
+    }
+    sql.push_str(" ORDER BY created_at DESC, rowid");
+
+    let rows = query_all(&db, &sql, params).await.map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ReservedCode {
+            code: row.get("code"),
+            batch_id: row.get("batch_id"),
+            location_id: row.try_get("location_id").ok().flatten(),
+            created_at: row.get("created_at"),
+            used_at: row.try_get("used_at").ok().flatten(),
+        })
+        .collect())
+}
@@ -0,0 +1,422 @@
+use crate::database::{models::{CategoryStats, DashboardStats, EmptyLocation, InventoryValuation, LocationStats, LowStockGroup, LowStockItem, LowStockReport, MovementPoint, ValuationGroup}, query_all, query_one};
+use crate::commands::items;
+use crate::database::DbPool;
+use crate::labels::location_paths;
+use crate::suppliers;
+use tauri::State;
+use sqlx::Row;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Default number of days the consumption rate is averaged over
+const DEFAULT_WINDOW_DAYS: u32 = 90;
+
+/// Default number of days of consumption a reorder should cover
+const DEFAULT_COVER_DAYS: u32 = 30;
+
+/// Longest period a reorder may cover, ten years
+const MAX_COVER_DAYS: u32 = 3650;
+
+/// Default number of days of inventory movements on the dashboard
+const DEFAULT_MOVEMENT_DAYS: u32 = 30;
+
+/// Default number of recently changed items on the dashboard
+const DEFAULT_RECENT_LIMIT: u32 = 10;
+
+/// Quantity to order to get back above the minimum with `cover_days` of stock to spare.
+/// Items without recorded consumption are topped up to twice their minimum.
+pub fn suggested_reorder(quantity: i32, min_quantity: i32, daily_consumption: f64, cover_days: u32) -> i32 {
+    let target = if daily_consumption > 0.0 {
+        min_quantity.saturating_add((daily_consumption * cover_days.min(MAX_COVER_DAYS) as f64).ceil() as i32)
+    } else {
+        min_quantity.saturating_mul(2).max(1)
+    };
+    target.saturating_sub(quantity).max(1)
+}
+
+/// Items at or below their minimum quantity, grouped by category and location, with the
+/// average consumption over the last `window_days` days (90 by default), the estimated
+/// days until each runs out and a reorder quantity covering `cover_days` days (30 by default).
+///
+/// Consumption counts every removal except stocktake corrections and transfers. Items created
+/// within the window are averaged over the days since they were created.
+#[tauri::command]
+pub async fn get_low_stock_report(
+    db: State<'_, DbPool>,
+    window_days: Option<u32>,
+    cover_days: Option<u32>,
+) -> Result<LowStockReport, String> {
+    let window_days = window_days.unwrap_or(DEFAULT_WINDOW_DAYS).max(1);
+    let cover_days = cover_days.unwrap_or(DEFAULT_COVER_DAYS).min(MAX_COVER_DAYS);
+    let since = format!("-{} days", window_days);
+
+    let result = query_all(
+        &db,
+        "SELECT i.id, i.name, i.category, i.specifications, i.unit, i.location_id, i.quantity, i.min_quantity,
+            COALESCE((SELECT SUM(-l.quantity_change) FROM inventory_log l
+                WHERE l.item_id = i.id AND l.quantity_change < 0 AND l.operation_type NOT IN ('stocktake', 'transfer')
+                AND l.created_at >= datetime('now', ?1)), 0) AS consumed,
+            julianday('now') - julianday(MAX(i.created_at, datetime('now', ?1))) AS span_days
+         FROM items i
+         WHERE i.min_quantity IS NOT NULL AND i.quantity <= i.min_quantity AND i.deleted_at IS NULL
+         ORDER BY i.category, i.location_id, i.name",
+        vec![since],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let paths = location_paths(&db).await?;
+
+    let mut items: Vec<LowStockItem> = result
+        .iter()
+        .map(|row| {
+            let quantity: i32 = row.get("quantity");
+            let min_quantity: i32 = row.get("min_quantity");
+            let consumed: i32 = row.get("consumed");
+            // At least a day, so a burst right after creating an item doesn't look endless
+            let span_days: f64 = row.try_get::<f64, _>("span_days").unwrap_or(window_days as f64).max(1.0);
+            let daily_consumption = consumed as f64 / span_days;
+            let location_id: Option<i32> = row.try_get("location_id").ok().flatten();
+
+            LowStockItem {
+                item_id: row.get("id"),
+                name: row.get("name"),
+                category: row.try_get("category").ok().flatten(),
+                specifications: row.try_get("specifications").ok().flatten(),
+                unit: row.try_get("unit").ok().flatten(),
+                location_id,
+                location_path: location_id.and_then(|id| paths.get(&id).cloned()),
+                quantity,
+                min_quantity,
+                consumed,
+                daily_consumption,
+                days_until_empty: (daily_consumption > 0.0).then(|| quantity.max(0) as f64 / daily_consumption),
+                suggested_reorder: suggested_reorder(quantity, min_quantity, daily_consumption, cover_days),
+            }
+        })
+        .collect();
+
+    items.sort_by(|a, b| {
+        (&a.category, &a.location_path, &a.name).cmp(&(&b.category, &b.location_path, &b.name))
+    });
+
+    let item_count = items.len();
+    let mut groups: Vec<LowStockGroup> = Vec::new();
+    for item in items {
+        match groups.last_mut() {
+            Some(group) if group.category == item.category && group.location_id == item.location_id => {
+                group.items.push(item);
+            }
+            _ => groups.push(LowStockGroup {
+                category: item.category.clone(),
+                location_id: item.location_id,
+                location_path: item.location_path.clone(),
+                items: vec![item],
+            }),
+        }
+    }
+
+    Ok(LowStockReport {
+        window_days,
+        cover_days,
+        item_count,
+        groups,
+    })
+}
+
+/// Everything the dashboard shows, aggregated in the database: totals, counts per category
+/// and per top-level location, low-stock and empty locations, recently changed items and
+/// daily inventory movements over the last `days` days (30 by default).
+#[tauri::command]
+pub async fn get_dashboard_stats(
+    db: State<'_, DbPool>,
+    days: Option<u32>,
+    recent_limit: Option<u32>,
+) -> Result<DashboardStats, String> {
+    let days = days.unwrap_or(DEFAULT_MOVEMENT_DAYS).clamp(1, 366);
+    let recent_limit = recent_limit.unwrap_or(DEFAULT_RECENT_LIMIT);
+
+    let totals = query_one(
+        &db,
+        "SELECT (SELECT COUNT(*) FROM locations) AS total_locations,
+            COUNT(*) AS total_items,
+            COALESCE(SUM(quantity), 0) AS total_units,
+            COALESCE(SUM(CASE WHEN id NOT IN (SELECT item_id FROM item_stock WHERE location_id IS NOT NULL) THEN 1 ELSE 0 END), 0) AS unassigned_items,
+            COALESCE(SUM(CASE WHEN min_quantity IS NOT NULL AND quantity <= min_quantity THEN 1 ELSE 0 END), 0) AS low_stock_count
+         FROM items WHERE deleted_at IS NULL",
+        vec![],
+    )
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "Failed to count items".to_string())?;
+
+    let categories = query_all(
+        &db,
+        "SELECT category, COUNT(*) AS item_count, COALESCE(SUM(quantity), 0) AS total_units
+         FROM items WHERE deleted_at IS NULL GROUP BY category ORDER BY item_count DESC, category",
+        vec![],
+    )
+    .await
+    .map_err(|e| e.to_string())?
+    .iter()
+    .map(|row| CategoryStats {
+        category: row.try_get("category").ok().flatten(),
+        item_count: row.get("item_count"),
+        total_units: row.get("total_units"),
+    })
+    .collect();
+
+    // Item counts per location, rolled up to the top-level locations
+    let locations = query_all(&db, "SELECT id, name, parent_id FROM locations ORDER BY name", vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+    let parents: HashMap<i32, Option<i32>> = locations
+        .iter()
+        .map(|row| (row.get("id"), row.try_get("parent_id").ok().flatten()))
+        .collect();
+
+    let stock: Vec<(i32, i32, i32)> = query_all(
+        &db,
+        "SELECT s.location_id, s.item_id, s.quantity FROM item_stock s JOIN items i ON i.id = s.item_id
+         WHERE s.location_id IS NOT NULL AND i.deleted_at IS NULL",
+        vec![],
+    )
+    .await
+    .map_err(|e| e.to_string())?
+    .iter()
+    .map(|row| (row.get("location_id"), row.get("item_id"), row.get("quantity")))
+    .collect();
+
+    // Items stocked in several places under the same root are counted once
+    let mut rolled_up: HashMap<i32, (HashSet<i32>, i32)> = HashMap::new();
+    let mut occupied: HashSet<i32> = HashSet::new();
+    for (location_id, item_id, quantity) in &stock {
+        // Walk up to the root, bounded in case the tree contains a cycle
+        let mut root = *location_id;
+        let mut current = Some(*location_id);
+        let mut steps = 0;
+        while let Some(id) = current {
+            if !parents.contains_key(&id) || steps > parents.len() {
+                break;
+            }
+            occupied.insert(id);
+            root = id;
+            current = parents.get(&id).copied().flatten();
+            steps += 1;
+        }
+        let entry = rolled_up.entry(root).or_default();
+        entry.0.insert(*item_id);
+        entry.1 += quantity;
+    }
+
+    let top_locations = locations
+        .iter()
+        .filter(|row| row.try_get::<Option<i32>, _>("parent_id").ok().flatten().is_none())
+        .map(|row| {
+            let location_id: i32 = row.get("id");
+            let (item_ids, total_units) = rolled_up.remove(&location_id).unwrap_or_default();
+            LocationStats {
+                location_id,
+                name: row.get("name"),
+                item_count: item_ids.len() as i32,
+                total_units,
+            }
+        })
+        .collect();
+
+    let paths = location_paths(&db).await?;
+    let empty_locations = locations
+        .iter()
+        .filter(|row| !occupied.contains(&row.get::<i32, _>("id")))
+        .map(|row| {
+            let location_id: i32 = row.get("id");
+            EmptyLocation {
+                location_id,
+                name: row.get("name"),
+                path: paths.get(&location_id).cloned().unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    let recent_items = items::load_items(
+        &db,
+        &format!("{} WHERE deleted_at IS NULL ORDER BY updated_at DESC, id DESC LIMIT ?1", items::ITEM_SELECT),
+        vec![recent_limit.to_string()],
+    )
+    .await?;
+
+    // Daily movements, with empty days filled in so the series can be charted directly
+    let start = chrono::Utc::now().date_naive() - chrono::Duration::days(days as i64 - 1);
+    let mut logged: HashMap<String, MovementPoint> = query_all(
+        &db,
+        "SELECT date(created_at) AS date,
+            COALESCE(SUM(CASE WHEN quantity_change > 0 THEN quantity_change ELSE 0 END), 0) AS added,
+            COALESCE(SUM(CASE WHEN quantity_change < 0 THEN -quantity_change ELSE 0 END), 0) AS removed,
+            COUNT(*) AS changes
+         FROM inventory_log WHERE date(created_at) >= ?1 AND transfer_id IS NULL GROUP BY date(created_at)",
+        vec![start.format("%Y-%m-%d").to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?
+    .iter()
+    .map(|row| {
+        let date: String = row.get("date");
+        (date.clone(), MovementPoint {
+            date,
+            added: row.get("added"),
+            removed: row.get("removed"),
+            changes: row.get("changes"),
+        })
+    })
+    .collect();
+
+    let movements = (0..days as i64)
+        .map(|offset| {
+            let date = (start + chrono::Duration::days(offset)).format("%Y-%m-%d").to_string();
+            logged
+                .remove(&date)
+                .unwrap_or(MovementPoint { date, added: 0, removed: 0, changes: 0 })
+        })
+        .collect();
+
+    Ok(DashboardStats {
+        total_locations: totals.get("total_locations"),
+        total_items: totals.get("total_items"),
+        total_units: totals.get("total_units"),
+        categories,
+        top_locations,
+        unassigned_items: totals.get("unassigned_items"),
+        low_stock_count: totals.get("low_stock_count"),
+        empty_locations,
+        recent_items,
+        movements,
+    })
+}
+
+/// Items, units and value of one valuation group
+type ValuationTotals = (HashSet<i32>, i32, f64);
+
+/// Groups keyed by name and location, most valuable first
+fn valuation_groups(groups: BTreeMap<(Option<String>, Option<i32>), ValuationTotals>, currency: &str) -> Vec<ValuationGroup> {
+    let mut groups: Vec<ValuationGroup> = groups
+        .into_iter()
+        .map(|((name, location_id), (item_ids, total_units, value))| ValuationGroup {
+            name,
+            location_id,
+            currency: currency.to_string(),
+            item_count: item_ids.len() as i32,
+            total_units,
+            value,
+        })
+        .collect();
+    groups.sort_by(|a, b| b.value.total_cmp(&a.value));
+    groups
+}
+
+/// Value of the stock in every location, by category and by location, in one currency
+/// (CNY by default). Each item is valued at its preferred offer, or else at its cheapest
+/// priced offer, among the offers in that currency; items without such an offer are
+/// counted separately.
+#[tauri::command]
+pub async fn get_inventory_valuation(
+    db: State<'_, DbPool>,
+    currency: Option<String>,
+) -> Result<InventoryValuation, String> {
+    let currency = suppliers::normalize_currency(currency.as_deref())?;
+
+    let rows = query_all(
+        &db,
+        "WITH unit_cost AS (
+            SELECT item_id, unit_price,
+                ROW_NUMBER() OVER (PARTITION BY item_id ORDER BY is_preferred DESC, unit_price, id) AS pick
+            FROM item_offers WHERE unit_price IS NOT NULL AND currency = ?1
+         )
+         SELECT s.item_id, s.location_id, s.quantity, i.category, c.unit_price
+         FROM item_stock s JOIN items i ON i.id = s.item_id
+         LEFT JOIN unit_cost c ON c.item_id = s.item_id AND c.pick = 1
+         WHERE i.deleted_at IS NULL AND s.quantity > 0",
+        vec![currency.clone()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let paths = location_paths(&db).await?;
+
+    let mut totals = BTreeMap::new();
+    let mut by_category = BTreeMap::new();
+    let mut by_location = BTreeMap::new();
+    let mut unpriced_items: HashSet<i32> = HashSet::new();
+    let mut unpriced_units = 0;
+    for row in &rows {
+        let item_id: i32 = row.get("item_id");
+        let quantity: i32 = row.get("quantity");
+        let location_id: Option<i32> = row.try_get("location_id").ok().flatten();
+        let unit_price: Option<f64> = row.try_get("unit_price").ok().flatten();
+
+        let Some(unit_price) = unit_price else {
+            unpriced_items.insert(item_id);
+            unpriced_units += quantity;
+            continue;
+        };
+        let value = unit_price * quantity as f64;
+
+        for (groups, key) in [
+            (&mut totals, (None, None)),
+            (&mut by_category, (row.try_get("category").ok().flatten(), None)),
+            (&mut by_location, (location_id.and_then(|id| paths.get(&id).cloned()), location_id)),
+        ] {
+            let entry: &mut ValuationTotals = groups.entry(key).or_default();
+            entry.0.insert(item_id);
+            entry.1 += quantity;
+            entry.2 += value;
+        }
+    }
+
+    Ok(InventoryValuation {
+        totals: valuation_groups(totals, &currency),
+        by_category: valuation_groups(by_category, &currency),
+        by_location: valuation_groups(by_location, &currency),
+        unpriced_items: unpriced_items.len() as i32,
+        unpriced_units,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::suggested_reorder;
+
+    #[test]
+    fn zero_stock_orders_up_to_target() {
+        // Without consumption the target is twice the minimum
+        assert_eq!(suggested_reorder(0, 10, 0.0, 30), 20);
+        // With consumption it is the minimum plus the days to cover
+        assert_eq!(suggested_reorder(0, 10, 2.0, 30), 70);
+    }
+
+    #[test]
+    fn stock_at_threshold_orders_the_difference() {
+        assert_eq!(suggested_reorder(10, 10, 0.0, 30), 10);
+        // 0.25 a day over 30 days rounds up to 8
+        assert_eq!(suggested_reorder(10, 10, 0.25, 30), 8);
+    }
+
+    #[test]
+    fn no_threshold_orders_at_least_one() {
+        assert_eq!(suggested_reorder(0, 0, 0.0, 30), 1);
+        assert_eq!(suggested_reorder(5, 0, 0.0, 30), 1);
+        assert_eq!(suggested_reorder(5, 0, 1.0, 30), 25);
+    }
+
+    #[test]
+    fn negative_stock_is_made_up() {
+        assert_eq!(suggested_reorder(-3, 2, 0.0, 30), 7);
+    }
+
+    #[test]
+    fn large_inputs_saturate() {
+        assert_eq!(suggested_reorder(0, i32::MAX, 0.0, 30), i32::MAX);
+        assert_eq!(suggested_reorder(0, i32::MAX, 1.0, 30), i32::MAX);
+        assert_eq!(suggested_reorder(i32::MIN, 10, 0.0, 30), i32::MAX);
+        // The period is capped at ten years
+        assert_eq!(suggested_reorder(0, 0, 1.0, u32::MAX), 3650);
+    }
+}
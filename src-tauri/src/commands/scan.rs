@@ -0,0 +1,78 @@
+use crate::commands::locations::find_location_by_qr;
+use crate::database::models::{DecodedCode, ImageScanResult};
+use crate::database::DbPool;
+use crate::scan;
+use image::DynamicImage;
+use tauri::State;
+
+/// Decode an image on a blocking thread and resolve each code against the locations
+async fn decode_and_resolve(db: &DbPool, img: DynamicImage) -> Result<Vec<DecodedCode>, String> {
+    let decoded = tauri::async_runtime::spawn_blocking(move || scan::decode(&img))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let mut codes = Vec::new();
+    for code in decoded {
+        // Codes from another installation or other products simply don't resolve
+        let lookup = match find_location_by_qr(db, &code.text).await {
+            Ok(lookup) => lookup,
+            Err(e) => {
+                eprintln!("Scanned code {} not resolved: {}", code.text, e);
+                None
+            }
+        };
+        codes.push(DecodedCode {
+            text: code.text,
+            format: code.format,
+            points: code.points,
+            warning: lookup.as_ref().and_then(|lookup| lookup.warning.clone()),
+            location: lookup.map(|lookup| lookup.location),
+        });
+    }
+
+    Ok(codes)
+}
+
+/// Decode all QR codes and barcodes in a photo, given either as a file path or as
+/// base64 data, and look up the locations they belong to
+#[tauri::command]
+pub async fn decode_image_codes(
+    db: State<'_, DbPool>,
+    path: Option<String>,
+    image_base64: Option<String>,
+) -> Result<Vec<DecodedCode>, String> {
+    let img = match (path, image_base64) {
+        (Some(path), None) => scan::load_file(&path)?,
+        (None, Some(data)) => scan::load_base64(&data)?,
+        _ => return Err("Provide either an image path or base64 image data".to_string()),
+    };
+
+    let codes = decode_and_resolve(&db, img).await?;
+    eprintln!("Decoded {} codes from image", codes.len());
+
+    Ok(codes)
+}
+
+/// Decode a batch of photos, e.g. pictures of every shelf. A file that can't be read
+/// is reported in its result instead of failing the whole batch.
+#[tauri::command]
+pub async fn scan_images(
+    db: State<'_, DbPool>,
+    paths: Vec<String>,
+) -> Result<Vec<ImageScanResult>, String> {
+    let mut results = Vec::new();
+
+    for path in paths {
+        let decoded = match scan::load_file(&path) {
+            Ok(img) => decode_and_resolve(&db, img).await,
+            Err(e) => Err(e),
+        };
+
+        results.push(match decoded {
+            Ok(codes) => ImageScanResult { source: path, codes, error: None },
+            Err(e) => ImageScanResult { source: path, codes: Vec::new(), error: Some(e) },
+        });
+    }
+
+    Ok(results)
+}
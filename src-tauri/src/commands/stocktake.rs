@@ -0,0 +1,439 @@
+use crate::alerts;
+use crate::commands::locations::find_location_by_qr;
+use crate::database::{begin, models::{Stocktake, StocktakeDetail, StocktakeLine, StocktakeScan}, query_all, query_one, execute_with_optional, tx_execute, tx_query_all, tx_query_one, DbTransaction};
+use crate::database::DbPool;
+use crate::labels::expand_location_ids;
+use crate::stock;
+use tauri::{AppHandle, State};
+use sqlx::Row;
+use std::collections::HashMap;
+
+const STOCKTAKE_SELECT: &str = "SELECT s.id, s.location_id, l.name AS location_name, s.include_sublocations, s.status, s.notes, s.created_at, s.finished_at,
+    (SELECT COUNT(*) FROM stocktake_entries WHERE stocktake_id = s.id) AS line_count,
+    (SELECT COUNT(*) FROM stocktake_entries WHERE stocktake_id = s.id AND counted_quantity IS NOT NULL) AS counted_count
+    FROM stocktakes s JOIN locations l ON l.id = s.location_id";
+
+/// One line per stock row, i.e. per item and location
+const LINE_SELECT: &str = "SELECT e.stock_id, s.item_id, i.name AS item_name, i.unit, s.location_id, lo.name AS location_name,
+    e.expected_quantity, s.quantity AS system_quantity, e.counted_quantity, e.counted_at
+    FROM stocktake_entries e JOIN item_stock s ON s.id = e.stock_id JOIN items i ON i.id = s.item_id LEFT JOIN locations lo ON lo.id = s.location_id";
+
+fn row_to_stocktake(row: &sqlx::sqlite::SqliteRow) -> Stocktake {
+    Stocktake {
+        id: row.get("id"),
+        location_id: row.get("location_id"),
+        location_name: row.get("location_name"),
+        include_sublocations: row.get("include_sublocations"),
+        status: row.get("status"),
+        notes: row.try_get("notes").ok(),
+        created_at: row.get("created_at"),
+        finished_at: row.try_get("finished_at").ok().flatten(),
+        line_count: row.get("line_count"),
+        counted_count: row.get("counted_count"),
+    }
+}
+
+fn row_to_line(row: &sqlx::sqlite::SqliteRow) -> StocktakeLine {
+    let system_quantity: i32 = row.get("system_quantity");
+    let counted_quantity: Option<i32> = row.try_get("counted_quantity").ok().flatten();
+
+    StocktakeLine {
+        stock_id: row.get("stock_id"),
+        item_id: row.get("item_id"),
+        item_name: row.get("item_name"),
+        unit: row.try_get("unit").ok(),
+        location_id: row.try_get("location_id").ok().flatten(),
+        location_name: row.try_get("location_name").ok().flatten(),
+        expected_quantity: row.get("expected_quantity"),
+        system_quantity,
+        counted_quantity,
+        variance: counted_quantity.map(|counted| counted - system_quantity),
+        counted_at: row.try_get("counted_at").ok().flatten(),
+    }
+}
+
+async fn load_stocktake(db: &DbPool, id: i32) -> Result<Stocktake, String> {
+    let result = query_one(db, &format!("{} WHERE s.id = ?1", STOCKTAKE_SELECT), vec![id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    result
+        .map(|row| row_to_stocktake(&row))
+        .ok_or_else(|| "Stocktake not found".to_string())
+}
+
+async fn load_open_stocktake(db: &DbPool, id: i32) -> Result<Stocktake, String> {
+    let stocktake = load_stocktake(db, id).await?;
+    if stocktake.status != "open" {
+        return Err(format!("Stocktake {} is already {}", id, stocktake.status));
+    }
+    Ok(stocktake)
+}
+
+/// Locations covered by a stocktake
+async fn stocktake_scope(db: &DbPool, stocktake: &Stocktake) -> Result<Vec<i32>, String> {
+    if stocktake.include_sublocations {
+        expand_location_ids(db, &[stocktake.location_id]).await
+    } else {
+        Ok(vec![stocktake.location_id])
+    }
+}
+
+async fn load_line(db: &DbPool, stocktake_id: i32, stock_id: i32) -> Result<StocktakeLine, String> {
+    let result = query_one(
+        db,
+        &format!("{} WHERE e.stocktake_id = ?1 AND e.stock_id = ?2", LINE_SELECT),
+        vec![stocktake_id.to_string(), stock_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    result
+        .map(|row| row_to_line(&row))
+        .ok_or_else(|| "Item is not part of this stocktake".to_string())
+}
+
+/// Stock row of an item being counted. Without `location_id`, the item must have exactly one
+/// stock row among the stocktake's lines or, failing that, within the counted area.
+async fn find_counted_stock(
+    tx: &mut DbTransaction,
+    stocktake_id: i32,
+    scope: &[i32],
+    item_id: i32,
+    location_id: Option<i32>,
+) -> Result<i32, String> {
+    if let Some(location_id) = location_id {
+        if !scope.contains(&location_id) {
+            return Err("Location is not part of this stocktake".to_string());
+        }
+        return stock::find_or_create(tx, item_id, Some(location_id)).await;
+    }
+
+    let lines = tx_query_all(
+        tx,
+        "SELECT e.stock_id FROM stocktake_entries e JOIN item_stock s ON s.id = e.stock_id WHERE e.stocktake_id = ?1 AND s.item_id = ?2",
+        vec![stocktake_id.to_string(), item_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let candidates: Vec<i32> = if lines.is_empty() {
+        stock::item_rows(tx, item_id)
+            .await?
+            .iter()
+            .filter(|row| row.location_id.is_some_and(|id| scope.contains(&id)))
+            .map(|row| row.id)
+            .collect()
+    } else {
+        lines.iter().map(|row| row.get("stock_id")).collect()
+    };
+
+    match candidates.as_slice() {
+        [] => Err("Item is not stored in a location covered by this stocktake".to_string()),
+        [stock_id] => Ok(*stock_id),
+        _ => Err("Item is stored in several counted locations; choose which one was counted".to_string()),
+    }
+}
+
+/// Start counting a location, by default together with all locations below it.
+/// The quantity of every stock row there is recorded as the expected quantity.
+#[tauri::command]
+pub async fn start_stocktake(
+    db: State<'_, DbPool>,
+    location_id: i32,
+    include_sublocations: Option<bool>,
+    notes: Option<String>,
+) -> Result<i32, String> {
+    let include_sublocations = include_sublocations.unwrap_or(true);
+    let location_ids = if include_sublocations {
+        expand_location_ids(&db, &[location_id]).await?
+    } else {
+        vec![location_id]
+    };
+
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    let exists = tx_query_one(&mut tx, "SELECT id FROM locations WHERE id = ?1", vec![location_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?;
+    if exists.is_none() {
+        return Err("Location not found".to_string());
+    }
+
+    tx_execute(
+        &mut tx,
+        "INSERT INTO stocktakes (location_id, include_sublocations, notes) VALUES (?1, ?2, ?3)",
+        vec![
+            Some(location_id.to_string()),
+            Some((include_sublocations as i32).to_string()),
+            notes,
+        ],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let result = tx_query_one(&mut tx, "SELECT last_insert_rowid() as id", vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let id: i32 = match result {
+        Some(row) => row.get("id"),
+        None => return Err("Failed to get inserted ID".to_string()),
+    };
+
+    for location_id in &location_ids {
+        tx_execute(
+            &mut tx,
+            "INSERT INTO stocktake_entries (stocktake_id, stock_id, expected_quantity)
+             SELECT ?1, s.id, s.quantity FROM item_stock s JOIN items i ON i.id = s.item_id WHERE s.location_id = ?2 AND i.deleted_at IS NULL",
+            vec![Some(id.to_string()), Some(location_id.to_string())],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    eprintln!("Started stocktake {} for {} locations", id, location_ids.len());
+
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn get_stocktakes(
+    db: State<'_, DbPool>,
+    status: Option<String>,
+) -> Result<Vec<Stocktake>, String> {
+    let mut query = String::from(STOCKTAKE_SELECT);
+    let mut params = vec![];
+
+    if let Some(status) = status {
+        query.push_str(" WHERE s.status = ?1");
+        params.push(status);
+    }
+    query.push_str(" ORDER BY s.created_at DESC, s.id DESC");
+
+    let result = query_all(&db, &query, params)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(result.iter().map(row_to_stocktake).collect())
+}
+
+/// A stocktake with every line and its variance against the current system quantity
+#[tauri::command]
+pub async fn get_stocktake(
+    db: State<'_, DbPool>,
+    id: i32,
+) -> Result<StocktakeDetail, String> {
+    let stocktake = load_stocktake(&db, id).await?;
+
+    let result = query_all(
+        &db,
+        &format!("{} WHERE e.stocktake_id = ?1 ORDER BY lo.name, i.name", LINE_SELECT),
+        vec![id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(StocktakeDetail {
+        stocktake,
+        lines: result.iter().map(row_to_line).collect(),
+    })
+}
+
+/// Resolve a scanned location code during a stocktake and list the items to count there
+#[tauri::command]
+pub async fn scan_stocktake_location(
+    db: State<'_, DbPool>,
+    stocktake_id: i32,
+    payload: String,
+) -> Result<StocktakeScan, String> {
+    let stocktake = load_open_stocktake(&db, stocktake_id).await?;
+
+    let lookup = find_location_by_qr(&db, &payload)
+        .await?
+        .ok_or_else(|| "Location not found".to_string())?;
+
+    if !stocktake_scope(&db, &stocktake).await?.contains(&lookup.location.id) {
+        return Err(format!("{} is not part of this stocktake", lookup.location.name));
+    }
+
+    let result = query_all(
+        &db,
+        &format!("{} WHERE e.stocktake_id = ?1 AND s.location_id = ?2 ORDER BY i.name", LINE_SELECT),
+        vec![stocktake_id.to_string(), lookup.location.id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(StocktakeScan {
+        location: lookup.location,
+        warning: lookup.warning,
+        lines: result.iter().map(row_to_line).collect(),
+    })
+}
+
+/// Record the counted quantity of an item in one location. With `increment`, `quantity` is
+/// added to the count so far, e.g. one per scanned unit. `location_id` is only needed when
+/// the item is stored in several counted locations. Items found in the counted area that
+/// weren't there when the stocktake started are added to it.
+#[tauri::command]
+pub async fn record_stocktake_count(
+    db: State<'_, DbPool>,
+    stocktake_id: i32,
+    item_id: i32,
+    location_id: Option<i32>,
+    quantity: i32,
+    increment: Option<bool>,
+) -> Result<StocktakeLine, String> {
+    let stocktake = load_open_stocktake(&db, stocktake_id).await?;
+
+    if quantity < 0 && !increment.unwrap_or(false) {
+        return Err("Counted quantity cannot be negative".to_string());
+    }
+
+    let scope = stocktake_scope(&db, &stocktake).await?;
+
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    let stock_id = find_counted_stock(&mut tx, stocktake_id, &scope, item_id, location_id).await?;
+
+    tx_execute(
+        &mut tx,
+        "INSERT OR IGNORE INTO stocktake_entries (stocktake_id, stock_id, expected_quantity) SELECT ?1, id, quantity FROM item_stock WHERE id = ?2",
+        vec![Some(stocktake_id.to_string()), Some(stock_id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let sql = if increment.unwrap_or(false) {
+        "UPDATE stocktake_entries SET counted_quantity = MAX(COALESCE(counted_quantity, 0) + ?1, 0), counted_at = CURRENT_TIMESTAMP WHERE stocktake_id = ?2 AND stock_id = ?3"
+    } else {
+        "UPDATE stocktake_entries SET counted_quantity = ?1, counted_at = CURRENT_TIMESTAMP WHERE stocktake_id = ?2 AND stock_id = ?3"
+    };
+
+    tx_execute(
+        &mut tx,
+        sql,
+        vec![Some(quantity.to_string()), Some(stocktake_id.to_string()), Some(stock_id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    load_line(&db, stocktake_id, stock_id).await
+}
+
+/// Forget the count of an item, so it is left unchanged when the stocktake is committed.
+/// Without `location_id`, the count is cleared in every location.
+#[tauri::command]
+pub async fn clear_stocktake_count(
+    db: State<'_, DbPool>,
+    stocktake_id: i32,
+    item_id: i32,
+    location_id: Option<i32>,
+) -> Result<(), String> {
+    load_open_stocktake(&db, stocktake_id).await?;
+
+    execute_with_optional(
+        &db,
+        "UPDATE stocktake_entries SET counted_quantity = NULL, counted_at = NULL WHERE stocktake_id = ?1
+         AND stock_id IN (SELECT id FROM item_stock WHERE item_id = ?2 AND (?3 IS NULL OR location_id = ?3))",
+        vec![Some(stocktake_id.to_string()), Some(item_id.to_string()), location_id.map(|id| id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Set every counted stock row to its counted quantity, logging each adjustment with
+/// operation type `stocktake`. Rows that weren't counted are left unchanged. Returns the
+/// number of stock rows whose quantity changed.
+#[tauri::command]
+pub async fn commit_stocktake(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    id: i32,
+) -> Result<usize, String> {
+    load_open_stocktake(&db, id).await?;
+
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    // Claim the stocktake first, so a second commit running concurrently can't apply
+    // the same counts again
+    let claimed = tx_execute(
+        &mut tx,
+        "UPDATE stocktakes SET status = 'committed', finished_at = CURRENT_TIMESTAMP WHERE id = ?1 AND status = 'open'",
+        vec![Some(id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    if claimed != 1 {
+        return Err(format!("Stocktake {} is no longer open", id));
+    }
+
+    let lines = tx_query_all(
+        &mut tx,
+        "SELECT e.stock_id, e.counted_quantity, s.quantity FROM stocktake_entries e JOIN item_stock s ON s.id = e.stock_id
+         WHERE e.stocktake_id = ?1 AND e.counted_quantity IS NOT NULL AND e.counted_quantity != s.quantity",
+        vec![id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // Item totals before the first adjustment, for the stock alerts
+    let mut previous_totals: HashMap<i32, i32> = HashMap::new();
+    for line in &lines {
+        let counted: i32 = line.get("counted_quantity");
+        let current: i32 = line.get("quantity");
+
+        let change = stock::adjust(
+            &mut tx,
+            line.get("stock_id"),
+            counted - current,
+            "stocktake",
+            "stocktake",
+            Some(format!("盘点 #{}", id)),
+        )
+        .await?;
+        previous_totals.entry(change.item_id).or_insert(change.previous_total);
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    eprintln!("Committed stocktake {}: {} stock rows adjusted", id, lines.len());
+
+    for (item_id, previous_total) in previous_totals {
+        if let Err(e) = alerts::check_item(&app, &db, item_id, previous_total).await {
+            eprintln!("Failed to check stock alert for item {}: {}", item_id, e);
+        }
+    }
+
+    Ok(lines.len())
+}
+
+/// Close a stocktake without changing any quantities
+#[tauri::command]
+pub async fn abandon_stocktake(
+    db: State<'_, DbPool>,
+    id: i32,
+    notes: Option<String>,
+) -> Result<(), String> {
+    load_open_stocktake(&db, id).await?;
+
+    let affected = execute_with_optional(
+        &db,
+        "UPDATE stocktakes SET status = 'abandoned', finished_at = CURRENT_TIMESTAMP, notes = COALESCE(?1, notes) WHERE id = ?2 AND status = 'open'",
+        vec![notes, Some(id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    if affected != 1 {
+        return Err(format!("Stocktake {} is no longer open", id));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,397 @@
+use crate::database::{begin, models::{ItemOffer, ItemOfferInput, Manufacturer, PriceRecord, Supplier}, query_all, query_one, execute_with_optional, tx_execute, tx_query_one, DbTransaction};
+use crate::database::DbPool;
+use crate::suppliers;
+use tauri::State;
+use sqlx::Row;
+
+const SUPPLIER_SELECT: &str = "SELECT s.id, s.name, s.website, s.notes, s.created_at,
+    (SELECT COUNT(*) FROM item_offers o WHERE o.supplier_id = s.id) AS offer_count
+    FROM suppliers s";
+
+const MANUFACTURER_SELECT: &str = "SELECT m.id, m.name, m.website, m.notes, m.created_at,
+    (SELECT COUNT(*) FROM items i WHERE i.manufacturer_id = m.id AND i.deleted_at IS NULL) AS item_count
+    FROM manufacturers m";
+
+const OFFER_SELECT: &str = "SELECT o.id, o.item_id, i.name AS item_name, o.supplier_id, s.name AS supplier_name, o.sku,
+    o.unit_price, o.currency, o.pack_size, o.url, o.is_preferred, o.notes, o.created_at, o.updated_at
+    FROM item_offers o JOIN items i ON i.id = o.item_id JOIN suppliers s ON s.id = o.supplier_id";
+
+fn row_to_supplier(row: &sqlx::sqlite::SqliteRow) -> Supplier {
+    Supplier {
+        id: row.get("id"),
+        name: row.get("name"),
+        website: row.try_get("website").ok().flatten(),
+        notes: row.try_get("notes").ok().flatten(),
+        offer_count: row.get("offer_count"),
+        created_at: row.get("created_at"),
+    }
+}
+
+fn row_to_manufacturer(row: &sqlx::sqlite::SqliteRow) -> Manufacturer {
+    Manufacturer {
+        id: row.get("id"),
+        name: row.get("name"),
+        website: row.try_get("website").ok().flatten(),
+        notes: row.try_get("notes").ok().flatten(),
+        item_count: row.get("item_count"),
+        created_at: row.get("created_at"),
+    }
+}
+
+fn row_to_offer(row: &sqlx::sqlite::SqliteRow) -> ItemOffer {
+    ItemOffer {
+        id: row.get("id"),
+        item_id: row.get("item_id"),
+        item_name: row.get("item_name"),
+        supplier_id: row.get("supplier_id"),
+        supplier_name: row.get("supplier_name"),
+        sku: row.try_get("sku").ok().flatten(),
+        unit_price: row.try_get("unit_price").ok().flatten(),
+        currency: row.get("currency"),
+        pack_size: row.get("pack_size"),
+        url: row.try_get("url").ok().flatten(),
+        is_preferred: row.get("is_preferred"),
+        notes: row.try_get("notes").ok().flatten(),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+/// Insert into or update `table` (suppliers or manufacturers), failing on a duplicate name
+async fn save_named(
+    db: &DbPool,
+    table: &str,
+    label: &str,
+    id: Option<i32>,
+    name: &str,
+    website: Option<String>,
+    notes: Option<String>,
+) -> Result<i32, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Name cannot be empty".to_string());
+    }
+    let existing = query_one(
+        db,
+        &format!("SELECT id FROM {} WHERE name = ?1 AND id != ?2", table),
+        vec![name.to_string(), id.unwrap_or(0).to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    if existing.is_some() {
+        return Err(format!("{} \"{}\" already exists", label, name));
+    }
+
+    match id {
+        Some(id) => {
+            let affected = execute_with_optional(
+                db,
+                &format!("UPDATE {} SET name = ?1, website = ?2, notes = ?3 WHERE id = ?4", table),
+                vec![Some(name.to_string()), website, notes, Some(id.to_string())],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            if affected == 0 {
+                return Err(format!("{} not found", label));
+            }
+            Ok(id)
+        }
+        None => {
+            execute_with_optional(
+                db,
+                &format!("INSERT INTO {} (name, website, notes) VALUES (?1, ?2, ?3)", table),
+                vec![Some(name.to_string()), website, notes],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            let row = query_one(db, &format!("SELECT id FROM {} WHERE name = ?1", table), vec![name.to_string()])
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "Failed to get inserted ID".to_string())?;
+            Ok(row.get("id"))
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_suppliers(
+    db: State<'_, DbPool>,
+) -> Result<Vec<Supplier>, String> {
+    let result = query_all(&db, &format!("{} ORDER BY s.name", SUPPLIER_SELECT), vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(result.iter().map(row_to_supplier).collect())
+}
+
+#[tauri::command]
+pub async fn create_supplier(
+    db: State<'_, DbPool>,
+    name: String,
+    website: Option<String>,
+    notes: Option<String>,
+) -> Result<i32, String> {
+    save_named(&db, "suppliers", "Supplier", None, &name, website, notes).await
+}
+
+#[tauri::command]
+pub async fn update_supplier(
+    db: State<'_, DbPool>,
+    id: i32,
+    name: String,
+    website: Option<String>,
+    notes: Option<String>,
+) -> Result<(), String> {
+    save_named(&db, "suppliers", "Supplier", Some(id), &name, website, notes).await.map(|_| ())
+}
+
+/// Delete a supplier together with its offers and their price history
+#[tauri::command]
+pub async fn delete_supplier(
+    db: State<'_, DbPool>,
+    id: i32,
+) -> Result<(), String> {
+    execute_with_optional(&db, "DELETE FROM suppliers WHERE id = ?1", vec![Some(id.to_string())])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_manufacturers(
+    db: State<'_, DbPool>,
+) -> Result<Vec<Manufacturer>, String> {
+    let result = query_all(&db, &format!("{} ORDER BY m.name", MANUFACTURER_SELECT), vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(result.iter().map(row_to_manufacturer).collect())
+}
+
+#[tauri::command]
+pub async fn create_manufacturer(
+    db: State<'_, DbPool>,
+    name: String,
+    website: Option<String>,
+    notes: Option<String>,
+) -> Result<i32, String> {
+    save_named(&db, "manufacturers", "Manufacturer", None, &name, website, notes).await
+}
+
+#[tauri::command]
+pub async fn update_manufacturer(
+    db: State<'_, DbPool>,
+    id: i32,
+    name: String,
+    website: Option<String>,
+    notes: Option<String>,
+) -> Result<(), String> {
+    save_named(&db, "manufacturers", "Manufacturer", Some(id), &name, website, notes).await.map(|_| ())
+}
+
+/// Delete a manufacturer; its items keep their part numbers but lose the manufacturer
+#[tauri::command]
+pub async fn delete_manufacturer(
+    db: State<'_, DbPool>,
+    id: i32,
+) -> Result<(), String> {
+    execute_with_optional(&db, "DELETE FROM manufacturers WHERE id = ?1", vec![Some(id.to_string())])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Offers for an item or from a supplier, preferred and cheapest first
+#[tauri::command]
+pub async fn get_item_offers(
+    db: State<'_, DbPool>,
+    item_id: Option<i32>,
+    supplier_id: Option<i32>,
+) -> Result<Vec<ItemOffer>, String> {
+    let mut query = format!("{} WHERE 1=1", OFFER_SELECT);
+    let mut params = Vec::new();
+    if let Some(item_id) = item_id {
+        params.push(item_id.to_string());
+        query.push_str(&format!(" AND o.item_id = ?{}", params.len()));
+    }
+    if let Some(supplier_id) = supplier_id {
+        params.push(supplier_id.to_string());
+        query.push_str(&format!(" AND o.supplier_id = ?{}", params.len()));
+    }
+    query.push_str(" ORDER BY i.name, o.is_preferred DESC, o.unit_price IS NULL, o.unit_price, s.name");
+
+    let result = query_all(&db, &query, params)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(result.iter().map(row_to_offer).collect())
+}
+
+fn check_offer(offer: &ItemOfferInput) -> Result<(String, i32), String> {
+    if offer.unit_price.is_some_and(|price| !price.is_finite() || price < 0.0) {
+        return Err("Unit price cannot be negative".to_string());
+    }
+    let pack_size = offer.pack_size.unwrap_or(1);
+    if pack_size <= 0 {
+        return Err("Pack size must be positive".to_string());
+    }
+    Ok((suppliers::normalize_currency(offer.currency.as_deref())?, pack_size))
+}
+
+/// Record the offer's price and preference after it was written
+async fn after_save(tx: &mut DbTransaction, offer_id: i32, offer: &ItemOfferInput, currency: &str) -> Result<(), String> {
+    if let Some(unit_price) = offer.unit_price {
+        suppliers::record_price(tx, offer_id, unit_price, currency).await?;
+    }
+    if offer.is_preferred {
+        suppliers::set_preferred(tx, offer.item_id, offer_id).await?;
+    }
+    Ok(())
+}
+
+/// Add a supplier offer for an item. Its price starts the offer's price history.
+#[tauri::command]
+pub async fn create_item_offer(
+    db: State<'_, DbPool>,
+    offer: ItemOfferInput,
+) -> Result<i32, String> {
+    let (currency, pack_size) = check_offer(&offer)?;
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    tx_query_one(&mut tx, "SELECT id FROM items WHERE id = ?1 AND deleted_at IS NULL", vec![offer.item_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Item not found".to_string())?;
+
+    tx_execute(
+        &mut tx,
+        "INSERT INTO item_offers (item_id, supplier_id, sku, unit_price, currency, pack_size, url, notes) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        vec![
+            Some(offer.item_id.to_string()),
+            Some(offer.supplier_id.to_string()),
+            offer.sku.clone(),
+            offer.unit_price.map(|v| v.to_string()),
+            Some(currency.clone()),
+            Some(pack_size.to_string()),
+            offer.url.clone(),
+            offer.notes.clone(),
+        ],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let id: i32 = tx_query_one(&mut tx, "SELECT last_insert_rowid() as id", vec![])
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Failed to get inserted ID".to_string())?
+        .get("id");
+    after_save(&mut tx, id, &offer, &currency).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+/// Update an offer. A changed price is added to the price history.
+#[tauri::command]
+pub async fn update_item_offer(
+    db: State<'_, DbPool>,
+    id: i32,
+    offer: ItemOfferInput,
+) -> Result<(), String> {
+    let (currency, pack_size) = check_offer(&offer)?;
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    tx_query_one(&mut tx, "SELECT id FROM items WHERE id = ?1 AND deleted_at IS NULL", vec![offer.item_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Item not found".to_string())?;
+
+    let affected = tx_execute(
+        &mut tx,
+        "UPDATE item_offers SET item_id = ?1, supplier_id = ?2, sku = ?3, unit_price = ?4, currency = ?5, pack_size = ?6, url = ?7, notes = ?8,
+            is_preferred = ?10, updated_at = CURRENT_TIMESTAMP WHERE id = ?9",
+        vec![
+            Some(offer.item_id.to_string()),
+            Some(offer.supplier_id.to_string()),
+            offer.sku.clone(),
+            offer.unit_price.map(|v| v.to_string()),
+            Some(currency.clone()),
+            Some(pack_size.to_string()),
+            offer.url.clone(),
+            offer.notes.clone(),
+            Some(id.to_string()),
+            Some(if offer.is_preferred { "1" } else { "0" }.to_string()),
+        ],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    if affected == 0 {
+        return Err("Offer not found".to_string());
+    }
+    after_save(&mut tx, id, &offer, &currency).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Delete an offer and its price history
+#[tauri::command]
+pub async fn delete_item_offer(
+    db: State<'_, DbPool>,
+    id: i32,
+) -> Result<(), String> {
+    execute_with_optional(&db, "DELETE FROM item_offers WHERE id = ?1", vec![Some(id.to_string())])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Price changes of an offer or of every offer for an item, oldest first
+#[tauri::command]
+pub async fn get_price_history(
+    db: State<'_, DbPool>,
+    item_id: Option<i32>,
+    offer_id: Option<i32>,
+) -> Result<Vec<PriceRecord>, String> {
+    if item_id.is_none() && offer_id.is_none() {
+        return Err("Give an item or an offer".to_string());
+    }
+
+    let mut query = String::from(
+        "SELECT h.id, h.offer_id, o.item_id, s.name AS supplier_name, o.sku, h.unit_price, h.currency, h.recorded_at
+         FROM price_history h JOIN item_offers o ON o.id = h.offer_id JOIN suppliers s ON s.id = o.supplier_id WHERE 1=1",
+    );
+    let mut params = Vec::new();
+    if let Some(item_id) = item_id {
+        params.push(item_id.to_string());
+        query.push_str(&format!(" AND o.item_id = ?{}", params.len()));
+    }
+    if let Some(offer_id) = offer_id {
+        params.push(offer_id.to_string());
+        query.push_str(&format!(" AND h.offer_id = ?{}", params.len()));
+    }
+    query.push_str(" ORDER BY h.recorded_at, h.id");
+
+    let result = query_all(&db, &query, params)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(result
+        .iter()
+        .map(|row| PriceRecord {
+            id: row.get("id"),
+            offer_id: row.get("offer_id"),
+            item_id: row.get("item_id"),
+            supplier_name: row.get("supplier_name"),
+            sku: row.try_get("sku").ok().flatten(),
+            unit_price: row.get("unit_price"),
+            currency: row.get("currency"),
+            recorded_at: row.get("recorded_at"),
+        })
+        .collect())
+}
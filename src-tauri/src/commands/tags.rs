@@ -0,0 +1,201 @@
+use crate::database::{begin, models::Tag, query_all, query_one, execute, tx_execute, tx_query_one};
+use crate::database::DbPool;
+use crate::tags;
+use tauri::State;
+use sqlx::Row;
+
+const TAG_SELECT: &str = "SELECT t.id, t.name, t.created_at,
+    (SELECT COUNT(*) FROM item_tags it JOIN items i ON i.id = it.item_id WHERE it.tag_id = t.id AND i.deleted_at IS NULL) AS item_count,
+    (SELECT COUNT(*) FROM location_tags lt WHERE lt.tag_id = t.id) AS location_count
+    FROM tags t";
+
+fn row_to_tag(row: &sqlx::sqlite::SqliteRow) -> Tag {
+    Tag {
+        id: row.get("id"),
+        name: row.get("name"),
+        item_count: row.get("item_count"),
+        location_count: row.get("location_count"),
+        created_at: row.get("created_at"),
+    }
+}
+
+/// Tag named `name` (case-insensitively) other than `except_id`
+async fn find_by_name(db: &DbPool, name: &str, except_id: Option<i32>) -> Result<Option<i32>, String> {
+    let row = query_one(
+        db,
+        "SELECT id FROM tags WHERE name = ?1 AND id != ?2",
+        vec![name.to_string(), except_id.unwrap_or(0).to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(row.map(|row| row.get("id")))
+}
+
+#[tauri::command]
+pub async fn get_tags(
+    db: State<'_, DbPool>,
+) -> Result<Vec<Tag>, String> {
+    let result = query_all(&db, &format!("{} ORDER BY t.name", TAG_SELECT), vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(result.iter().map(row_to_tag).collect())
+}
+
+#[tauri::command]
+pub async fn create_tag(
+    db: State<'_, DbPool>,
+    name: String,
+) -> Result<i32, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Tag name cannot be empty".to_string());
+    }
+    if find_by_name(&db, name, None).await?.is_some() {
+        return Err(format!("Tag \"{}\" already exists", name));
+    }
+
+    execute(&db, "INSERT INTO tags (name) VALUES (?1)", vec![name.to_string()])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    find_by_name(&db, name, None)
+        .await?
+        .ok_or_else(|| "Failed to get inserted ID".to_string())
+}
+
+/// Rename a tag. Renaming to the name of another tag fails; merge the tags instead.
+#[tauri::command]
+pub async fn rename_tag(
+    db: State<'_, DbPool>,
+    id: i32,
+    name: String,
+) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Tag name cannot be empty".to_string());
+    }
+    if find_by_name(&db, name, Some(id)).await?.is_some() {
+        return Err(format!("Tag \"{}\" already exists; merge the tags instead", name));
+    }
+
+    let affected = execute(&db, "UPDATE tags SET name = ?1 WHERE id = ?2", vec![name.to_string(), id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?;
+    if affected == 0 {
+        return Err("Tag not found".to_string());
+    }
+
+    Ok(())
+}
+
+/// Move every item and location tagged with one of `source_ids` to `target_id` and delete
+/// the source tags
+#[tauri::command]
+pub async fn merge_tags(
+    db: State<'_, DbPool>,
+    source_ids: Vec<i32>,
+    target_id: i32,
+) -> Result<(), String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    tx_query_one(&mut tx, "SELECT id FROM tags WHERE id = ?1", vec![target_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Tag not found".to_string())?;
+
+    for source_id in source_ids.into_iter().filter(|id| *id != target_id) {
+        let params = vec![Some(target_id.to_string()), Some(source_id.to_string())];
+        tx_execute(
+            &mut tx,
+            "INSERT OR IGNORE INTO item_tags (item_id, tag_id) SELECT item_id, ?1 FROM item_tags WHERE tag_id = ?2",
+            params.clone(),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        tx_execute(
+            &mut tx,
+            "INSERT OR IGNORE INTO location_tags (location_id, tag_id) SELECT location_id, ?1 FROM location_tags WHERE tag_id = ?2",
+            params,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        for sql in [
+            "DELETE FROM item_tags WHERE tag_id = ?1",
+            "DELETE FROM location_tags WHERE tag_id = ?1",
+            "DELETE FROM tags WHERE id = ?1",
+        ] {
+            tx_execute(&mut tx, sql, vec![Some(source_id.to_string())])
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Delete a tag and remove it from every item and location
+#[tauri::command]
+pub async fn delete_tag(
+    db: State<'_, DbPool>,
+    id: i32,
+) -> Result<(), String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    for sql in [
+        "DELETE FROM item_tags WHERE tag_id = ?1",
+        "DELETE FROM location_tags WHERE tag_id = ?1",
+        "DELETE FROM tags WHERE id = ?1",
+    ] {
+        tx_execute(&mut tx, sql, vec![Some(id.to_string())])
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Replace the tags of an item, creating tags that don't exist yet
+#[tauri::command]
+pub async fn set_item_tags(
+    db: State<'_, DbPool>,
+    item_id: i32,
+    tags: Vec<String>,
+) -> Result<(), String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    tx_query_one(&mut tx, "SELECT id FROM items WHERE id = ?1", vec![item_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Item not found".to_string())?;
+    tags::set_item_tags(&mut tx, item_id, &tags).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Replace the tags of a location, creating tags that don't exist yet
+#[tauri::command]
+pub async fn set_location_tags(
+    db: State<'_, DbPool>,
+    location_id: i32,
+    tags: Vec<String>,
+) -> Result<(), String> {
+    let mut tx = begin(&db).await.map_err(|e| e.to_string())?;
+
+    tx_query_one(&mut tx, "SELECT id FROM locations WHERE id = ?1", vec![location_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Location not found".to_string())?;
+    tags::set_location_tags(&mut tx, location_id, &tags).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(())
+}
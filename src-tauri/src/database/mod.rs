@@ -6,6 +6,8 @@ pub mod models;
 
 pub type DbPool = Arc<SqlitePool>;
 
+pub type DbTransaction = sqlx::Transaction<'static, sqlx::Sqlite>;
+
 /// Execute a query and return all rows
 pub async fn query_all(pool: &DbPool, sql: &str, params: Vec<String>) -> Result<Vec<sqlx::sqlite::SqliteRow>, sqlx::Error> {
     let mut query = sqlx::query(sql);
@@ -46,6 +48,114 @@ pub async fn execute_with_optional(
     query.execute(&**pool).await.map(|r| r.rows_affected())
 }
 
+/// Start a transaction. Nothing is saved until `tx.commit()` is called; dropping the
+/// transaction rolls it back.
+pub async fn begin(pool: &DbPool) -> Result<DbTransaction, sqlx::Error> {
+    pool.begin().await
+}
+
+/// Execute a query inside a transaction and return all rows
+pub async fn tx_query_all(tx: &mut DbTransaction, sql: &str, params: Vec<String>) -> Result<Vec<sqlx::sqlite::SqliteRow>, sqlx::Error> {
+    let mut query = sqlx::query(sql);
+    for param in params {
+        query = query.bind(param);
+    }
+    query.fetch_all(&mut **tx).await
+}
+
+/// Execute a query inside a transaction and return the first row
+pub async fn tx_query_one(tx: &mut DbTransaction, sql: &str, params: Vec<String>) -> Result<Option<sqlx::sqlite::SqliteRow>, sqlx::Error> {
+    let mut query = sqlx::query(sql);
+    for param in params {
+        query = query.bind(param);
+    }
+    query.fetch_optional(&mut **tx).await
+}
+
+/// Execute a query inside a transaction with optional parameters and return the number of affected rows
+pub async fn tx_execute(tx: &mut DbTransaction, sql: &str, params: Vec<Option<String>>) -> Result<u64, sqlx::Error> {
+    let mut query = sqlx::query(sql);
+    for param in params {
+        query = query.bind(param);
+    }
+    query.execute(&mut **tx).await.map(|r| r.rows_affected())
+}
+
+/// Read a value from the settings table
+pub async fn get_setting(pool: &DbPool, key: &str) -> Result<Option<String>, sqlx::Error> {
+    let row = query_one(pool, "SELECT value FROM settings WHERE key = ?1", vec![key.to_string()]).await?;
+    Ok(row.map(|row| sqlx::Row::get(&row, "value")))
+}
+
+/// Insert or replace a value in the settings table
+pub async fn set_setting(pool: &DbPool, key: &str, value: &str) -> Result<(), sqlx::Error> {
+    execute(
+        pool,
+        "INSERT INTO settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+        vec![key.to_string(), value.to_string()],
+    )
+    .await
+    .map(|_| ())
+}
+
+/// Read a value from the settings table, storing `default` first if it isn't set yet
+pub async fn get_or_init_setting(pool: &DbPool, key: &str, default: &str) -> Result<String, sqlx::Error> {
+    execute(
+        pool,
+        "INSERT OR IGNORE INTO settings (key, value) VALUES (?1, ?2)",
+        vec![key.to_string(), default.to_string()],
+    )
+    .await?;
+
+    Ok(get_setting(pool, key).await?.unwrap_or_else(|| default.to_string()))
+}
+
+/// Remove a value from the settings table
+pub async fn delete_setting(pool: &DbPool, key: &str) -> Result<(), sqlx::Error> {
+    execute(pool, "DELETE FROM settings WHERE key = ?1", vec![key.to_string()]).await.map(|_| ())
+}
+
+/// Add a column to an existing table unless it is already there. Migrations run on every
+/// start, and SQLite has no `ADD COLUMN IF NOT EXISTS`.
+async fn add_column(pool: &SqlitePool, table: &str, column: &str, definition: &str) -> Result<(), sqlx::Error> {
+    let existing = sqlx::query("SELECT name FROM pragma_table_info(?1) WHERE name = ?2")
+        .bind(table)
+        .bind(column)
+        .fetch_optional(pool)
+        .await?;
+
+    if existing.is_none() {
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Run a data migration once. Unlike the schema migrations it is not safe to repeat, so
+/// the settings table records that it has been applied.
+async fn migrate_once(pool: &SqlitePool, key: &str, sql: &str) -> Result<(), sqlx::Error> {
+    let applied = sqlx::query("SELECT 1 FROM settings WHERE key = ?1")
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+    if applied.is_some() {
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(sql).execute(&mut *tx).await?;
+    sqlx::query("INSERT INTO settings (key, value) VALUES (?1, '1')")
+        .bind(key)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    eprintln!("Applied data migration {}", key);
+    Ok(())
+}
+
 pub async fn get_pool(app: &AppHandle) -> Option<DbPool> {
     app.try_state::<DbPool>().map(|state| state.inner().clone())
 }
@@ -84,6 +194,68 @@ pub async fn init(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     let result = sqlx::query(migration_sql_2).execute(&pool).await?;
     eprintln!("Fixed {} locations with parent_id = 0", result.rows_affected());
 
+    let migration_sql_3 = include_str!("../../migrations/3_label_templates.sql");
+    sqlx::query(migration_sql_3).execute(&pool).await?;
+
+    let migration_sql_4 = include_str!("../../migrations/4_settings.sql");
+    sqlx::query(migration_sql_4).execute(&pool).await?;
+
+    let migration_sql_5 = include_str!("../../migrations/5_location_code_aliases.sql");
+    sqlx::query(migration_sql_5).execute(&pool).await?;
+
+    let migration_sql_6 = include_str!("../../migrations/6_reserved_codes.sql");
+    sqlx::query(migration_sql_6).execute(&pool).await?;
+
+    let migration_sql_7 = include_str!("../../migrations/7_stocktakes.sql");
+    sqlx::query(migration_sql_7).execute(&pool).await?;
+
+    let migration_sql_8 = include_str!("../../migrations/8_stock_alerts.sql");
+    sqlx::query(migration_sql_8).execute(&pool).await?;
+
+    let migration_sql_9 = include_str!("../../migrations/9_item_stock.sql");
+    sqlx::query(migration_sql_9).execute(&pool).await?;
+
+    // Give every existing item one stock row at its location
+    migrate_once(
+        &pool,
+        "migration_item_stock",
+        "INSERT INTO item_stock (item_id, location_id, quantity)
+         SELECT id, location_id, COALESCE(quantity, 0) FROM items
+         WHERE NOT EXISTS (SELECT 1 FROM item_stock s WHERE s.item_id = items.id)",
+    )
+    .await?;
+
+    // Location whose stock an inventory change applied to
+    add_column(&pool, "inventory_log", "location_id", "INTEGER REFERENCES locations(id) ON DELETE SET NULL").await?;
+
+    // Shared by the two entries of a stock transfer
+    add_column(&pool, "inventory_log", "transfer_id", "TEXT").await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_inventory_log_transfer ON inventory_log(transfer_id)")
+        .execute(&pool)
+        .await?;
+
+    let migration_sql_10 = include_str!("../../migrations/10_tags.sql");
+    sqlx::query(migration_sql_10).execute(&pool).await?;
+
+    // Soft-deleted items are hidden everywhere but can be restored
+    add_column(&pool, "items", "deleted_at", "DATETIME").await?;
+
+    let migration_sql_11 = include_str!("../../migrations/11_location_tags.sql");
+    sqlx::query(migration_sql_11).execute(&pool).await?;
+
+    let migration_sql_12 = include_str!("../../migrations/12_projects.sql");
+    sqlx::query(migration_sql_12).execute(&pool).await?;
+
+    let migration_sql_13 = include_str!("../../migrations/13_suppliers.sql");
+    sqlx::query(migration_sql_13).execute(&pool).await?;
+
+    // Manufacturer part number, used to match BOM lines and supplier offers
+    add_column(&pool, "items", "mpn", "TEXT").await?;
+    add_column(&pool, "items", "manufacturer_id", "INTEGER REFERENCES manufacturers(id) ON DELETE SET NULL").await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_items_mpn ON items(mpn)")
+        .execute(&pool)
+        .await?;
+
     eprintln!("Migrations executed successfully");
 
     // Store pool in app state
@@ -10,6 +10,8 @@ pub struct Location {
     pub qr_code_id: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -26,26 +28,254 @@ pub struct Item {
     pub image_path: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// Where the item is stocked; `quantity` is the total across these
+    #[serde(default)]
+    pub stock: Vec<ItemStock>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Set while the item is in the trash
+    #[serde(default)]
+    pub deleted_at: Option<String>,
+    /// Manufacturer part number
+    #[serde(default)]
+    pub mpn: Option<String>,
+    #[serde(default)]
+    pub manufacturer_id: Option<i32>,
+    /// Name of the manufacturer
+    #[serde(default)]
+    pub manufacturer: Option<String>,
+}
+
+/// Quantity of an item kept in one location
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemStock {
+    pub id: i32,
+    pub item_id: i32,
+    pub item_name: String,
+    /// None for stock not assigned to a location
+    pub location_id: Option<i32>,
+    pub location_name: Option<String>,
+    pub quantity: i32,
+    pub updated_at: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InventoryLog {
     pub id: i32,
     pub item_id: i32,
+    /// Location whose stock changed
+    pub location_id: Option<i32>,
     pub quantity_change: i32,
+    /// Item total across all locations after the change
     pub quantity_after: i32,
     pub operation_type: String,
     pub source: String,
     pub notes: Option<String>,
+    /// Links the two entries of a transfer between locations
+    pub transfer_id: Option<String>,
     pub created_at: String,
 }
 
+/// A quantity of an item moved from one location to another
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StockTransfer {
+    pub transfer_id: String,
+    pub item_id: i32,
+    pub quantity: i32,
+    /// Both stock rows after the transfer
+    pub from: ItemStock,
+    pub to: ItemStock,
+}
+
+/// A stock count of a location, optionally including everything below it.
+/// `status` is "open", "committed" or "abandoned".
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Stocktake {
+    pub id: i32,
+    pub location_id: i32,
+    pub location_name: String,
+    pub include_sublocations: bool,
+    pub status: String,
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+    pub line_count: i32,
+    pub counted_count: i32,
+}
+
+/// The stock of one item in one location, as counted in a stocktake
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StocktakeLine {
+    pub stock_id: i32,
+    pub item_id: i32,
+    pub item_name: String,
+    pub unit: Option<String>,
+    pub location_id: Option<i32>,
+    pub location_name: Option<String>,
+    /// System quantity when the stocktake was started
+    pub expected_quantity: i32,
+    /// Current system quantity, which the count is compared against
+    pub system_quantity: i32,
+    pub counted_quantity: Option<i32>,
+    /// Counted minus system quantity, once counted
+    pub variance: Option<i32>,
+    pub counted_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StocktakeDetail {
+    pub stocktake: Stocktake,
+    pub lines: Vec<StocktakeLine>,
+}
+
+/// A location scanned during a stocktake, with the items to count there
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StocktakeScan {
+    pub location: Location,
+    /// Set when the scanned code has been retired
+    pub warning: Option<String>,
+    pub lines: Vec<StocktakeLine>,
+}
+
+/// An item at or below its minimum quantity
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LowStockItem {
+    pub item_id: i32,
+    pub name: String,
+    pub category: Option<String>,
+    pub specifications: Option<String>,
+    pub unit: Option<String>,
+    pub location_id: Option<i32>,
+    /// Full location path, e.g. 货架A / 2层 / 1号盒子
+    pub location_path: Option<String>,
+    pub quantity: i32,
+    pub min_quantity: i32,
+    /// Units taken out during the report window
+    pub consumed: i32,
+    /// Average units taken out per day
+    pub daily_consumption: f64,
+    /// At the current rate; None when nothing has been consumed
+    pub days_until_empty: Option<f64>,
+    pub suggested_reorder: i32,
+}
+
+/// Low-stock items sharing a category and location
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LowStockGroup {
+    pub category: Option<String>,
+    pub location_id: Option<i32>,
+    pub location_path: Option<String>,
+    pub items: Vec<LowStockItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LowStockReport {
+    /// Days of inventory history the consumption rate is averaged over
+    pub window_days: u32,
+    /// Days of consumption a reorder should cover
+    pub cover_days: u32,
+    pub item_count: usize,
+    pub groups: Vec<LowStockGroup>,
+}
+
+/// When system notifications are shown for items falling below their minimum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMode {
+    /// A notification for every item as soon as it runs low
+    Immediate,
+    /// One summary notification per day
+    Digest,
+    /// No system notifications; the in-app event is still emitted
+    Off,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StockAlertSettings {
+    pub mode: AlertMode,
+    /// Local hour (0-23) from which the daily digest is sent
+    pub digest_hour: u32,
+}
+
+impl Default for StockAlertSettings {
+    fn default() -> Self {
+        Self {
+            mode: AlertMode::Immediate,
+            digest_hour: 9,
+        }
+    }
+}
+
+/// Payload of the `stock-low` event, emitted when an item falls to or below its minimum
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockAlert {
+    pub item_id: i32,
+    pub name: String,
+    pub quantity: i32,
+    pub min_quantity: i32,
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryStats {
+    pub category: Option<String>,
+    pub item_count: i32,
+    pub total_units: i32,
+}
+
+/// Items stored in a top-level location or anywhere below it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LocationStats {
+    pub location_id: i32,
+    pub name: String,
+    pub item_count: i32,
+    pub total_units: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmptyLocation {
+    pub location_id: i32,
+    pub name: String,
+    pub path: String,
+}
+
+/// Inventory movements on one day (UTC)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MovementPoint {
+    pub date: String,
+    pub added: i32,
+    pub removed: i32,
+    /// Number of log entries
+    pub changes: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DashboardStats {
+    pub total_locations: i32,
+    pub total_items: i32,
+    pub total_units: i32,
+    pub categories: Vec<CategoryStats>,
+    pub top_locations: Vec<LocationStats>,
+    /// Items not assigned to any location
+    pub unassigned_items: i32,
+    pub low_stock_count: i32,
+    /// Locations with no items in them or anywhere below them
+    pub empty_locations: Vec<EmptyLocation>,
+    pub recent_items: Vec<Item>,
+    /// One point per day of the requested period, oldest first
+    pub movements: Vec<MovementPoint>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LocationInput {
     pub name: String,
     pub parent_id: Option<i32>,
     pub location_type: String,
     pub description: Option<String>,
+    /// Pre-printed code to use instead of a new one, as scanned or the bare code
+    #[serde(default)]
+    pub reserved_code: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -59,6 +289,13 @@ pub struct ItemInput {
     pub min_quantity: Option<i32>,
     pub notes: Option<String>,
     pub image_path: Option<String>,
+    /// Replaces the item's tags when given
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub mpn: Option<String>,
+    #[serde(default)]
+    pub manufacturer_id: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -66,6 +303,304 @@ pub struct ItemFilter {
     pub category: Option<String>,
     pub location_id: Option<i32>,
     pub search: Option<String>,
+    /// List soft-deleted items instead of current ones
+    pub deleted: Option<bool>,
+    /// Items with at least one of these tags
+    #[serde(default)]
+    pub tags_any: Option<Vec<String>>,
+    /// Items with every one of these tags
+    #[serde(default)]
+    pub tags_all: Option<Vec<String>>,
+    /// Items with none of these tags
+    #[serde(default)]
+    pub tags_none: Option<Vec<String>>,
+}
+
+/// A tag with the number of items and locations carrying it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+    pub item_count: i32,
+    pub location_count: i32,
+    pub created_at: String,
+}
+
+/// Something built from items, e.g. a PCB
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Project {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub line_count: i32,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// One bill-of-materials line: how many of an item each unit of a project needs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BomLine {
+    pub id: i32,
+    pub project_id: i32,
+    pub item_id: i32,
+    pub item_name: String,
+    pub unit: Option<String>,
+    pub quantity_per_unit: i32,
+    /// Reference designators, e.g. "R1, R5"
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Stock of one BOM item against what a build needs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BuildabilityLine {
+    pub item_id: i32,
+    pub item_name: String,
+    pub required: i32,
+    pub in_stock: i32,
+    /// Held for other builds
+    pub reserved: i32,
+    pub available: i32,
+    pub shortage: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Buildability {
+    pub project_id: i32,
+    pub quantity: i32,
+    pub can_build: bool,
+    /// Most units that can be built from available stock
+    pub max_buildable: i32,
+    pub lines: Vec<BuildabilityLine>,
+}
+
+/// A batch of a project, holding its parts while `reserved`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectBuild {
+    pub id: i32,
+    pub project_id: i32,
+    pub project_name: String,
+    pub quantity: i32,
+    /// "reserved", "consumed" or "cancelled"
+    pub status: String,
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+}
+
+/// Outcome of a bulk operation for one item
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkItemResult {
+    pub item_id: i32,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// One quantity change in a bulk adjustment; `stock_id` as in `update_quantity`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkAdjustment {
+    pub item_id: i32,
+    pub change: i32,
+    pub stock_id: Option<i32>,
+}
+
+/// An item a BOM line might refer to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BomMatchCandidate {
+    pub item_id: i32,
+    pub item_name: String,
+    /// "mpn", "supplier_part", "value_footprint" or "fuzzy"
+    pub method: String,
+    /// 1.0 for part number matches, lower for weaker matches
+    pub score: f64,
+}
+
+/// One line of an imported BOM file and the item it was matched to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BomImportLine {
+    /// Record number in the file, counting from 1
+    pub row: i32,
+    pub reference: Option<String>,
+    pub quantity: i32,
+    pub value: Option<String>,
+    pub footprint: Option<String>,
+    pub mpn: Option<String>,
+    pub manufacturer: Option<String>,
+    /// Supplier part number, e.g. the LCSC code in JLCPCB BOMs
+    pub supplier_part: Option<String>,
+    /// Header of the column the supplier part number came from, e.g. "LCSC Part"
+    #[serde(default)]
+    pub supplier_part_column: Option<String>,
+    pub description: Option<String>,
+    /// Matched item, `None` if unmatched or unclear
+    pub item_id: Option<i32>,
+    pub item_name: Option<String>,
+    /// Method of the match, or "created" for items created from the line
+    pub match_method: Option<String>,
+    #[serde(default)]
+    pub score: f64,
+    #[serde(default)]
+    pub candidates: Vec<BomMatchCandidate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BomImportPreview {
+    /// "kicad", "jlcpcb" or "generic"
+    pub format: String,
+    pub lines: Vec<BomImportLine>,
+    pub matched: i32,
+    pub unmatched: i32,
+}
+
+/// A distributor parts are bought from
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Supplier {
+    pub id: i32,
+    pub name: String,
+    pub website: Option<String>,
+    pub notes: Option<String>,
+    pub offer_count: i32,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manufacturer {
+    pub id: i32,
+    pub name: String,
+    pub website: Option<String>,
+    pub notes: Option<String>,
+    pub item_count: i32,
+    pub created_at: String,
+}
+
+/// What a supplier sells an item for
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItemOffer {
+    pub id: i32,
+    pub item_id: i32,
+    pub item_name: String,
+    pub supplier_id: i32,
+    pub supplier_name: String,
+    /// The supplier's part number
+    pub sku: Option<String>,
+    /// Price of a single piece; None while unknown
+    pub unit_price: Option<f64>,
+    /// ISO 4217 code, e.g. "CNY"
+    pub currency: String,
+    /// Pieces per pack; orders come in multiples of it
+    pub pack_size: i32,
+    pub url: Option<String>,
+    /// Used to value the item's stock
+    pub is_preferred: bool,
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItemOfferInput {
+    pub item_id: i32,
+    pub supplier_id: i32,
+    pub sku: Option<String>,
+    pub unit_price: Option<f64>,
+    /// "CNY" when not given
+    pub currency: Option<String>,
+    /// 1 when not given
+    pub pack_size: Option<i32>,
+    pub url: Option<String>,
+    #[serde(default)]
+    pub is_preferred: bool,
+    pub notes: Option<String>,
+}
+
+/// Price of an offer from `recorded_at` until the next record
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PriceRecord {
+    pub id: i32,
+    pub offer_id: i32,
+    pub item_id: i32,
+    pub supplier_name: String,
+    pub sku: Option<String>,
+    pub unit_price: f64,
+    pub currency: String,
+    pub recorded_at: String,
+}
+
+/// Stock value of a group of items in one currency
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValuationGroup {
+    /// Category or location path; None for uncategorized items or stock without a location
+    pub name: Option<String>,
+    /// Set for location groups
+    pub location_id: Option<i32>,
+    pub currency: String,
+    pub item_count: i32,
+    pub total_units: i32,
+    pub value: f64,
+}
+
+/// Value of the stock at each item's preferred (or else cheapest) offer. Amounts in different
+/// currencies are kept apart rather than converted.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InventoryValuation {
+    /// A single group for all stock, empty if nothing is priced
+    pub totals: Vec<ValuationGroup>,
+    pub by_category: Vec<ValuationGroup>,
+    pub by_location: Vec<ValuationGroup>,
+    /// Stocked items without a priced offer in the currency, left out of the totals
+    pub unpriced_items: i32,
+    pub unpriced_units: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QrSettings {
+    /// Identifies this installation in QR links, so codes from another database are recognized
+    pub instance_id: String,
+    pub signing_enabled: bool,
+}
+
+/// Location a scanned QR code resolved to
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QrLookup {
+    pub location: Location,
+    /// The code is no longer the location's current code, e.g. after its label was replaced
+    pub retired: bool,
+    pub warning: Option<String>,
+}
+
+/// A code that used to identify a location and still resolves to it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RetiredCode {
+    pub code: String,
+    pub location_id: i32,
+    pub reason: Option<String>,
+    pub retired_at: String,
+}
+
+/// A code reserved for pre-printed labels
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReservedCode {
+    pub code: String,
+    pub batch_id: String,
+    pub location_id: Option<i32>,
+    pub created_at: String,
+    pub used_at: Option<String>,
+}
+
+/// A newly reserved batch of codes with the PDF sheet to print them on
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReservedCodeSheet {
+    pub batch_id: String,
+    pub codes: Vec<String>,
+    /// PDF as a data URL
+    pub pdf: String,
+}
+
+/// Binds a code (as scanned from a pre-printed label, or the bare code) to a location
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CodeAssignment {
+    pub location_id: i32,
+    pub code: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -81,3 +616,262 @@ pub struct SyncResult {
     pub message: String,
     pub timestamp: String,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QrErrorCorrection {
+    L,
+    M,
+    Q,
+    H,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QrOutputFormat {
+    Png,
+    Svg,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QrOptions {
+    pub error_correction: QrErrorCorrection,
+    /// Quiet zone width in modules
+    pub quiet_zone: u32,
+    /// Size of a single module in pixels
+    pub module_size: u32,
+    pub format: QrOutputFormat,
+    /// Foreground color as `#RRGGBB`
+    pub foreground: String,
+    /// Background color as `#RRGGBB`
+    pub background: String,
+}
+
+impl Default for QrOptions {
+    fn default() -> Self {
+        Self {
+            error_correction: QrErrorCorrection::M,
+            quiet_zone: 4,
+            module_size: 10,
+            format: QrOutputFormat::Png,
+            foreground: "#000000".to_string(),
+            background: "#FFFFFF".to_string(),
+        }
+    }
+}
+
+/// Item field that a label element can print
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelField {
+    Name,
+    Category,
+    Specifications,
+    Quantity,
+    Location,
+    Notes,
+    /// Type of a location label, e.g. 货架
+    LocationType,
+    /// Full path of the location, e.g. 货架A / 2层 / 1号盒子
+    Path,
+    /// Summary of what a location contains
+    Contents,
+}
+
+/// What a label is printed for, which decides the built-in layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelKind {
+    Item,
+    Location,
+}
+
+/// Barcode types that can be printed on labels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Symbology {
+    Qr,
+    DataMatrix,
+    Code128,
+    /// 12 digits (check digit added) or 13 digits
+    Ean13,
+}
+
+/// A single element on a label. Positions and sizes are in mm from the top-left
+/// corner of the label, font sizes in pt.
+///
+/// Text is wrapped to `width` (by default up to the next QR code or barcode on its right,
+/// or the label edge), shrunk down to `min_font_size` to fit in `max_lines` lines (default 1)
+/// and ellipsized if it still doesn't fit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LabelElement {
+    Field {
+        field: LabelField,
+        x: f32,
+        y: f32,
+        font_size: f32,
+        /// Static text printed before the value, e.g. "数量: "
+        label: Option<String>,
+        max_chars: Option<usize>,
+        width: Option<f32>,
+        max_lines: Option<usize>,
+        min_font_size: Option<f32>,
+    },
+    Text {
+        text: String,
+        x: f32,
+        y: f32,
+        font_size: f32,
+        width: Option<f32>,
+        max_lines: Option<usize>,
+        min_font_size: Option<f32>,
+    },
+    Qr {
+        x: f32,
+        y: f32,
+        size: f32,
+    },
+    /// Barcode of any symbology. Encodes `field` if set, otherwise the same code as the QR code.
+    Barcode {
+        symbology: Symbology,
+        field: Option<LabelField>,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelLayout {
+    /// Label size in mm the element positions were designed for.
+    /// The layout is scaled uniformly to fit the actual label cell.
+    pub width: f32,
+    pub height: f32,
+    pub elements: Vec<LabelElement>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabelTemplate {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub layout: LabelLayout,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabelTemplateInput {
+    pub name: String,
+    pub description: Option<String>,
+    pub layout: LabelLayout,
+}
+
+/// Physical label stock: a page divided into `columns` x `rows` labels.
+/// All sizes are in mm. The gutter between labels is the pitch minus the label size.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelStock {
+    pub id: String,
+    pub name: String,
+    pub page_width: f32,
+    pub page_height: f32,
+    pub margin_left: f32,
+    pub margin_top: f32,
+    pub label_width: f32,
+    pub label_height: f32,
+    /// Distance between the left edges of neighbouring labels
+    pub horizontal_pitch: f32,
+    /// Distance between the top edges of neighbouring labels
+    pub vertical_pitch: f32,
+    pub columns: u32,
+    pub rows: u32,
+    /// Draw cut lines around each label (plain paper rather than die-cut stock)
+    pub outline: bool,
+}
+
+/// Font file and face (for .ttc collections) used to render labels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FontSetting {
+    pub path: String,
+    #[serde(default)]
+    pub face_index: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FontInfo {
+    pub path: String,
+    pub face_index: u32,
+    pub family: String,
+    pub full_name: String,
+    /// Whether the face has Chinese glyphs
+    pub supports_cjk: bool,
+}
+
+/// Command language spoken by a label printer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrinterLanguage {
+    /// Zebra ZPL II
+    Zpl,
+    /// TSC TSPL/TSPL2, used by most cheap thermal label printers
+    Tspl,
+    /// Epson ESC/POS raster graphics, for receipt-style thermal printers
+    EscPos,
+}
+
+/// How labels are encoded for a raw printer. Labels are sent as bitmaps at the
+/// printer's resolution so they print exactly as laid out, including Chinese text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrinterOptions {
+    pub language: PrinterLanguage,
+    /// Printer resolution in dots per inch, usually 203 or 300
+    pub dpi: u32,
+    pub copies: u32,
+    /// Gap between labels on the roll in mm (TSPL only)
+    pub gap: f32,
+}
+
+impl Default for PrinterOptions {
+    fn default() -> Self {
+        Self {
+            language: PrinterLanguage::Zpl,
+            dpi: 203,
+            copies: 1,
+            gap: 2.0,
+        }
+    }
+}
+
+/// Where raw printer data is sent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PrinterTarget {
+    /// Raw TCP socket, port 9100 by default (JetDirect/AppSocket)
+    Tcp { host: String, port: Option<u16> },
+    /// Device file such as /dev/usb/lp0, or a shared printer path on Windows
+    Device { path: String },
+}
+
+/// A QR code or barcode found in an image, resolved to a location if it is one of ours
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DecodedCode {
+    pub text: String,
+    /// Barcode format, e.g. "qrcode", "code 128", "datamatrix"
+    pub format: String,
+    /// Position of the code in the image, in pixels
+    pub points: Vec<[f32; 2]>,
+    pub location: Option<Location>,
+    /// Set when the code has been retired and the label should be replaced
+    pub warning: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImageScanResult {
+    /// File path of the image, or "base64" for uploaded photos
+    pub source: String,
+    pub codes: Vec<DecodedCode>,
+    pub error: Option<String>,
+}
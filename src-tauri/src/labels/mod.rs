@@ -0,0 +1,337 @@
+use crate::codes::CodeContext;
+use crate::database::{get_setting, models::{FontSetting, LabelKind, LabelLayout}, query_all, query_one, DbPool};
+use crate::render::font::{self, LabelFont};
+use sqlx::Row;
+use std::collections::HashMap;
+
+pub mod printer;
+pub mod stock;
+pub mod template;
+
+/// Settings key holding the user's label font as JSON
+pub const FONT_SETTING_KEY: &str = "label_font";
+
+/// Values available to a label template for one item or location
+#[derive(Debug, Clone)]
+pub struct LabelData {
+    pub kind: LabelKind,
+    pub name: String,
+    pub category: Option<String>,
+    pub specifications: Option<String>,
+    /// Stock of an item, or the number of different items in a location
+    pub quantity: i32,
+    pub unit: Option<String>,
+    pub location: Option<String>,
+    pub notes: Option<String>,
+    pub location_type: Option<String>,
+    pub path: Option<String>,
+    pub contents: Option<String>,
+    /// Payload encoded into the QR code
+    pub qr_data: String,
+}
+
+impl LabelData {
+    /// Placeholder data used when previewing a template without a real item
+    pub fn sample() -> Self {
+        Self {
+            kind: LabelKind::Item,
+            name: "5.1k电阻".to_string(),
+            category: Some("电阻".to_string()),
+            specifications: Some("0805 1% 1/8W".to_string()),
+            quantity: 100,
+            unit: Some("个".to_string()),
+            location: Some("1号盒子".to_string()),
+            notes: None,
+            location_type: Some("盒子".to_string()),
+            path: Some("货架A / 2层 / 1号盒子".to_string()),
+            contents: Some("3 种物品: 5.1k电阻、10k电阻、100nF电容".to_string()),
+            qr_data: "LOC-00000000".to_string(),
+        }
+    }
+}
+
+/// Label for a reserved code that isn't attached to a location yet.
+/// Only the start of the code is printed as text, which is enough to tell labels apart.
+pub fn reserved_code_label(codes: &CodeContext, code_id: &str) -> LabelData {
+    LabelData {
+        kind: LabelKind::Location,
+        name: code_id.chars().take(8).collect::<String>().to_uppercase(),
+        category: None,
+        specifications: None,
+        quantity: 0,
+        unit: None,
+        location: None,
+        notes: None,
+        location_type: None,
+        path: None,
+        contents: None,
+        qr_data: codes.payload(code_id),
+    }
+}
+
+/// Display name of a location type, as shown in the location tree
+fn location_type_label(location_type: &str) -> String {
+    match location_type {
+        "shelf" => "货架".to_string(),
+        "box" => "盒子".to_string(),
+        "compartment" => "隔间".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Full path of every location, from the root down, e.g. "货架A / 2层 / 1号盒子"
+pub async fn location_paths(db: &DbPool) -> Result<HashMap<i32, String>, String> {
+    let rows = query_all(db, "SELECT id, name, parent_id FROM locations", vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let locations: HashMap<i32, (String, Option<i32>)> = rows
+        .iter()
+        .map(|row| (row.get("id"), (row.get("name"), row.try_get("parent_id").ok())))
+        .collect();
+
+    let mut paths = HashMap::new();
+    for id in locations.keys() {
+        let mut names = Vec::new();
+        let mut current = Some(*id);
+        // Bounded by the number of locations in case the tree contains a cycle
+        while let Some(location_id) = current {
+            let Some((name, parent_id)) = locations.get(&location_id) else {
+                break;
+            };
+            names.push(name.as_str());
+            if names.len() > locations.len() {
+                break;
+            }
+            current = *parent_id;
+        }
+        names.reverse();
+        paths.insert(*id, names.join(" / "));
+    }
+
+    Ok(paths)
+}
+
+/// Load label data for the given items, skipping IDs that don't exist.
+/// Items without a location get their own ID as QR payload.
+pub async fn load_item_labels(db: &DbPool, item_ids: &[i32]) -> Result<Vec<LabelData>, String> {
+    let paths = location_paths(db).await?;
+    let codes = CodeContext::load(db).await?;
+
+    let mut labels = Vec::new();
+    for item_id in item_ids {
+        let result = query_one(
+            db,
+            "SELECT i.id, i.name, i.category, i.specifications, i.quantity, i.unit, i.notes, i.location_id, l.name as location_name, l.location_type, l.qr_code_id FROM items i LEFT JOIN locations l ON i.location_id = l.id WHERE i.id = ?1",
+            vec![item_id.to_string()],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+        if let Some(row) = result {
+            let id: i32 = row.get("id");
+            let location_id: Option<i32> = row.try_get("location_id").ok();
+            labels.push(LabelData {
+                kind: LabelKind::Item,
+                name: row.get("name"),
+                category: row.try_get("category").ok(),
+                specifications: row.try_get("specifications").ok(),
+                quantity: row.get("quantity"),
+                unit: row.try_get("unit").ok(),
+                location: row.try_get("location_name").ok(),
+                notes: row.try_get("notes").ok(),
+                location_type: row.try_get::<String, _>("location_type").ok().map(|t| location_type_label(&t)),
+                path: location_id.and_then(|id| paths.get(&id).cloned()),
+                contents: None,
+                qr_data: row
+                    .try_get::<String, _>("qr_code_id")
+                    .map(|code| codes.payload(&code))
+                    .unwrap_or_else(|_| id.to_string()),
+            });
+        }
+    }
+
+    Ok(labels)
+}
+
+/// IDs of the given locations followed by all of their descendants, without duplicates
+pub async fn expand_location_ids(db: &DbPool, location_ids: &[i32]) -> Result<Vec<i32>, String> {
+    let rows = query_all(db, "SELECT id, parent_id FROM locations ORDER BY name", vec![])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+    for row in &rows {
+        if let Ok(parent_id) = row.try_get::<i32, _>("parent_id") {
+            children.entry(parent_id).or_default().push(row.get("id"));
+        }
+    }
+
+    let mut expanded = Vec::new();
+    for id in location_ids {
+        // Depth-first, so each box follows the shelf it sits on
+        let mut stack = vec![*id];
+        while let Some(id) = stack.pop() {
+            if expanded.contains(&id) {
+                continue;
+            }
+            expanded.push(id);
+            if let Some(child_ids) = children.get(&id) {
+                stack.extend(child_ids.iter().rev());
+            }
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// Summary of a location's contents, e.g. "3 种物品: 5.1k电阻、10k电阻、100nF电容; 2 个子位置"
+fn contents_summary(item_names: &[String], child_count: usize) -> Option<String> {
+    let mut parts = Vec::new();
+    if !item_names.is_empty() {
+        parts.push(format!("{} 种物品: {}", item_names.len(), item_names.join("、")));
+    }
+    if child_count > 0 {
+        parts.push(format!("{} 个子位置", child_count));
+    }
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("; "))
+    }
+}
+
+/// Load label data for the given locations, skipping IDs that don't exist
+pub async fn load_location_labels(db: &DbPool, location_ids: &[i32]) -> Result<Vec<LabelData>, String> {
+    let paths = location_paths(db).await?;
+    let codes = CodeContext::load(db).await?;
+
+    let mut labels = Vec::new();
+    for location_id in location_ids {
+        let result = query_one(
+            db,
+            "SELECT id, name, location_type, description, qr_code_id, (SELECT COUNT(*) FROM locations c WHERE c.parent_id = locations.id) as child_count FROM locations WHERE id = ?1",
+            vec![location_id.to_string()],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let Some(row) = result else {
+            continue;
+        };
+
+        let item_rows = query_all(
+            db,
+            "SELECT DISTINCT i.name FROM item_stock s JOIN items i ON i.id = s.item_id WHERE s.location_id = ?1 AND i.deleted_at IS NULL ORDER BY i.name",
+            vec![location_id.to_string()],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        let item_names: Vec<String> = item_rows.iter().map(|row| row.get("name")).collect();
+
+        let id: i32 = row.get("id");
+        let location_type: String = row.get("location_type");
+        let child_count: i64 = row.get("child_count");
+
+        labels.push(LabelData {
+            kind: LabelKind::Location,
+            name: row.get("name"),
+            category: None,
+            specifications: None,
+            quantity: item_names.len() as i32,
+            unit: Some("种".to_string()),
+            location: row.try_get("name").ok(),
+            notes: row.try_get("description").ok(),
+            location_type: Some(location_type_label(&location_type)),
+            path: paths.get(&id).cloned(),
+            contents: contents_summary(&item_names, child_count as usize),
+            qr_data: row
+                .try_get::<String, _>("qr_code_id")
+                .map(|code| codes.payload(&code))
+                .unwrap_or_else(|_| id.to_string()),
+        });
+    }
+
+    Ok(labels)
+}
+
+/// Load item labels followed by location labels. With `include_sublocations`, every
+/// location below the given ones gets a label too, e.g. all boxes on a shelf.
+pub async fn load_labels(db: &DbPool, item_ids: &[i32], location_ids: &[i32], include_sublocations: bool) -> Result<Vec<LabelData>, String> {
+    let location_ids = if include_sublocations {
+        expand_location_ids(db, location_ids).await?
+    } else {
+        location_ids.to_vec()
+    };
+
+    let mut labels = load_item_labels(db, item_ids).await?;
+    labels.extend(load_location_labels(db, &location_ids).await?);
+
+    if labels.is_empty() {
+        return Err("No items or locations found".to_string());
+    }
+
+    Ok(labels)
+}
+
+/// Built-in or saved layouts for item and location labels
+pub struct Layouts {
+    pub item: LabelLayout,
+    pub location: LabelLayout,
+}
+
+impl Layouts {
+    /// Load both layouts. A template applies to both kinds of label; without one,
+    /// each kind uses its own built-in layout.
+    pub async fn load(db: &DbPool, template_id: Option<i32>) -> Result<Self, String> {
+        Ok(Self {
+            item: load_layout(db, template_id, LabelKind::Item).await?,
+            location: load_layout(db, template_id, LabelKind::Location).await?,
+        })
+    }
+
+    pub fn for_label(&self, data: &LabelData) -> &LabelLayout {
+        match data.kind {
+            LabelKind::Item => &self.item,
+            LabelKind::Location => &self.location,
+        }
+    }
+}
+
+/// Load the layout of a saved template, or the built-in layout for `kind` if no template is given
+pub async fn load_layout(db: &DbPool, template_id: Option<i32>, kind: LabelKind) -> Result<LabelLayout, String> {
+    let Some(template_id) = template_id else {
+        return Ok(template::default_layout(kind));
+    };
+
+    let result = query_one(
+        db,
+        "SELECT layout FROM label_templates WHERE id = ?1",
+        vec![template_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match result {
+        Some(row) => serde_json::from_str(&row.get::<String, _>("layout"))
+            .map_err(|e| format!("Invalid label template layout: {}", e)),
+        None => Err("Label template not found".to_string()),
+    }
+}
+
+/// Read the configured label font, if any
+pub async fn font_setting(db: &DbPool) -> Result<Option<FontSetting>, String> {
+    let value = get_setting(db, FONT_SETTING_KEY).await.map_err(|e| e.to_string())?;
+    value
+        .map(|value| serde_json::from_str(&value).map_err(|e| format!("Invalid font setting: {}", e)))
+        .transpose()
+}
+
+/// Load the font used to render labels
+pub async fn load_font(db: &DbPool) -> Result<LabelFont, String> {
+    let setting = font_setting(db).await?;
+    tauri::async_runtime::spawn_blocking(move || font::load(setting.as_ref()))
+        .await
+        .map_err(|e| e.to_string())
+}
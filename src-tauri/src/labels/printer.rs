@@ -0,0 +1,339 @@
+use crate::database::models::{LabelLayout, PrinterLanguage, PrinterOptions, PrinterTarget, QrOptions};
+use crate::labels::{template, LabelData};
+use image::{Rgb, RgbImage};
+use rusttype::Font;
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Default raw printing port (JetDirect/AppSocket)
+const RAW_PORT: u16 = 9100;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// ESC/POS printers only accept a limited number of rows per raster command
+const ESC_POS_BAND_HEIGHT: u32 = 255;
+
+/// One label as a monochrome bitmap, rows packed 8 dots per byte with the most
+/// significant bit first. A set bit is a black dot.
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Bitmap {
+    /// Threshold a rendered label into black and white dots
+    fn from_image(img: &RgbImage) -> Self {
+        let bytes_per_row = img.width().div_ceil(8);
+        let mut data = vec![0u8; (bytes_per_row * img.height()) as usize];
+
+        for (x, y, Rgb([r, g, b])) in img.enumerate_pixels() {
+            let luma = (*r as u32 * 299 + *g as u32 * 587 + *b as u32 * 114) / 1000;
+            if luma < 128 {
+                data[(y * bytes_per_row + x / 8) as usize] |= 0x80 >> (x % 8);
+            }
+        }
+
+        Self {
+            width: img.width(),
+            height: img.height(),
+            data,
+        }
+    }
+
+    pub fn bytes_per_row(&self) -> u32 {
+        self.width.div_ceil(8)
+    }
+
+    fn row(&self, y: u32) -> &[u8] {
+        let bytes_per_row = self.bytes_per_row() as usize;
+        &self.data[y as usize * bytes_per_row..(y as usize + 1) * bytes_per_row]
+    }
+}
+
+/// Render a label at the printer's resolution
+pub fn render_bitmap(font: &Font, layout: &LabelLayout, data: &LabelData, qr_options: &QrOptions, dpi: u32) -> Result<Bitmap, String> {
+    let px_per_mm = dpi as f32 / 25.4;
+    let width = (layout.width * px_per_mm).round() as u32;
+    let height = (layout.height * px_per_mm).round() as u32;
+
+    let mut img = RgbImage::from_pixel(width, height, Rgb([255, 255, 255]));
+    template::render_image(&mut img, font, layout, data, qr_options, (0, 0), (width, height))?;
+
+    Ok(Bitmap::from_image(&img))
+}
+
+/// ZPL II: one format per label, the bitmap sent as an ASCII hex graphic field
+fn encode_zpl(bitmap: &Bitmap, copies: u32, output: &mut Vec<u8>) {
+    let total_bytes = bitmap.data.len();
+    let hex: String = bitmap.data.iter().map(|b| format!("{:02X}", b)).collect();
+
+    output.extend_from_slice(
+        format!(
+            "^XA^PW{}^LL{}^LH0,0^FO0,0^GFA,{},{},{},{}^FS^PQ{}^XZ\n",
+            bitmap.width,
+            bitmap.height,
+            total_bytes,
+            total_bytes,
+            bitmap.bytes_per_row(),
+            hex,
+            copies,
+        )
+        .as_bytes(),
+    );
+}
+
+/// TSPL: media setup, then the bitmap as binary data. TSPL prints cleared bits as black,
+/// so the bitmap is inverted.
+fn encode_tspl(bitmap: &Bitmap, layout: &LabelLayout, options: &PrinterOptions, output: &mut Vec<u8>) {
+    output.extend_from_slice(
+        format!(
+            "SIZE {:.1} mm,{:.1} mm\r\nGAP {:.1} mm,0 mm\r\nDIRECTION 1\r\nCLS\r\nBITMAP 0,0,{},{},0,",
+            layout.width,
+            layout.height,
+            options.gap,
+            bitmap.bytes_per_row(),
+            bitmap.height,
+        )
+        .as_bytes(),
+    );
+    output.extend(bitmap.data.iter().map(|b| !b));
+    output.extend_from_slice(format!("\r\nPRINT 1,{}\r\n", options.copies).as_bytes());
+}
+
+/// ESC/POS: `GS v 0` raster images in bands, followed by a short feed
+fn encode_esc_pos(bitmap: &Bitmap, copies: u32, output: &mut Vec<u8>) {
+    let bytes_per_row = bitmap.bytes_per_row();
+
+    for _ in 0..copies {
+        // ESC @: initialize printer
+        output.extend_from_slice(&[0x1B, 0x40]);
+
+        let mut top = 0;
+        while top < bitmap.height {
+            let rows = ESC_POS_BAND_HEIGHT.min(bitmap.height - top);
+            output.extend_from_slice(&[
+                0x1D, 0x76, 0x30, 0x00,
+                (bytes_per_row & 0xFF) as u8,
+                (bytes_per_row >> 8) as u8,
+                (rows & 0xFF) as u8,
+                (rows >> 8) as u8,
+            ]);
+            for y in top..top + rows {
+                output.extend_from_slice(bitmap.row(y));
+            }
+            top += rows;
+        }
+
+        // ESC d 3: feed three lines so the label clears the tear bar
+        output.extend_from_slice(&[0x1B, 0x64, 0x03]);
+    }
+}
+
+/// Encode rendered labels into a printer job. `labels` pairs each bitmap with the layout
+/// it was rendered from, which gives the physical label size.
+pub fn encode(labels: &[(Bitmap, &LabelLayout)], options: &PrinterOptions) -> Vec<u8> {
+    let mut output = Vec::new();
+
+    for (bitmap, layout) in labels {
+        match options.language {
+            PrinterLanguage::Zpl => encode_zpl(bitmap, options.copies, &mut output),
+            PrinterLanguage::Tspl => encode_tspl(bitmap, layout, options, &mut output),
+            PrinterLanguage::EscPos => encode_esc_pos(bitmap, options.copies, &mut output),
+        }
+    }
+
+    output
+}
+
+/// Check printer options before rendering
+pub fn validate(options: &PrinterOptions) -> Result<(), String> {
+    if !(100..=600).contains(&options.dpi) {
+        return Err("Printer DPI must be between 100 and 600".to_string());
+    }
+    if options.copies == 0 {
+        return Err("Copies must be at least 1".to_string());
+    }
+    if options.gap < 0.0 {
+        return Err("Label gap cannot be negative".to_string());
+    }
+    Ok(())
+}
+
+/// Fail unless `path` is a printer device, so a wrong setting can't overwrite a regular file.
+/// On Unix that means a character device such as `/dev/usb/lp0`; elsewhere, anything that
+/// isn't a plain file or directory, like `\\.\USB001`.
+fn check_device(path: &str) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        let metadata = std::fs::metadata(path)
+            .map_err(|e| format!("Failed to open printer device {}: {}", path, e))?;
+        if !metadata.file_type().is_char_device() {
+            return Err(format!("{} is not a printer device", path));
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        if std::fs::metadata(path).is_ok_and(|metadata| metadata.is_file() || metadata.is_dir()) {
+            return Err(format!("{} is not a printer device", path));
+        }
+    }
+
+    Ok(())
+}
+
+/// Send a printer job to a raw TCP port or a device file. This blocks until the data is
+/// written, so call it from a blocking task.
+pub fn send(target: &PrinterTarget, data: &[u8]) -> Result<(), String> {
+    match target {
+        PrinterTarget::Tcp { host, port } => {
+            let port = port.unwrap_or(RAW_PORT);
+            let address = (host.as_str(), port)
+                .to_socket_addrs()
+                .map_err(|e| format!("Invalid printer address {}:{}: {}", host, port, e))?
+                .next()
+                .ok_or_else(|| format!("Printer address {}:{} did not resolve", host, port))?;
+
+            let mut stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+                .map_err(|e| format!("Failed to connect to printer {}: {}", address, e))?;
+            stream.set_write_timeout(Some(CONNECT_TIMEOUT * 6))
+                .map_err(|e| e.to_string())?;
+            stream.write_all(data)
+                .map_err(|e| format!("Failed to send data to printer: {}", e))?;
+            stream.flush().map_err(|e| e.to_string())?;
+        }
+        PrinterTarget::Device { path } => {
+            check_device(path)?;
+            let mut device = std::fs::OpenOptions::new()
+                .write(true)
+                .open(path)
+                .map_err(|e| format!("Failed to open printer device {}: {}", path, e))?;
+            device.write_all(data)
+                .map_err(|e| format!("Failed to write to printer device: {}", e))?;
+            device.flush().map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    /// 10 x 2 dots: a full first row minus the last 2 dots, and a dot at each end of the second
+    fn small_bitmap() -> Bitmap {
+        Bitmap {
+            width: 10,
+            height: 2,
+            data: vec![0xFF, 0xC0, 0x80, 0x40],
+        }
+    }
+
+    fn layout() -> LabelLayout {
+        LabelLayout {
+            width: 25.0,
+            height: 10.0,
+            elements: Vec::new(),
+        }
+    }
+
+    /// Encode the small bitmap and send it to a local listener, returning what it received
+    fn print(language: PrinterLanguage, copies: u32) -> Vec<u8> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let receiver = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let layout = layout();
+        let options = PrinterOptions { language, copies, ..PrinterOptions::default() };
+        let job = encode(&[(small_bitmap(), &layout)], &options);
+        let target = PrinterTarget::Tcp { host: "127.0.0.1".to_string(), port: Some(port) };
+        send(&target, &job).unwrap();
+
+        let received = receiver.join().unwrap();
+        assert_eq!(received, job);
+        received
+    }
+
+    #[test]
+    fn from_image_thresholds_dark_pixels() {
+        let mut img = RgbImage::from_pixel(10, 2, Rgb([255, 255, 255]));
+        img.put_pixel(0, 0, Rgb([0, 0, 0]));
+        img.put_pixel(9, 1, Rgb([100, 100, 100]));
+        img.put_pixel(1, 0, Rgb([200, 200, 200]));
+
+        let bitmap = Bitmap::from_image(&img);
+        assert_eq!(bitmap.bytes_per_row(), 2);
+        assert_eq!(bitmap.data, vec![0x80, 0x00, 0x00, 0x40]);
+    }
+
+    #[test]
+    fn sends_zpl() {
+        let received = print(PrinterLanguage::Zpl, 1);
+        assert_eq!(
+            String::from_utf8(received).unwrap(),
+            "^XA^PW10^LL2^LH0,0^FO0,0^GFA,4,4,2,FFC08040^FS^PQ1^XZ\n"
+        );
+    }
+
+    #[test]
+    fn sends_tspl_inverted() {
+        let mut expected = b"SIZE 25.0 mm,10.0 mm\r\nGAP 2.0 mm,0 mm\r\nDIRECTION 1\r\nCLS\r\nBITMAP 0,0,2,2,0,".to_vec();
+        expected.extend_from_slice(&[0x00, 0x3F, 0x7F, 0xBF]);
+        expected.extend_from_slice(b"\r\nPRINT 1,2\r\n");
+
+        assert_eq!(print(PrinterLanguage::Tspl, 2), expected);
+    }
+
+    #[test]
+    fn sends_esc_pos_per_copy() {
+        let copy = [
+            0x1B, 0x40,
+            0x1D, 0x76, 0x30, 0x00, 0x02, 0x00, 0x02, 0x00,
+            0xFF, 0xC0, 0x80, 0x40,
+            0x1B, 0x64, 0x03,
+        ];
+
+        assert_eq!(print(PrinterLanguage::EscPos, 2), [copy, copy].concat());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn device_target_must_be_a_character_device() {
+        let file = std::env::temp_dir().join(format!("printer-test-{}", std::process::id()));
+        std::fs::write(&file, b"keep").unwrap();
+        let target = PrinterTarget::Device { path: file.to_string_lossy().to_string() };
+        let result = send(&target, b"^XA^XZ");
+        let contents = std::fs::read(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert!(result.unwrap_err().contains("is not a printer device"));
+        assert_eq!(contents, b"keep");
+
+        let directory = PrinterTarget::Device { path: std::env::temp_dir().to_string_lossy().to_string() };
+        assert!(send(&directory, b"^XA^XZ").is_err());
+        assert!(send(&PrinterTarget::Device { path: "/dev/null".to_string() }, b"^XA^XZ").is_ok());
+    }
+
+    #[test]
+    fn esc_pos_splits_tall_labels_into_bands() {
+        let bitmap = Bitmap { width: 8, height: 300, data: vec![0xAA; 300] };
+        let mut output = Vec::new();
+        encode_esc_pos(&bitmap, 1, &mut output);
+
+        assert_eq!(&output[2..10], &[0x1D, 0x76, 0x30, 0x00, 0x01, 0x00, 0xFF, 0x00]);
+        let second = 10 + 255;
+        assert_eq!(&output[second..second + 8], &[0x1D, 0x76, 0x30, 0x00, 0x01, 0x00, 0x2D, 0x00]);
+        assert_eq!(output.len(), 2 + 8 + 255 + 8 + 45 + 3);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::database::models::LabelStock;
+
+fn sheet(id: &str, name: &str, page: (f32, f32), margin: (f32, f32), label: (f32, f32), pitch: (f32, f32), (columns, rows): (u32, u32)) -> LabelStock {
+    LabelStock {
+        id: id.to_string(),
+        name: name.to_string(),
+        page_width: page.0,
+        page_height: page.1,
+        margin_left: margin.0,
+        margin_top: margin.1,
+        label_width: label.0,
+        label_height: label.1,
+        horizontal_pitch: pitch.0,
+        vertical_pitch: pitch.1,
+        columns,
+        rows,
+        outline: false,
+    }
+}
+
+/// A roll of single labels where every label is its own page
+fn roll(id: &str, name: &str, width: f32, height: f32) -> LabelStock {
+    sheet(id, name, (width, height), (0.0, 0.0), (width, height), (width, height), (1, 1))
+}
+
+/// Built-in sticker sheets and thermal rolls
+pub fn presets() -> Vec<LabelStock> {
+    vec![
+        sheet("avery-l7160", "Avery L7160 (A4, 21 labels 63.5×38.1mm)", (210.0, 297.0), (7.25, 15.15), (63.5, 38.1), (66.04, 38.1), (3, 7)),
+        sheet("avery-l7163", "Avery L7163 (A4, 14 labels 99.1×38.1mm)", (210.0, 297.0), (4.65, 15.15), (99.1, 38.1), (101.6, 38.1), (2, 7)),
+        sheet("avery-l7651", "Avery L7651 (A4, 65 labels 38.1×21.2mm)", (210.0, 297.0), (4.75, 10.7), (38.1, 21.2), (40.64, 21.2), (5, 13)),
+        sheet("avery-5160", "Avery 5160 (Letter, 30 labels 66.7×25.4mm)", (215.9, 279.4), (4.76, 12.7), (66.68, 25.4), (69.85, 25.4), (3, 10)),
+        roll("thermal-40x30", "热敏标签 40×30mm", 40.0, 30.0),
+        roll("thermal-50x25", "热敏标签 50×25mm", 50.0, 25.0),
+        roll("thermal-60x40", "热敏标签 60×40mm", 60.0, 40.0),
+    ]
+}
+
+/// Plain paper divided into equal cells with cut lines and no margins
+pub fn paper_grid(paper_size: &str, columns: u32, rows: u32) -> LabelStock {
+    let (page_width, page_height) = match paper_size {
+        "A4" => (210.0, 297.0),
+        "Letter" => (215.9, 279.4),
+        "A5" => (148.0, 210.0),
+        _ => (210.0, 297.0), // Default A4
+    };
+
+    let label_width = page_width / columns as f32;
+    let label_height = page_height / rows as f32;
+
+    LabelStock {
+        outline: true,
+        ..sheet(
+            &format!("{}-{}x{}", paper_size, columns, rows),
+            &format!("{} {}×{}", paper_size, columns, rows),
+            (page_width, page_height),
+            (0.0, 0.0),
+            (label_width, label_height),
+            (label_width, label_height),
+            (columns, rows),
+        )
+    }
+}
+
+/// Check that the labels fit on the page and don't overlap
+pub fn validate(stock: &LabelStock) -> Result<(), String> {
+    if stock.columns == 0 || stock.rows == 0 {
+        return Err("Label stock must have at least one column and one row".to_string());
+    }
+    if stock.label_width <= 0.0 || stock.label_height <= 0.0 {
+        return Err("Label width and height must be positive".to_string());
+    }
+    if stock.horizontal_pitch < stock.label_width || stock.vertical_pitch < stock.label_height {
+        return Err("Label pitch must not be smaller than the label size".to_string());
+    }
+
+    // Allow for rounding in published sheet dimensions
+    let tolerance = 0.5;
+    let right = stock.margin_left + stock.horizontal_pitch * (stock.columns - 1) as f32 + stock.label_width;
+    let bottom = stock.margin_top + stock.vertical_pitch * (stock.rows - 1) as f32 + stock.label_height;
+    if right > stock.page_width + tolerance || bottom > stock.page_height + tolerance {
+        return Err(format!("Labels do not fit on a {}×{}mm page", stock.page_width, stock.page_height));
+    }
+
+    Ok(())
+}
+
+pub fn labels_per_page(stock: &LabelStock) -> usize {
+    (stock.columns * stock.rows) as usize
+}
+
+/// Top-left corner of the label at `position` on a page, in mm from the page's top-left corner.
+/// Positions run left to right, then top to bottom.
+pub fn label_origin(stock: &LabelStock, position: usize) -> (f32, f32) {
+    let col = (position % stock.columns as usize) as f32;
+    let row = (position / stock.columns as usize) as f32;
+    (
+        stock.margin_left + col * stock.horizontal_pitch,
+        stock.margin_top + row * stock.vertical_pitch,
+    )
+}
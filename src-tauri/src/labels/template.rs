@@ -0,0 +1,437 @@
+use crate::database::models::{LabelElement, LabelField, LabelKind, LabelLayout, QrOptions};
+use crate::labels::LabelData;
+use crate::render::barcode::{self, Barcode};
+use crate::render::qr::{self, QrMatrix};
+use crate::render::text::{self, FittedText};
+use image::RgbImage;
+use printpdf::{Color, IndirectFontRef, Mm, PdfLayerReference, Rgb};
+use rusttype::Font;
+use unicode_segmentation::UnicodeSegmentation;
+
+const MM_PER_PT: f32 = 25.4 / 72.0;
+
+/// Space kept between text and a QR code or the label edge, in mm
+const TEXT_GAP: f32 = 2.0;
+
+/// Built-in item layout, matching the original hard-coded label:
+/// name, specifications, quantity and location on the left, QR code on the right.
+/// Text shrinks to fit beside the QR code instead of being cut at a fixed length.
+pub fn default_item_layout() -> LabelLayout {
+    LabelLayout {
+        width: 70.0,
+        height: 40.0,
+        elements: vec![
+            LabelElement::Field {
+                field: LabelField::Name,
+                x: 5.0,
+                y: 5.0,
+                font_size: 14.0,
+                label: Some("物料名: ".to_string()),
+                max_chars: None,
+                width: None,
+                max_lines: None,
+                min_font_size: Some(9.0),
+            },
+            LabelElement::Field {
+                field: LabelField::Specifications,
+                x: 5.0,
+                y: 11.0,
+                font_size: 10.0,
+                label: Some("规格: ".to_string()),
+                max_chars: None,
+                width: None,
+                max_lines: None,
+                min_font_size: Some(7.0),
+            },
+            LabelElement::Field {
+                field: LabelField::Quantity,
+                x: 5.0,
+                y: 16.0,
+                font_size: 11.0,
+                label: Some("数量: ".to_string()),
+                max_chars: None,
+                width: None,
+                max_lines: None,
+                min_font_size: Some(8.0),
+            },
+            LabelElement::Field {
+                field: LabelField::Location,
+                x: 5.0,
+                y: 21.0,
+                font_size: 10.0,
+                label: Some("位置: ".to_string()),
+                max_chars: None,
+                width: None,
+                max_lines: Some(2),
+                min_font_size: Some(7.0),
+            },
+            LabelElement::Qr {
+                x: 35.0,
+                y: 5.0,
+                size: 30.0,
+            },
+        ],
+    }
+}
+
+/// Built-in location layout for box and shelf labels: name, type, full path and a
+/// summary of the contents on the left, QR code on the right.
+pub fn default_location_layout() -> LabelLayout {
+    LabelLayout {
+        width: 70.0,
+        height: 40.0,
+        elements: vec![
+            LabelElement::Field {
+                field: LabelField::Name,
+                x: 5.0,
+                y: 4.0,
+                font_size: 16.0,
+                label: None,
+                max_chars: None,
+                width: None,
+                max_lines: None,
+                min_font_size: Some(10.0),
+            },
+            LabelElement::Field {
+                field: LabelField::LocationType,
+                x: 5.0,
+                y: 11.0,
+                font_size: 9.0,
+                label: Some("类型: ".to_string()),
+                max_chars: None,
+                width: None,
+                max_lines: None,
+                min_font_size: None,
+            },
+            LabelElement::Field {
+                field: LabelField::Path,
+                x: 5.0,
+                y: 16.0,
+                font_size: 9.0,
+                label: Some("路径: ".to_string()),
+                max_chars: None,
+                width: None,
+                max_lines: Some(2),
+                min_font_size: Some(7.0),
+            },
+            LabelElement::Field {
+                field: LabelField::Contents,
+                x: 5.0,
+                y: 25.0,
+                font_size: 8.0,
+                label: None,
+                max_chars: None,
+                width: None,
+                max_lines: Some(3),
+                min_font_size: Some(6.0),
+            },
+            LabelElement::Qr {
+                x: 40.0,
+                y: 7.0,
+                size: 26.0,
+            },
+        ],
+    }
+}
+
+/// Pre-printed blank code: a QR code with the start of the code below it, so labels
+/// can be told apart when they are attached to new locations
+pub fn reserved_code_layout() -> LabelLayout {
+    LabelLayout {
+        width: 20.0,
+        height: 26.0,
+        elements: vec![
+            LabelElement::Qr {
+                x: 1.0,
+                y: 1.0,
+                size: 18.0,
+            },
+            LabelElement::Field {
+                field: LabelField::Name,
+                x: 1.0,
+                y: 20.5,
+                font_size: 7.0,
+                label: None,
+                max_chars: None,
+                width: Some(18.0),
+                max_lines: None,
+                min_font_size: Some(5.0),
+            },
+        ],
+    }
+}
+
+pub fn default_layout(kind: LabelKind) -> LabelLayout {
+    match kind {
+        LabelKind::Item => default_item_layout(),
+        LabelKind::Location => default_location_layout(),
+    }
+}
+
+/// Check that a layout can be rendered
+pub fn validate_layout(layout: &LabelLayout) -> Result<(), String> {
+    if layout.width <= 0.0 || layout.height <= 0.0 {
+        return Err("Label width and height must be positive".to_string());
+    }
+
+    for element in &layout.elements {
+        match element {
+            LabelElement::Field { font_size, width, min_font_size, .. } | LabelElement::Text { font_size, width, min_font_size, .. } => {
+                if *font_size <= 0.0 || min_font_size.is_some_and(|size| size <= 0.0) {
+                    return Err("Font size must be positive".to_string());
+                }
+                if width.is_some_and(|width| width <= 0.0) {
+                    return Err("Text width must be positive".to_string());
+                }
+            }
+            LabelElement::Qr { size, .. } => {
+                if *size <= 0.0 {
+                    return Err("QR code size must be positive".to_string());
+                }
+            }
+            LabelElement::Barcode { width, height, .. } => {
+                if *width <= 0.0 || *height <= 0.0 {
+                    return Err("Barcode width and height must be positive".to_string());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve the text printed by a field, or `None` if the item has no value for it
+fn field_value(data: &LabelData, field: LabelField) -> Option<String> {
+    match field {
+        LabelField::Name => Some(data.name.clone()),
+        LabelField::Category => data.category.clone(),
+        LabelField::Specifications => data.specifications.clone(),
+        LabelField::Quantity => {
+            let unit = data.unit.as_deref().unwrap_or("个");
+            Some(format!("{} {}", data.quantity, unit))
+        }
+        LabelField::Location => data.location.clone(),
+        LabelField::Notes => data.notes.clone(),
+        LabelField::LocationType => data.location_type.clone(),
+        LabelField::Path => data.path.clone(),
+        LabelField::Contents => data.contents.clone(),
+    }
+}
+
+/// Text of a text-like element, with the field label and truncation applied
+fn element_text(element: &LabelElement, data: &LabelData) -> Option<String> {
+    match element {
+        LabelElement::Field { field, label, max_chars, .. } => {
+            let mut value = field_value(data, *field)?;
+            if let Some(max_chars) = max_chars {
+                value = value.graphemes(true).take(*max_chars).collect();
+            }
+            Some(format!("{}{}", label.as_deref().unwrap_or(""), value))
+        }
+        LabelElement::Text { text, .. } => Some(text.clone()),
+        LabelElement::Qr { .. } | LabelElement::Barcode { .. } => None,
+    }
+}
+
+/// Data encoded by a barcode element: the chosen field, or the QR payload
+fn barcode_data(data: &LabelData, field: Option<LabelField>) -> Result<String, String> {
+    match field {
+        Some(field) => field_value(data, field).ok_or_else(|| format!("{} has no value to encode in its barcode", data.name)),
+        None => Ok(data.qr_data.clone()),
+    }
+}
+
+/// Width available to a text element in mm. Without an explicit width, text runs up to
+/// the nearest QR code or barcode on its right that shares its rows, or to the label edge.
+fn available_width(layout: &LabelLayout, x: f32, y: f32, height: f32) -> f32 {
+    let right = layout
+        .elements
+        .iter()
+        .filter_map(|element| {
+            let (code_x, code_y, code_height) = match element {
+                LabelElement::Qr { x, y, size } => (*x, *y, *size),
+                LabelElement::Barcode { x, y, height, .. } => (*x, *y, *height),
+                _ => return None,
+            };
+            (code_x > x && code_y < y + height && code_y + code_height > y).then_some(code_x)
+        })
+        .fold(layout.width, f32::min);
+
+    (right - x - TEXT_GAP).max(1.0)
+}
+
+/// A text element laid out in layout units: position in mm, font size in pt
+struct PlacedText {
+    x: f32,
+    y: f32,
+    text: FittedText,
+}
+
+fn place_text(font: &Font, layout: &LabelLayout, element: &LabelElement, data: &LabelData) -> Result<Option<PlacedText>, String> {
+    let (x, y, font_size, width, max_lines, min_font_size) = match element {
+        LabelElement::Field { x, y, font_size, width, max_lines, min_font_size, .. }
+        | LabelElement::Text { x, y, font_size, width, max_lines, min_font_size, .. } => (*x, *y, *font_size, *width, *max_lines, *min_font_size),
+        LabelElement::Qr { .. } | LabelElement::Barcode { .. } => return Ok(None),
+    };
+
+    let Some(content) = element_text(element, data) else {
+        return Ok(None);
+    };
+    text::check_glyphs(font, &content)?;
+    let max_lines = max_lines.unwrap_or(1);
+    let line_height = font_size * MM_PER_PT * text::LINE_HEIGHT;
+    let width = width.unwrap_or_else(|| available_width(layout, x, y, line_height * max_lines as f32));
+
+    // Measure in pt so the result is the same for PDF and image output
+    let fitted = text::fit(
+        font,
+        &content,
+        font_size,
+        min_font_size.unwrap_or(font_size),
+        width / MM_PER_PT,
+        max_lines,
+    );
+
+    Ok(Some(PlacedText { x, y, text: fitted }))
+}
+
+/// Uniform scale factor mapping layout mm to the actual label size
+fn layout_scale(layout: &LabelLayout, width: f32, height: f32) -> f32 {
+    (width / layout.width).min(height / layout.height)
+}
+
+/// Document font plus the same font parsed for measuring text
+pub struct PdfFont<'a> {
+    pub handle: &'a IndirectFontRef,
+    pub metrics: &'a Font<'a>,
+}
+
+/// Render one label into a PDF layer. `origin` is the top-left corner of the label cell.
+pub fn render_pdf(
+    layer: &PdfLayerReference,
+    font: &PdfFont,
+    layout: &LabelLayout,
+    data: &LabelData,
+    qr_options: &QrOptions,
+    origin: (Mm, Mm),
+    (width, height): (Mm, Mm),
+) -> Result<(), String> {
+    let (x, y) = origin;
+    let scale = layout_scale(layout, width.0, height.0);
+
+    for element in &layout.elements {
+        match element {
+            LabelElement::Field { .. } | LabelElement::Text { .. } => {
+                let Some(placed) = place_text(font.metrics, layout, element, data)? else {
+                    continue;
+                };
+
+                let font_size = placed.text.font_size * scale;
+                let line_height = font_size * MM_PER_PT * text::LINE_HEIGHT;
+
+                // A QR code drawn earlier leaves its own fill color behind
+                layer.set_fill_color(Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)));
+
+                for (i, line) in placed.text.lines.iter().enumerate() {
+                    // Text is placed by its baseline, roughly 80% of the font size below the top
+                    let top = y.0 - placed.y * scale - line_height * i as f32;
+                    let baseline = top - font_size * MM_PER_PT * 0.8;
+                    layer.use_text(line.clone(), font_size, Mm(x.0 + placed.x * scale), Mm(baseline), font.handle);
+                }
+            }
+            LabelElement::Qr { x: el_x, y: el_y, size } => {
+                let matrix = QrMatrix::encode(&data.qr_data, qr_options.error_correction)?;
+                let size = size * scale;
+                let bottom = y.0 - el_y * scale - size;
+                qr::draw_on_pdf_layer(layer, &matrix, qr_options, Mm(x.0 + el_x * scale), Mm(bottom), Mm(size))?;
+            }
+            LabelElement::Barcode { symbology, field, x: el_x, y: el_y, width: el_width, height: el_height } => {
+                let code = Barcode::encode(*symbology, &barcode_data(data, *field)?, qr_options.error_correction)?;
+                let bottom = y.0 - (el_y + el_height) * scale;
+                barcode::draw_on_pdf_layer(
+                    layer,
+                    &code,
+                    qr_options,
+                    Mm(x.0 + el_x * scale),
+                    Mm(bottom),
+                    Mm(el_width * scale),
+                    Mm(el_height * scale),
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Render one label into an image. `origin` is the top-left pixel of the label cell.
+pub fn render_image(
+    img: &mut RgbImage,
+    font: &Font,
+    layout: &LabelLayout,
+    data: &LabelData,
+    qr_options: &QrOptions,
+    origin: (u32, u32),
+    (width, height): (u32, u32),
+) -> Result<(), String> {
+    let (x, y) = origin;
+    let px_per_mm = layout_scale(layout, width as f32, height as f32);
+
+    for element in &layout.elements {
+        match element {
+            LabelElement::Field { .. } | LabelElement::Text { .. } => {
+                let Some(placed) = place_text(font, layout, element, data)? else {
+                    continue;
+                };
+
+                let font_px = placed.text.font_size * MM_PER_PT * px_per_mm;
+                let line_height = font_px * text::LINE_HEIGHT;
+                let text_x = x + (placed.x * px_per_mm) as u32;
+
+                for (i, line) in placed.text.lines.iter().enumerate() {
+                    let text_y = y + (placed.y * px_per_mm + line_height * i as f32) as u32;
+                    text::draw_text(img, font, line, text_x, text_y, font_px);
+                }
+            }
+            LabelElement::Qr { x: el_x, y: el_y, size } => {
+                let matrix = QrMatrix::encode(&data.qr_data, qr_options.error_correction)?;
+                let qr_x = x + (el_x * px_per_mm) as u32;
+                let qr_y = y + (el_y * px_per_mm) as u32;
+                qr::draw_on_image(img, &matrix, qr_options, qr_x, qr_y, (size * px_per_mm) as u32)?;
+            }
+            LabelElement::Barcode { symbology, field, x: el_x, y: el_y, width: el_width, height: el_height } => {
+                let code = Barcode::encode(*symbology, &barcode_data(data, *field)?, qr_options.error_correction)?;
+                barcode::draw_on_image(
+                    img,
+                    &code,
+                    qr_options,
+                    x + (el_x * px_per_mm) as u32,
+                    y + (el_y * px_per_mm) as u32,
+                    (el_width * px_per_mm) as u32,
+                    (el_height * px_per_mm) as u32,
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::labels::printer::render_bitmap;
+    use crate::render::font;
+
+    #[test]
+    fn bundled_font_renders_built_in_layouts_in_chinese() {
+        let font = font::bundled().metrics().unwrap();
+        let item = LabelData::sample();
+        let location = LabelData { kind: LabelKind::Location, ..LabelData::sample() };
+
+        for (layout, data) in [(default_item_layout(), &item), (default_location_layout(), &location)] {
+            let bitmap = render_bitmap(&font, &layout, data, &QrOptions::default(), 203).unwrap();
+            assert!(bitmap.data.iter().any(|b| *b != 0));
+        }
+    }
+}
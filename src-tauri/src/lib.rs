@@ -1,21 +1,63 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod alerts;
+mod bom;
+mod codes;
 mod commands;
 mod database;
+mod labels;
+mod render;
+mod scan;
+mod stock;
+mod suppliers;
+mod tags;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let mut builder = tauri::Builder::default();
+
+    // On Windows and Linux a scanned ics:// link starts a new process; hand the link
+    // to the running app instead (the deep-link plugin then emits it as usual)
+    #[cfg(desktop)]
+    {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            use tauri::Manager;
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.set_focus();
+            }
+        }));
+    }
+
+    builder
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_sql::Builder::default().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .setup(|app| {
+            use tauri::Manager;
+
             tauri::async_runtime::block_on(async {
                 // Initialize database on first run
                 database::init(app.handle()).await?;
+                codes::init_instance_id(&app.state::<database::DbPool>()).await?;
                 Ok::<(), Box<dyn std::error::Error>>(())
             })?;
+
+            // Daily low-stock summary when digest notifications are enabled
+            alerts::spawn_digest_task(app.handle().clone());
+
+            // Installers register the ics:// scheme; in development it has to be
+            // registered at runtime
+            #[cfg(any(windows, target_os = "linux"))]
+            {
+                use tauri_plugin_deep_link::DeepLinkExt;
+                if let Err(e) = app.deep_link().register_all() {
+                    eprintln!("Failed to register deep link scheme: {}", e);
+                }
+            }
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -24,15 +66,101 @@ pub fn run() {
             commands::locations::update_location,
             commands::locations::delete_location,
             commands::locations::get_location_by_qr,
+            commands::locations::resolve_qr_code,
             commands::items::get_items,
             commands::items::create_item,
             commands::items::update_item,
             commands::items::delete_item,
             commands::items::update_quantity,
+            commands::items::get_item_stock,
+            commands::items::get_location_stock,
+            commands::items::add_item_stock,
+            commands::items::delete_item_stock,
+            commands::items::transfer_stock,
+            commands::bulk::bulk_move_items,
+            commands::bulk::bulk_set_category,
+            commands::bulk::bulk_adjust_quantities,
+            commands::bulk::bulk_delete_items,
+            commands::bulk::bulk_restore_items,
+            commands::bulk::bulk_tag_items,
+            commands::tags::get_tags,
+            commands::tags::create_tag,
+            commands::tags::rename_tag,
+            commands::tags::merge_tags,
+            commands::tags::delete_tag,
+            commands::tags::set_item_tags,
+            commands::tags::set_location_tags,
+            commands::projects::get_projects,
+            commands::projects::create_project,
+            commands::projects::update_project,
+            commands::projects::delete_project,
+            commands::projects::get_project_bom,
+            commands::projects::set_bom_line,
+            commands::projects::delete_bom_line,
+            commands::projects::check_buildability,
+            commands::projects::reserve_build,
+            commands::projects::get_project_builds,
+            commands::projects::cancel_build,
+            commands::projects::consume_build,
+            commands::bom::preview_bom_import,
+            commands::bom::create_items_from_bom,
+            commands::bom::apply_bom_import,
+            commands::suppliers::get_suppliers,
+            commands::suppliers::create_supplier,
+            commands::suppliers::update_supplier,
+            commands::suppliers::delete_supplier,
+            commands::suppliers::get_manufacturers,
+            commands::suppliers::create_manufacturer,
+            commands::suppliers::update_manufacturer,
+            commands::suppliers::delete_manufacturer,
+            commands::suppliers::get_item_offers,
+            commands::suppliers::create_item_offer,
+            commands::suppliers::update_item_offer,
+            commands::suppliers::delete_item_offer,
+            commands::suppliers::get_price_history,
+            commands::stocktake::start_stocktake,
+            commands::stocktake::get_stocktakes,
+            commands::stocktake::get_stocktake,
+            commands::stocktake::scan_stocktake_location,
+            commands::stocktake::record_stocktake_count,
+            commands::stocktake::clear_stocktake_count,
+            commands::stocktake::commit_stocktake,
+            commands::stocktake::abandon_stocktake,
+            commands::reports::get_low_stock_report,
+            commands::reports::get_dashboard_stats,
+            commands::reports::get_inventory_valuation,
+            commands::alerts::get_stock_alert_settings,
+            commands::alerts::set_stock_alert_settings,
+            commands::alerts::set_item_alerts_muted,
+            commands::alerts::get_muted_items,
             commands::qrcode::generate_location_qr,
+            commands::qrcode::export_location_qr_svg,
             commands::qrcode::generate_batch_qr,
+            commands::qrcode::get_qr_settings,
+            commands::qrcode::set_qr_signing,
+            commands::qrcode::regenerate_location_qr,
+            commands::qrcode::assign_location_qr,
+            commands::qrcode::assign_location_codes,
+            commands::qrcode::get_retired_location_codes,
+            commands::qrcode::get_reserved_codes,
             commands::pdf::generate_pdf_labels,
+            commands::pdf::generate_reserved_code_sheet,
+            commands::pdf::reprint_reserved_code_sheet,
             commands::pdf::generate_image_labels,
+            commands::labels::get_label_templates,
+            commands::labels::get_default_label_layout,
+            commands::labels::get_label_stocks,
+            commands::labels::create_label_template,
+            commands::labels::update_label_template,
+            commands::labels::delete_label_template,
+            commands::labels::preview_label_template,
+            commands::labels::list_fonts,
+            commands::labels::get_label_font,
+            commands::labels::set_label_font,
+            commands::printer::generate_printer_labels,
+            commands::printer::print_labels,
+            commands::scan::decode_image_codes,
+            commands::scan::scan_images,
             commands::sync::configure_webdav,
             commands::sync::configure_s3,
             commands::sync::sync_upload,
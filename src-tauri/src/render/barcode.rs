@@ -0,0 +1,200 @@
+use crate::database::models::{QrErrorCorrection, QrOptions, Symbology};
+use crate::render::qr::{self, fill_rect, rect_ring, to_pdf_color, QrMatrix};
+use barcoders::sym::code128::Code128;
+use barcoders::sym::ean13::EAN13;
+use datamatrix::{DataMatrix, SymbolList};
+use image::{Rgb, RgbImage};
+use printpdf::{Mm, PdfLayerReference, Polygon, PolygonMode, WindingOrder};
+
+/// Quiet zone required on each side of a linear barcode, in modules
+const LINEAR_QUIET_ZONE: u32 = 10;
+
+/// An encoded barcode as a grid of dark/light modules. Linear barcodes have a single
+/// row that is stretched to the full height when drawn.
+pub struct Barcode {
+    columns: usize,
+    rows: usize,
+    modules: Vec<bool>,
+    linear: bool,
+}
+
+impl Barcode {
+    pub fn encode(symbology: Symbology, data: &str, error_correction: QrErrorCorrection) -> Result<Self, String> {
+        match symbology {
+            Symbology::Qr => {
+                let matrix = QrMatrix::encode(data, error_correction)?;
+                let width = matrix.width();
+                let modules = (0..width * width).map(|i| matrix.is_dark(i % width, i / width)).collect();
+                Ok(Self { columns: width, rows: width, modules, linear: false })
+            }
+            Symbology::DataMatrix => {
+                let code = DataMatrix::encode_str(data, SymbolList::default())
+                    .map_err(|e| format!("DataMatrix generation failed: {:?}", e))?;
+                let bitmap = code.bitmap();
+                let mut modules = vec![false; bitmap.width() * bitmap.height()];
+                for (x, y) in bitmap.pixels() {
+                    modules[y * bitmap.width() + x] = true;
+                }
+                Ok(Self { columns: bitmap.width(), rows: bitmap.height(), modules, linear: false })
+            }
+            Symbology::Code128 => {
+                if data.is_empty() || !data.chars().all(|c| c.is_ascii() && !c.is_ascii_control()) {
+                    return Err(format!("Code128 can only encode printable ASCII: {}", data));
+                }
+                // Character set C packs digit pairs, set B covers all printable ASCII
+                let charset = if data.len() >= 4 && data.len().is_multiple_of(2) && data.chars().all(|c| c.is_ascii_digit()) {
+                    'Ć'
+                } else {
+                    'Ɓ'
+                };
+                let code = Code128::new(format!("{}{}", charset, data))
+                    .map_err(|e| format!("Code128 generation failed: {}", e))?;
+                Ok(Self::linear(&code.encode()))
+            }
+            Symbology::Ean13 => {
+                // 12 digits get a check digit appended, 13 digits must have a valid one
+                let code = EAN13::new(data.trim())
+                    .map_err(|e| format!("EAN-13 generation failed for {}: {}", data, e))?;
+                Ok(Self::linear(&code.encode()))
+            }
+        }
+    }
+
+    fn linear(bars: &[u8]) -> Self {
+        Self {
+            columns: bars.len(),
+            rows: 1,
+            modules: bars.iter().map(|bar| *bar == 1).collect(),
+            linear: true,
+        }
+    }
+
+    fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.columns + x]
+    }
+
+    /// Width and height in modules, including the quiet zone
+    fn size_with_quiet_zone(&self, quiet_zone: u32) -> (u32, u32) {
+        if self.linear {
+            (self.columns as u32 + 2 * LINEAR_QUIET_ZONE, 1)
+        } else {
+            (self.columns as u32 + 2 * quiet_zone, self.rows as u32 + 2 * quiet_zone)
+        }
+    }
+
+    fn quiet_zone(&self, quiet_zone: u32) -> u32 {
+        if self.linear { LINEAR_QUIET_ZONE } else { quiet_zone }
+    }
+
+    /// Horizontal runs of dark modules as (row, first column, length)
+    fn runs(&self) -> Vec<(usize, usize, usize)> {
+        let mut runs = Vec::new();
+        for y in 0..self.rows {
+            let mut x = 0;
+            while x < self.columns {
+                if !self.is_dark(x, y) {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < self.columns && self.is_dark(x, y) {
+                    x += 1;
+                }
+                runs.push((y, start, x - start));
+            }
+        }
+        runs
+    }
+}
+
+/// Draw a barcode into a `width` x `height` area of an image. 2D codes keep square
+/// modules and are centered; linear barcodes fill the full height.
+pub fn draw_on_image(img: &mut RgbImage, barcode: &Barcode, options: &QrOptions, x: u32, y: u32, width: u32, height: u32) -> Result<(), String> {
+    qr::check_options(options)?;
+    let foreground = Rgb(qr::parse_hex_color(&options.foreground)?);
+    let background = Rgb(qr::parse_hex_color(&options.background)?);
+
+    let (columns, rows) = barcode.size_with_quiet_zone(options.quiet_zone);
+    let (module_width, module_height) = if barcode.linear {
+        (width / columns, height)
+    } else {
+        let module = (width / columns).min(height / rows);
+        (module, module)
+    };
+    if module_width == 0 || module_height == 0 {
+        return Err(format!("Barcode area of {}x{}px is too small for {} modules", width, height, columns));
+    }
+
+    fill_rect(img, x, y, width, height, background);
+
+    let quiet_zone = barcode.quiet_zone(options.quiet_zone);
+    let left = x + (width - module_width * columns) / 2 + quiet_zone * module_width;
+    let top = if barcode.linear { y } else { y + (height - module_height * rows) / 2 + quiet_zone * module_height };
+
+    for (row, start, length) in barcode.runs() {
+        fill_rect(
+            img,
+            left + start as u32 * module_width,
+            top + row as u32 * module_height,
+            length as u32 * module_width,
+            module_height,
+            foreground,
+        );
+    }
+
+    Ok(())
+}
+
+/// Draw a barcode as filled vector rectangles into a `width` x `height` area of a PDF layer.
+/// `x`/`y` is the bottom-left corner.
+pub fn draw_on_pdf_layer(layer: &PdfLayerReference, barcode: &Barcode, options: &QrOptions, x: Mm, y: Mm, width: Mm, height: Mm) -> Result<(), String> {
+    qr::check_options(options)?;
+    let foreground = qr::parse_hex_color(&options.foreground)?;
+    let background = qr::parse_hex_color(&options.background)?;
+
+    let (columns, rows) = barcode.size_with_quiet_zone(options.quiet_zone);
+    let (module_width, module_height) = if barcode.linear {
+        (width.0 / columns as f32, height.0)
+    } else {
+        let module = (width.0 / columns as f32).min(height.0 / rows as f32);
+        (module, module)
+    };
+
+    layer.set_fill_color(to_pdf_color(background));
+    layer.add_polygon(Polygon {
+        rings: vec![rect_ring(x, y, width, height)],
+        mode: PolygonMode::Fill,
+        winding_order: WindingOrder::NonZero,
+    });
+
+    let quiet_zone = barcode.quiet_zone(options.quiet_zone) as f32;
+    let left = x.0 + (width.0 - module_width * columns as f32) / 2.0 + quiet_zone * module_width;
+    // PDF coordinates grow upwards, so row 0 is at the top
+    let top = if barcode.linear {
+        y.0 + height.0
+    } else {
+        y.0 + height.0 - (height.0 - module_height * rows as f32) / 2.0 - quiet_zone * module_height
+    };
+
+    let rings = barcode
+        .runs()
+        .into_iter()
+        .map(|(row, start, length)| {
+            rect_ring(
+                Mm(left + start as f32 * module_width),
+                Mm(top - (row + 1) as f32 * module_height),
+                Mm(length as f32 * module_width),
+                Mm(module_height),
+            )
+        })
+        .collect();
+
+    layer.set_fill_color(to_pdf_color(foreground));
+    layer.add_polygon(Polygon {
+        rings,
+        mode: PolygonMode::Fill,
+        winding_order: WindingOrder::NonZero,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,300 @@
+use crate::database::models::{FontInfo, FontSetting};
+use rusttype::Font;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Last-resort font compiled into the binary: Noto Sans SC cut down to Latin, common
+/// symbols and the GB2312 hanzi, generated by `fonts/subset-noto-sans-sc.sh`
+const BUNDLED_FONT: &[u8] = include_bytes!("../../fonts/NotoSansSC-Regular.ttf");
+
+/// Character used to check whether a font can render Chinese labels
+const CJK_PROBE: char = '中';
+
+/// Font resolved by `load` and the setting it was resolved for. Finding a system font
+/// can mean reading every installed font file, so it is done once per setting.
+static LOADED: Mutex<Option<(Option<FontSetting>, LabelFont)>> = Mutex::new(None);
+
+/// Font chosen for label rendering, as a standalone TrueType/OpenType file
+/// (a single face extracted from a collection if necessary)
+#[derive(Clone)]
+pub struct LabelFont {
+    pub data: Vec<u8>,
+    /// Where the font came from, for logging
+    pub source: String,
+}
+
+impl LabelFont {
+    /// Parse the font for measuring and rasterizing text
+    pub fn metrics(&self) -> Result<Font<'static>, String> {
+        Font::try_from_vec(self.data.clone()).ok_or_else(|| "Failed to parse font data".to_string())
+    }
+}
+
+/// Well-known Chinese fonts, checked before scanning whole font directories
+fn preferred_font_paths() -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        vec![
+            PathBuf::from("C:\\Windows\\Fonts\\simhei.ttf"),  // 黑体
+            PathBuf::from("C:\\Windows\\Fonts\\simsun.ttc"),  // 宋体
+            PathBuf::from("C:\\Windows\\Fonts\\msyh.ttc"),    // 微软雅黑
+            PathBuf::from("C:\\Windows\\Fonts\\simkai.ttf"),  // 楷体
+        ]
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        vec![
+            PathBuf::from("/usr/share/fonts/truetype/wqy/wqy-zenhei.ttc"),
+            PathBuf::from("/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf"),
+            PathBuf::from("/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc"),
+            PathBuf::from("/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc"),
+            PathBuf::from("/system/fonts/NotoSansCJK-Regular.ttc"),  // Android
+            PathBuf::from("/system/fonts/DroidSansFallback.ttf"),    // Older Android
+            PathBuf::from("/System/Library/Fonts/PingFang.ttc"),     // macOS
+        ]
+    }
+}
+
+/// Directories searched for fonts, similar to fontconfig's default configuration
+fn font_directories() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    #[cfg(target_os = "windows")]
+    {
+        dirs.push(PathBuf::from("C:\\Windows\\Fonts"));
+        if let Some(local) = std::env::var_os("LOCALAPPDATA") {
+            dirs.push(PathBuf::from(local).join("Microsoft\\Windows\\Fonts"));
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        dirs.push(PathBuf::from("/System/Library/Fonts"));
+        dirs.push(PathBuf::from("/Library/Fonts"));
+        if let Some(home) = std::env::var_os("HOME") {
+            dirs.push(PathBuf::from(home).join("Library/Fonts"));
+        }
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        dirs.push(PathBuf::from("/system/fonts"));  // Android
+        dirs.push(PathBuf::from("/usr/share/fonts"));
+        dirs.push(PathBuf::from("/usr/local/share/fonts"));
+        if let Some(data_home) = std::env::var_os("XDG_DATA_HOME") {
+            dirs.push(PathBuf::from(data_home).join("fonts"));
+        }
+        if let Some(home) = std::env::var_os("HOME") {
+            dirs.push(PathBuf::from(&home).join(".local/share/fonts"));
+            dirs.push(PathBuf::from(home).join(".fonts"));
+        }
+    }
+
+    dirs
+}
+
+fn is_font_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| matches!(ext.to_ascii_lowercase().as_str(), "ttf" | "otf" | "ttc" | "otc"))
+        .unwrap_or(false)
+}
+
+/// Recursively collect font files below `dir`
+fn collect_font_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_font_files(&path, files);
+        } else if is_font_file(&path) {
+            files.push(path);
+        }
+    }
+}
+
+/// Describe every face in a font file
+fn describe_faces(path: &Path, data: &[u8]) -> Vec<FontInfo> {
+    let face_count = ttf_parser::fonts_in_collection(data).unwrap_or(1);
+
+    (0..face_count)
+        .filter_map(|index| {
+            let face = ttf_parser::Face::from_slice(data, index).ok()?;
+            let name = |id: u16| {
+                face.names()
+                    .into_iter()
+                    .filter(|name| name.name_id == id)
+                    .find_map(|name| name.to_string())
+            };
+
+            Some(FontInfo {
+                path: path.to_string_lossy().to_string(),
+                face_index: index,
+                family: name(ttf_parser::name_id::FAMILY).unwrap_or_default(),
+                full_name: name(ttf_parser::name_id::FULL_NAME).unwrap_or_default(),
+                supports_cjk: face.glyph_index(CJK_PROBE).is_some(),
+            })
+        })
+        .collect()
+}
+
+/// List all fonts installed in the system font directories
+pub fn scan_fonts() -> Vec<FontInfo> {
+    let mut files = Vec::new();
+    for dir in font_directories() {
+        collect_font_files(&dir, &mut files);
+    }
+    files.sort();
+    files.dedup();
+
+    files
+        .iter()
+        .filter_map(|path| std::fs::read(path).ok().map(|data| describe_faces(path, &data)))
+        .flatten()
+        .collect()
+}
+
+/// Extract a single face from a TrueType/OpenType collection into a standalone font file.
+/// Plain font files are returned unchanged for face 0.
+pub fn extract_face(data: &[u8], index: u32) -> Result<Vec<u8>, String> {
+    let read_u16 = |offset: usize| -> Result<u16, String> {
+        offset.checked_add(2).and_then(|end| data.get(offset..end))
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or_else(|| "Truncated font file".to_string())
+    };
+    let read_u32 = |offset: usize| -> Result<u32, String> {
+        offset.checked_add(4).and_then(|end| data.get(offset..end))
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| "Truncated font file".to_string())
+    };
+
+    if data.get(0..4) != Some(b"ttcf") {
+        return if index == 0 {
+            Ok(data.to_vec())
+        } else {
+            Err(format!("Font file has no face {}", index))
+        };
+    }
+
+    let face_count = read_u32(8)?;
+    if index >= face_count {
+        return Err(format!("Font collection has {} faces, no face {}", face_count, index));
+    }
+
+    // Offset table of the selected face, followed by its table records
+    let face_offset = read_u32(12 + index as usize * 4)? as usize;
+    let table_count = read_u16(face_offset.checked_add(4).ok_or("Truncated font file")?)? as usize;
+    let header_end = face_offset.checked_add(12 + table_count * 16).ok_or("Truncated font file")?;
+
+    let mut output = data.get(face_offset..header_end)
+        .ok_or("Truncated font file")?
+        .to_vec();
+
+    // Tables in a collection are addressed from the start of the collection file;
+    // copy each one and point its record at the new location
+    for table in 0..table_count {
+        let record = face_offset + 12 + table * 16;
+        let offset = read_u32(record + 8)? as usize;
+        let length = read_u32(record + 12)? as usize;
+        let end = offset.checked_add(length).ok_or("Truncated font file")?;
+        let table_data = data.get(offset..end).ok_or("Truncated font file")?;
+
+        let new_offset = output.len() as u32;
+        output[12 + table * 16 + 8..12 + table * 16 + 12].copy_from_slice(&new_offset.to_be_bytes());
+
+        output.extend_from_slice(table_data);
+        // Tables are 4-byte aligned
+        while output.len() % 4 != 0 {
+            output.push(0);
+        }
+    }
+
+    Ok(output)
+}
+
+pub fn load_face(path: &Path, index: u32) -> Result<LabelFont, String> {
+    let data = std::fs::read(path)
+        .map_err(|e| format!("Failed to read font file {:?}: {}", path, e))?;
+
+    Ok(LabelFont {
+        data: extract_face(&data, index)?,
+        source: format!("{} (face {})", path.display(), index),
+    })
+}
+
+/// The font compiled into the binary, for devices without a Chinese system font
+pub fn bundled() -> LabelFont {
+    LabelFont {
+        data: BUNDLED_FONT.to_vec(),
+        source: "bundled Noto Sans SC".to_string(),
+    }
+}
+
+/// Find the first face in a font file that can render Chinese text
+fn load_cjk_face(path: &Path) -> Option<LabelFont> {
+    let data = std::fs::read(path).ok()?;
+    let face = describe_faces(path, &data).into_iter().find(|face| face.supports_cjk)?;
+
+    Some(LabelFont {
+        data: extract_face(&data, face.face_index).ok()?,
+        source: format!("{} (face {})", path.display(), face.face_index),
+    })
+}
+
+/// Resolve the label font: the user's choice if set and readable, otherwise a well-known Chinese font,
+/// otherwise any Chinese-capable font in the system font directories, otherwise the
+/// bundled fallback. The result is reused until the setting changes.
+pub fn load(setting: Option<&FontSetting>) -> LabelFont {
+    let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((key, font)) = loaded.as_ref() {
+        if key.as_ref() == setting {
+            return font.clone();
+        }
+    }
+
+    let font = resolve(setting);
+    *loaded = Some((setting.cloned(), font.clone()));
+    font
+}
+
+fn resolve(setting: Option<&FontSetting>) -> LabelFont {
+    if let Some(setting) = setting {
+        // A configured font that has gone missing shouldn't block label printing
+        match load_face(Path::new(&setting.path), setting.face_index) {
+            Ok(font) => {
+                eprintln!("Using configured font: {}", font.source);
+                return font;
+            }
+            Err(e) => eprintln!("Configured font unavailable, searching system fonts: {}", e),
+        }
+    }
+
+    for path in preferred_font_paths() {
+        if path.exists() {
+            if let Some(font) = load_cjk_face(&path) {
+                eprintln!("Found Chinese font: {}", font.source);
+                return font;
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    for dir in font_directories() {
+        collect_font_files(&dir, &mut files);
+    }
+    files.sort();
+
+    for path in files {
+        if let Some(font) = load_cjk_face(&path) {
+            eprintln!("Found Chinese font: {}", font.source);
+            return font;
+        }
+    }
+
+    eprintln!("No Chinese font found, falling back to the bundled font");
+    bundled()
+}
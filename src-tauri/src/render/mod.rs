@@ -0,0 +1,4 @@
+pub mod barcode;
+pub mod font;
+pub mod qr;
+pub mod text;
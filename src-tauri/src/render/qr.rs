@@ -0,0 +1,281 @@
+use crate::database::models::{QrErrorCorrection, QrOptions, QrOutputFormat};
+use base64::Engine;
+use image::{Rgb, RgbImage};
+use printpdf::{Color as PdfColor, Mm, PdfLayerReference, Point, Polygon, PolygonMode, Rgb as PdfRgb, WindingOrder};
+use qrcode::{Color, EcLevel, QrCode};
+use std::io::Cursor;
+
+/// Largest module size in pixels accepted for rendering
+pub const MAX_MODULE_SIZE: u32 = 64;
+/// Widest quiet zone in modules accepted for rendering
+pub const MAX_QUIET_ZONE: u32 = 16;
+
+/// Encoded QR code as a square grid of dark/light modules
+pub struct QrMatrix {
+    width: usize,
+    modules: Vec<bool>,
+}
+
+impl QrMatrix {
+    pub fn encode(data: &str, error_correction: QrErrorCorrection) -> Result<Self, String> {
+        let ec_level = match error_correction {
+            QrErrorCorrection::L => EcLevel::L,
+            QrErrorCorrection::M => EcLevel::M,
+            QrErrorCorrection::Q => EcLevel::Q,
+            QrErrorCorrection::H => EcLevel::H,
+        };
+
+        let code = QrCode::with_error_correction_level(data, ec_level)
+            .map_err(|e| format!("QR code generation failed: {}", e))?;
+
+        Ok(Self {
+            width: code.width(),
+            modules: code.to_colors().into_iter().map(|c| c == Color::Dark).collect(),
+        })
+    }
+
+    /// Number of modules per side, excluding the quiet zone
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.width + x]
+    }
+}
+
+/// Parse a `#RRGGBB` (or `RRGGBB`) color string
+pub fn parse_hex_color(value: &str) -> Result<[u8; 3], String> {
+    let hex = value.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(format!("Invalid color: {}", value));
+    }
+
+    let mut rgb = [0u8; 3];
+    for (i, channel) in rgb.iter_mut().enumerate() {
+        *channel = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .map_err(|_| format!("Invalid color: {}", value))?;
+    }
+    Ok(rgb)
+}
+
+/// Reject module sizes and quiet zones outside the supported range, so a bad setting
+/// cannot produce a huge image
+pub fn check_options(options: &QrOptions) -> Result<(), String> {
+    if !(1..=MAX_MODULE_SIZE).contains(&options.module_size) {
+        return Err(format!("Module size must be between 1 and {}", MAX_MODULE_SIZE));
+    }
+    if options.quiet_zone > MAX_QUIET_ZONE {
+        return Err(format!("Quiet zone must be between 0 and {} modules", MAX_QUIET_ZONE));
+    }
+    Ok(())
+}
+
+/// Rasterize the matrix into an RGB image using the configured module size and quiet zone
+pub fn render_image(matrix: &QrMatrix, options: &QrOptions) -> Result<RgbImage, String> {
+    check_options(options)?;
+
+    let foreground = Rgb(parse_hex_color(&options.foreground)?);
+    let background = Rgb(parse_hex_color(&options.background)?);
+
+    let modules = matrix.width() as u32 + 2 * options.quiet_zone;
+    let image_size = modules * options.module_size;
+    let mut image = RgbImage::from_pixel(image_size, image_size, background);
+
+    for y in 0..matrix.width() {
+        for x in 0..matrix.width() {
+            if !matrix.is_dark(x, y) {
+                continue;
+            }
+
+            let left = (options.quiet_zone + x as u32) * options.module_size;
+            let top = (options.quiet_zone + y as u32) * options.module_size;
+            fill_rect(&mut image, left, top, options.module_size, options.module_size, foreground);
+        }
+    }
+
+    Ok(image)
+}
+
+/// Render the matrix as PNG bytes
+pub fn render_png(matrix: &QrMatrix, options: &QrOptions) -> Result<Vec<u8>, String> {
+    let image = render_image(matrix, options)?;
+
+    let mut buffer = vec![];
+    let mut cursor = Cursor::new(&mut buffer);
+    image.write_to(&mut cursor, image::ImageFormat::Png)
+        .map_err(|e| format!("PNG encoding failed: {}", e))?;
+
+    Ok(buffer)
+}
+
+/// Render the matrix as an SVG document, one path covering all dark modules.
+/// Coordinates are in modules, so the output scales without resampling.
+pub fn render_svg(matrix: &QrMatrix, options: &QrOptions) -> Result<String, String> {
+    check_options(options)?;
+    let foreground = parse_hex_color(&options.foreground)?;
+    let background = parse_hex_color(&options.background)?;
+
+    let modules = matrix.width() as u32 + 2 * options.quiet_zone;
+    let pixel_size = modules * options.module_size;
+
+    let mut path = String::new();
+    for y in 0..matrix.width() {
+        let mut x = 0;
+        while x < matrix.width() {
+            if !matrix.is_dark(x, y) {
+                x += 1;
+                continue;
+            }
+
+            let run_start = x;
+            while x < matrix.width() && matrix.is_dark(x, y) {
+                x += 1;
+            }
+
+            let left = options.quiet_zone as usize + run_start;
+            let top = options.quiet_zone as usize + y;
+            let run = x - run_start;
+            path.push_str(&format!("M{},{}h{}v1h-{}z", left, top, run, run));
+        }
+    }
+
+    Ok(format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{size}" height="{size}" viewBox="0 0 {modules} {modules}" shape-rendering="crispEdges">"#,
+            r#"<rect width="{modules}" height="{modules}" fill="{bg}"/>"#,
+            r#"<path d="{path}" fill="{fg}"/>"#,
+            "</svg>"
+        ),
+        size = pixel_size,
+        modules = modules,
+        bg = to_hex(background),
+        fg = to_hex(foreground),
+        path = path,
+    ))
+}
+
+/// Encode `data` and return it as a data URL in the requested output format
+pub fn render_data_url(data: &str, options: &QrOptions) -> Result<String, String> {
+    let matrix = QrMatrix::encode(data, options.error_correction)?;
+
+    match options.format {
+        QrOutputFormat::Png => {
+            let png = render_png(&matrix, options)?;
+            let base64_string = base64::engine::general_purpose::STANDARD.encode(&png);
+            Ok(format!("data:image/png;base64,{}", base64_string))
+        }
+        QrOutputFormat::Svg => {
+            let svg = render_svg(&matrix, options)?;
+            let base64_string = base64::engine::general_purpose::STANDARD.encode(svg.as_bytes());
+            Ok(format!("data:image/svg+xml;base64,{}", base64_string))
+        }
+    }
+}
+
+/// Draw the matrix into a `size` x `size` square of an existing image.
+/// The module size is chosen so the code plus quiet zone fits, and the code is centered.
+pub fn draw_on_image(img: &mut RgbImage, matrix: &QrMatrix, options: &QrOptions, x: u32, y: u32, size: u32) -> Result<(), String> {
+    check_options(options)?;
+    let foreground = Rgb(parse_hex_color(&options.foreground)?);
+    let background = Rgb(parse_hex_color(&options.background)?);
+
+    let modules = matrix.width() as u32 + 2 * options.quiet_zone;
+    let module_size = size / modules;
+    if module_size == 0 {
+        return Err(format!("QR code area of {}px is too small for {} modules", size, modules));
+    }
+
+    fill_rect(img, x, y, size, size, background);
+
+    let offset = (size - module_size * modules) / 2 + options.quiet_zone * module_size;
+    for qr_y in 0..matrix.width() {
+        for qr_x in 0..matrix.width() {
+            if matrix.is_dark(qr_x, qr_y) {
+                let left = x + offset + qr_x as u32 * module_size;
+                let top = y + offset + qr_y as u32 * module_size;
+                fill_rect(img, left, top, module_size, module_size, foreground);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Draw the matrix as filled vector rectangles into a `size` x `size` square of a PDF layer.
+/// `x`/`y` is the bottom-left corner. Horizontal runs of dark modules are merged into one
+/// rectangle each so the resulting path stays small.
+pub fn draw_on_pdf_layer(layer: &PdfLayerReference, matrix: &QrMatrix, options: &QrOptions, x: Mm, y: Mm, size: Mm) -> Result<(), String> {
+    check_options(options)?;
+    let foreground = parse_hex_color(&options.foreground)?;
+    let background = parse_hex_color(&options.background)?;
+
+    let modules = matrix.width() as u32 + 2 * options.quiet_zone;
+    let module_size = size.0 / modules as f32;
+
+    layer.set_fill_color(to_pdf_color(background));
+    layer.add_polygon(Polygon {
+        rings: vec![rect_ring(x, y, size, size)],
+        mode: PolygonMode::Fill,
+        winding_order: WindingOrder::NonZero,
+    });
+
+    let mut rings = vec![];
+    for qr_y in 0..matrix.width() {
+        let mut qr_x = 0;
+        while qr_x < matrix.width() {
+            if !matrix.is_dark(qr_x, qr_y) {
+                qr_x += 1;
+                continue;
+            }
+
+            let run_start = qr_x;
+            while qr_x < matrix.width() && matrix.is_dark(qr_x, qr_y) {
+                qr_x += 1;
+            }
+
+            // PDF coordinates grow upwards, so row 0 is at the top of the square
+            let left = x.0 + (options.quiet_zone as usize + run_start) as f32 * module_size;
+            let bottom = y.0 + size.0 - (options.quiet_zone as usize + qr_y + 1) as f32 * module_size;
+            let run_width = (qr_x - run_start) as f32 * module_size;
+            rings.push(rect_ring(Mm(left), Mm(bottom), Mm(run_width), Mm(module_size)));
+        }
+    }
+
+    layer.set_fill_color(to_pdf_color(foreground));
+    layer.add_polygon(Polygon {
+        rings,
+        mode: PolygonMode::Fill,
+        winding_order: WindingOrder::NonZero,
+    });
+
+    Ok(())
+}
+
+pub fn rect_ring(x: Mm, y: Mm, width: Mm, height: Mm) -> Vec<(Point, bool)> {
+    vec![
+        (Point::new(x, y), false),
+        (Point::new(x + width, y), false),
+        (Point::new(x + width, y + height), false),
+        (Point::new(x, y + height), false),
+    ]
+}
+
+pub fn to_pdf_color(rgb: [u8; 3]) -> PdfColor {
+    PdfColor::Rgb(PdfRgb::new(rgb[0] as f32 / 255.0, rgb[1] as f32 / 255.0, rgb[2] as f32 / 255.0, None))
+}
+
+pub fn fill_rect(img: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+    let right = (x + width).min(img.width());
+    let bottom = (y + height).min(img.height());
+    for py in y..bottom {
+        for px in x..right {
+            img.put_pixel(px, py, color);
+        }
+    }
+}
+
+fn to_hex(rgb: [u8; 3]) -> String {
+    format!("#{:02X}{:02X}{:02X}", rgb[0], rgb[1], rgb[2])
+}
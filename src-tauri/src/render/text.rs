@@ -0,0 +1,170 @@
+use image::{Rgb, RgbImage};
+use rusttype::{point, Font, Scale};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Line height as a multiple of the font size
+pub const LINE_HEIGHT: f32 = 1.2;
+
+/// Text broken into lines at a font size that fits the requested box
+#[derive(Debug, Clone)]
+pub struct FittedText {
+    pub font_size: f32,
+    pub lines: Vec<String>,
+}
+
+/// Advance width of `text` at `font_size`, in the same unit as the font size
+pub fn measure(font: &Font, text: &str, font_size: f32) -> f32 {
+    let scale = Scale::uniform(font_size);
+    let mut width = 0.0;
+    let mut previous = None;
+
+    for c in text.chars() {
+        let glyph = font.glyph(c).scaled(scale);
+        if let Some(previous) = previous {
+            width += font.pair_kerning(scale, previous, glyph.id());
+        }
+        width += glyph.h_metrics().advance_width;
+        previous = Some(glyph.id());
+    }
+
+    width
+}
+
+/// Fail if the font has no glyph for a character of `text`, which would otherwise be
+/// drawn as an empty box or not at all
+pub fn check_glyphs(font: &Font, text: &str) -> Result<(), String> {
+    match text.chars().find(|c| !c.is_whitespace() && font.glyph(*c).id().0 == 0) {
+        Some(c) => Err(format!(
+            "The label font cannot display \"{}\"; choose a font that supports it in the label settings",
+            c
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Break `text` into lines no wider than `max_width`.
+/// Lines break at word boundaries, so CJK text can break between any two characters
+/// while Latin words stay together unless a single word is wider than the line.
+pub fn wrap(font: &Font, text: &str, font_size: f32, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+
+        for word in paragraph.split_word_bounds() {
+            let candidate = format!("{}{}", line, word);
+            if measure(font, &candidate, font_size) <= max_width {
+                line = candidate;
+                continue;
+            }
+
+            if !line.trim().is_empty() {
+                lines.push(line.trim_end().to_string());
+            }
+            line = String::new();
+
+            // Leading whitespace is dropped at the start of a new line
+            let word = if word.trim().is_empty() { "" } else { word };
+
+            // A word that doesn't fit on a line of its own is split between graphemes
+            for grapheme in word.graphemes(true) {
+                let candidate = format!("{}{}", line, grapheme);
+                if measure(font, &candidate, font_size) > max_width && !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                    line.push_str(grapheme);
+                } else {
+                    line = candidate;
+                }
+            }
+        }
+
+        if !line.trim().is_empty() {
+            lines.push(line.trim_end().to_string());
+        }
+    }
+
+    lines
+}
+
+/// Shorten `text` so it fits in `max_width`, ending in an ellipsis.
+/// Text is only cut between grapheme clusters so multi-byte characters stay intact.
+pub fn ellipsize(font: &Font, text: &str, font_size: f32, max_width: f32) -> String {
+    if measure(font, text, font_size) <= max_width {
+        return text.to_string();
+    }
+
+    // Fall back to three dots for fonts without a proper ellipsis glyph
+    let ellipsis = if font.glyph('…').id().0 == 0 { "..." } else { "…" };
+
+    let mut graphemes: Vec<&str> = text.graphemes(true).collect();
+    while !graphemes.is_empty() {
+        graphemes.pop();
+        let candidate = format!("{}{}", graphemes.concat().trim_end(), ellipsis);
+        if measure(font, &candidate, font_size) <= max_width {
+            return candidate;
+        }
+    }
+
+    String::new()
+}
+
+/// Fit `text` into a box `max_width` wide and `max_lines` lines high.
+/// The font shrinks from `font_size` down to `min_font_size` until the text fits;
+/// if it still doesn't fit, the last line is ellipsized.
+pub fn fit(font: &Font, text: &str, font_size: f32, min_font_size: f32, max_width: f32, max_lines: usize) -> FittedText {
+    let max_lines = max_lines.max(1);
+    let min_font_size = min_font_size.min(font_size);
+
+    let mut size = font_size;
+    loop {
+        let lines = wrap(font, text, size, max_width);
+        if lines.len() <= max_lines {
+            return FittedText { font_size: size, lines };
+        }
+
+        if size <= min_font_size {
+            break;
+        }
+        size = (size * 0.9).max(min_font_size);
+    }
+
+    let mut lines = wrap(font, text, min_font_size, max_width);
+    let separator = if text.contains(char::is_whitespace) { " " } else { "" };
+    let rest = lines.split_off(max_lines - 1).join(separator);
+    lines.push(ellipsize(font, &rest, min_font_size, max_width));
+
+    FittedText { font_size: min_font_size, lines }
+}
+
+/// Draw text on the image using rusttype. `y` is the top of the line, `font_size` is in pixels.
+pub fn draw_text(img: &mut RgbImage, font: &Font, text: &str, x: u32, y: u32, font_size: f32) {
+    let scale_font = Scale::uniform(font_size);
+
+    // Calculate vertical positioning (text baseline)
+    let v_metrics = font.v_metrics(scale_font);
+    let baseline = y as f32 + v_metrics.ascent;
+
+    for glyph in font.layout(text, scale_font, point(x as f32, baseline)) {
+        // Glyphs such as spaces have no outline to draw
+        let Some(bbox) = glyph.pixel_bounding_box() else {
+            continue;
+        };
+
+        glyph.draw(|gx, gy, intensity| {
+            let gx = gx as i32 + bbox.min.x;
+            let gy = gy as i32 + bbox.min.y;
+
+            if gx >= 0 && gy >= 0 {
+                let px = gx as u32;
+                let py = gy as u32;
+
+                if px < img.width() && py < img.height() {
+                    // Blend black text over whatever is already there
+                    let Rgb([r, g, b]) = *img.get_pixel(px, py);
+                    let blend = |c: u8| (c as f32 * (1.0 - intensity)) as u8;
+                    img.put_pixel(px, py, Rgb([blend(r), blend(g), blend(b)]));
+                }
+            }
+        });
+    }
+}
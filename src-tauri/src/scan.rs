@@ -0,0 +1,82 @@
+use base64::Engine;
+use image::DynamicImage;
+use rxing::Exceptions;
+
+/// Photos larger than this are also scanned downscaled, which finds large codes
+/// that the binarizer misses at full resolution
+const MAX_SCAN_SIZE: u32 = 2000;
+
+/// A barcode found in an image
+pub struct Decoded {
+    pub text: String,
+    pub format: String,
+    /// Corners or finder pattern centers in image pixels
+    pub points: Vec<[f32; 2]>,
+}
+
+/// Load an image from a file path
+pub fn load_file(path: &str) -> Result<DynamicImage, String> {
+    image::open(path).map_err(|e| format!("Failed to open image {}: {}", path, e))
+}
+
+/// Load an image from base64 data, with or without a `data:image/...;base64,` prefix
+pub fn load_base64(data: &str) -> Result<DynamicImage, String> {
+    let encoded = match data.split_once(',') {
+        Some((prefix, encoded)) if prefix.starts_with("data:") => encoded,
+        _ => data,
+    };
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| format!("Invalid base64 image: {}", e))?;
+
+    image::load_from_memory(&bytes).map_err(|e| format!("Failed to decode image: {}", e))
+}
+
+fn detect(img: &DynamicImage) -> Result<Vec<Decoded>, String> {
+    let luma = img.to_luma8();
+    let (width, height) = luma.dimensions();
+
+    let results = match rxing::helpers::detect_multiple_in_luma(luma.into_raw(), width, height) {
+        Ok(results) => results,
+        Err(Exceptions::NotFoundException(_)) => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Barcode detection failed: {}", e)),
+    };
+
+    Ok(results
+        .iter()
+        .map(|result| Decoded {
+            text: result.getText().to_string(),
+            format: result.getBarcodeFormat().to_string(),
+            points: result.getRXingResultPoints().iter().map(|point| [point.x, point.y]).collect(),
+        })
+        .collect())
+}
+
+/// Find and decode all QR codes and barcodes in an image. The same code seen more
+/// than once is only reported once.
+pub fn decode(img: &DynamicImage) -> Result<Vec<Decoded>, String> {
+    let mut found = detect(img)?;
+
+    if img.width().max(img.height()) > MAX_SCAN_SIZE {
+        let scale = img.width().max(img.height()) as f32 / MAX_SCAN_SIZE as f32;
+        let small = img.resize(MAX_SCAN_SIZE, MAX_SCAN_SIZE, image::imageops::FilterType::Triangle);
+        for mut decoded in detect(&small)? {
+            // Report positions in the original image
+            for point in &mut decoded.points {
+                point[0] *= scale;
+                point[1] *= scale;
+            }
+            found.push(decoded);
+        }
+    }
+
+    let mut unique: Vec<Decoded> = Vec::new();
+    for decoded in found {
+        if !unique.iter().any(|d| d.text == decoded.text && d.format == decoded.format) {
+            unique.push(decoded);
+        }
+    }
+
+    Ok(unique)
+}
@@ -0,0 +1,298 @@
+use crate::database::{models::ItemStock, tx_execute, tx_query_all, tx_query_one, DbTransaction};
+use sqlx::Row;
+
+pub const STOCK_SELECT: &str = "SELECT s.id, s.item_id, i.name AS item_name, s.location_id, l.name AS location_name, s.quantity, s.updated_at
+    FROM item_stock s JOIN items i ON i.id = s.item_id LEFT JOIN locations l ON l.id = s.location_id";
+
+pub fn row_to_stock(row: &sqlx::sqlite::SqliteRow) -> ItemStock {
+    ItemStock {
+        id: row.get("id"),
+        item_id: row.get("item_id"),
+        item_name: row.get("item_name"),
+        location_id: row.try_get("location_id").ok().flatten(),
+        location_name: row.try_get("location_name").ok().flatten(),
+        quantity: row.get("quantity"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+/// Result of changing the quantity of a stock row
+pub struct StockChange {
+    pub item_id: i32,
+    pub location_id: Option<i32>,
+    /// Quantity of the stock row after the change
+    pub quantity: i32,
+    /// Item total across all locations before and after the change
+    pub previous_total: i32,
+    pub total: i32,
+}
+
+/// Stock rows of an item
+pub async fn item_rows(tx: &mut DbTransaction, item_id: i32) -> Result<Vec<ItemStock>, String> {
+    let rows = tx_query_all(
+        tx,
+        &format!("{} WHERE s.item_id = ?1 ORDER BY l.name, s.id", STOCK_SELECT),
+        vec![item_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(rows.iter().map(row_to_stock).collect())
+}
+
+/// Fail if an open stocktake is counting the stock row. The count covers the row where it
+/// was when the stocktake started, so the stock must not move until it is committed.
+pub async fn check_not_counted(tx: &mut DbTransaction, stock_id: i32) -> Result<(), String> {
+    let counted = tx_query_one(
+        tx,
+        "SELECT t.id FROM stocktake_entries e JOIN stocktakes t ON t.id = e.stocktake_id
+         WHERE e.stock_id = ?1 AND t.status = 'open' LIMIT 1",
+        vec![stock_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match counted {
+        Some(row) => Err(format!(
+            "Stock is part of open stocktake {}; commit or abandon it first",
+            row.get::<i32, _>("id")
+        )),
+        None => Ok(()),
+    }
+}
+
+/// ID of the item's stock row at a location (`None` for unassigned stock), creating an
+/// empty row if there is none yet
+pub async fn find_or_create(tx: &mut DbTransaction, item_id: i32, location_id: Option<i32>) -> Result<i32, String> {
+    let existing = match location_id {
+        Some(location_id) => tx_query_one(
+            tx,
+            "SELECT id FROM item_stock WHERE item_id = ?1 AND location_id = ?2",
+            vec![item_id.to_string(), location_id.to_string()],
+        )
+        .await,
+        None => tx_query_one(
+            tx,
+            "SELECT id FROM item_stock WHERE item_id = ?1 AND location_id IS NULL ORDER BY id LIMIT 1",
+            vec![item_id.to_string()],
+        )
+        .await,
+    }
+    .map_err(|e| e.to_string())?;
+
+    if let Some(row) = existing {
+        return Ok(row.get("id"));
+    }
+
+    tx_execute(
+        tx,
+        "INSERT INTO item_stock (item_id, location_id, quantity) VALUES (?1, ?2, 0)",
+        vec![Some(item_id.to_string()), location_id.map(|id| id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let row = tx_query_one(tx, "SELECT last_insert_rowid() as id", vec![])
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Failed to get inserted ID".to_string())?;
+
+    Ok(row.get("id"))
+}
+
+/// Stock row to change for an item: `stock_id` if given, which must belong to the item,
+/// otherwise the item's only stock row, created at its main location if it has none
+pub async fn resolve(tx: &mut DbTransaction, item_id: i32, stock_id: Option<i32>) -> Result<i32, String> {
+    let rows = item_rows(tx, item_id).await?;
+    match (stock_id, rows.as_slice()) {
+        (Some(stock_id), rows) => {
+            if !rows.iter().any(|row| row.id == stock_id) {
+                return Err("Stock does not belong to this item".to_string());
+            }
+            Ok(stock_id)
+        }
+        (None, [row]) => Ok(row.id),
+        (None, []) => {
+            let item = tx_query_one(tx, "SELECT location_id FROM items WHERE id = ?1", vec![item_id.to_string()])
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "Item not found".to_string())?;
+            let location_id: Option<i32> = item.try_get("location_id").ok().flatten();
+            find_or_create(tx, item_id, location_id).await
+        }
+        (None, rows) => Err(format!("Item is stocked in {} locations; choose which one to update", rows.len())),
+    }
+}
+
+/// Recalculate an item's total quantity from its stock rows and return it
+pub async fn sync_total(tx: &mut DbTransaction, item_id: i32) -> Result<i32, String> {
+    tx_execute(
+        tx,
+        "UPDATE items SET quantity = (SELECT COALESCE(SUM(quantity), 0) FROM item_stock WHERE item_id = ?1), updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+        vec![Some(item_id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let row = tx_query_one(tx, "SELECT quantity FROM items WHERE id = ?1", vec![item_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Item not found".to_string())?;
+
+    Ok(row.get("quantity"))
+}
+
+/// Change the quantity of a stock row by `change`, log it in `inventory_log` and update the
+/// item's total. Fails without changes if the row would go negative.
+pub async fn adjust(
+    tx: &mut DbTransaction,
+    stock_id: i32,
+    change: i32,
+    operation_type: &str,
+    source: &str,
+    notes: Option<String>,
+) -> Result<StockChange, String> {
+    adjust_logged(tx, stock_id, change, operation_type, source, notes, None).await
+}
+
+/// `adjust`, recording the log entry as part of a transfer
+async fn adjust_logged(
+    tx: &mut DbTransaction,
+    stock_id: i32,
+    change: i32,
+    operation_type: &str,
+    source: &str,
+    notes: Option<String>,
+    transfer_id: Option<&str>,
+) -> Result<StockChange, String> {
+    let row = tx_query_one(
+        tx,
+        "SELECT s.item_id, s.location_id, s.quantity, i.quantity AS total FROM item_stock s JOIN items i ON i.id = s.item_id WHERE s.id = ?1",
+        vec![stock_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "Stock not found".to_string())?;
+
+    let item_id: i32 = row.get("item_id");
+    let location_id: Option<i32> = row.try_get("location_id").ok().flatten();
+    let previous_total: i32 = row.get("total");
+    let quantity = row.get::<i32, _>("quantity") + change;
+
+    if quantity < 0 {
+        return Err("Insufficient quantity".to_string());
+    }
+
+    tx_execute(
+        tx,
+        "UPDATE item_stock SET quantity = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+        vec![Some(quantity.to_string()), Some(stock_id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let total = sync_total(tx, item_id).await?;
+
+    tx_execute(
+        tx,
+        "INSERT INTO inventory_log (item_id, quantity_change, quantity_after, operation_type, source, notes, location_id, transfer_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        vec![
+            Some(item_id.to_string()),
+            Some(change.to_string()),
+            Some(total.to_string()),
+            Some(operation_type.to_string()),
+            Some(source.to_string()),
+            notes,
+            location_id.map(|id| id.to_string()),
+            transfer_id.map(|id| id.to_string()),
+        ],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(StockChange {
+        item_id,
+        location_id,
+        quantity,
+        previous_total,
+        total,
+    })
+}
+
+/// Move `quantity` of an item from one stock row to its stock at `to_location_id`, which is
+/// created if needed. Both log entries share a new transfer ID, which is returned together
+/// with the destination stock ID.
+pub async fn transfer(
+    tx: &mut DbTransaction,
+    from_stock_id: i32,
+    to_location_id: Option<i32>,
+    quantity: i32,
+    notes: Option<String>,
+) -> Result<(String, i32), String> {
+    if quantity <= 0 {
+        return Err("Transfer quantity must be positive".to_string());
+    }
+
+    let from = tx_query_one(tx, "SELECT item_id, location_id FROM item_stock WHERE id = ?1", vec![from_stock_id.to_string()])
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Stock not found".to_string())?;
+    let item_id: i32 = from.get("item_id");
+    let from_location_id: Option<i32> = from.try_get("location_id").ok().flatten();
+
+    if from_location_id == to_location_id {
+        return Err("Source and destination are the same location".to_string());
+    }
+
+    let to_stock_id = find_or_create(tx, item_id, to_location_id).await?;
+    check_not_counted(tx, from_stock_id).await?;
+    check_not_counted(tx, to_stock_id).await?;
+    let transfer_id = uuid::Uuid::new_v4().to_string();
+
+    adjust_logged(tx, from_stock_id, -quantity, "transfer", "transfer", notes.clone(), Some(&transfer_id)).await?;
+    let change = adjust_logged(tx, to_stock_id, quantity, "transfer", "transfer", notes, Some(&transfer_id)).await?;
+
+    // The item total doesn't change, so neither entry should show the halfway total
+    tx_execute(
+        tx,
+        "UPDATE inventory_log SET quantity_after = ?1 WHERE transfer_id = ?2",
+        vec![Some(change.total.to_string()), Some(transfer_id.clone())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok((transfer_id, to_stock_id))
+}
+
+/// Remove `quantity` of an item from wherever it is stocked, largest stock first, logging
+/// one entry per stock row touched. Returns the item total before the removal.
+pub async fn remove_from_item(
+    tx: &mut DbTransaction,
+    item_id: i32,
+    quantity: i32,
+    operation_type: &str,
+    source: &str,
+    notes: Option<String>,
+) -> Result<i32, String> {
+    let mut rows = item_rows(tx, item_id).await?;
+    let total: i32 = rows.iter().map(|row| row.quantity).sum();
+    if total < quantity {
+        let name = rows.first().map(|row| row.item_name.clone()).unwrap_or_else(|| format!("#{}", item_id));
+        return Err(format!("Insufficient quantity of {}: {} needed, {} in stock", name, quantity, total));
+    }
+
+    rows.sort_by_key(|row| std::cmp::Reverse(row.quantity));
+    let mut remaining = quantity;
+    for row in rows {
+        if remaining == 0 {
+            break;
+        }
+        let taken = remaining.min(row.quantity);
+        if taken > 0 {
+            adjust(tx, row.id, -taken, operation_type, source, notes.clone()).await?;
+            remaining -= taken;
+        }
+    }
+
+    Ok(total)
+}
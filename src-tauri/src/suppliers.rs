@@ -0,0 +1,79 @@
+use crate::database::{tx_execute, tx_query_one, DbTransaction};
+use sqlx::Row;
+
+/// Currency of offers entered without one
+pub const DEFAULT_CURRENCY: &str = "CNY";
+
+/// ID of the row in `table` named `name`, creating it if needed. Names are matched
+/// case-insensitively, so an existing row keeps its spelling.
+async fn ensure_named(tx: &mut DbTransaction, table: &str, name: &str) -> Result<i32, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Name cannot be empty".to_string());
+    }
+
+    tx_execute(tx, &format!("INSERT OR IGNORE INTO {} (name) VALUES (?1)", table), vec![Some(name.to_string())])
+        .await
+        .map_err(|e| e.to_string())?;
+    let row = tx_query_one(tx, &format!("SELECT id FROM {} WHERE name = ?1", table), vec![name.to_string()])
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Failed to create {}", table))?;
+
+    Ok(row.get("id"))
+}
+
+pub async fn ensure_manufacturer(tx: &mut DbTransaction, name: &str) -> Result<i32, String> {
+    ensure_named(tx, "manufacturers", name).await
+}
+
+/// Upper-case ISO 4217 code, defaulting to `DEFAULT_CURRENCY`
+pub fn normalize_currency(currency: Option<&str>) -> Result<String, String> {
+    let currency = currency.map(|c| c.trim().to_uppercase()).filter(|c| !c.is_empty());
+    match currency {
+        None => Ok(DEFAULT_CURRENCY.to_string()),
+        Some(code) if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) => Ok(code),
+        Some(code) => Err(format!("Invalid currency \"{}\"; use a three-letter code such as CNY or USD", code)),
+    }
+}
+
+/// Add a price history entry for an offer unless its latest entry already has this price
+pub async fn record_price(tx: &mut DbTransaction, offer_id: i32, unit_price: f64, currency: &str) -> Result<(), String> {
+    let latest = tx_query_one(
+        tx,
+        "SELECT unit_price, currency FROM price_history WHERE offer_id = ?1 ORDER BY recorded_at DESC, id DESC LIMIT 1",
+        vec![offer_id.to_string()],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let unchanged = latest.is_some_and(|row| {
+        row.get::<f64, _>("unit_price") == unit_price && row.get::<String, _>("currency") == currency
+    });
+    if unchanged {
+        return Ok(());
+    }
+
+    tx_execute(
+        tx,
+        "INSERT INTO price_history (offer_id, unit_price, currency) VALUES (?1, ?2, ?3)",
+        vec![Some(offer_id.to_string()), Some(unit_price.to_string()), Some(currency.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Make `offer_id` the only preferred offer of its item
+pub async fn set_preferred(tx: &mut DbTransaction, item_id: i32, offer_id: i32) -> Result<(), String> {
+    tx_execute(
+        tx,
+        "UPDATE item_offers SET is_preferred = CASE WHEN id = ?2 THEN 1 ELSE 0 END WHERE item_id = ?1",
+        vec![Some(item_id.to_string()), Some(offer_id.to_string())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
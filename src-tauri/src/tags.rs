@@ -0,0 +1,68 @@
+use crate::database::{tx_execute, tx_query_one, DbTransaction};
+use sqlx::Row;
+
+/// Trimmed tag names without blanks or case-insensitive duplicates, in their given order
+pub fn normalize(names: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for name in names.iter().map(|name| name.trim()).filter(|name| !name.is_empty()) {
+        if !normalized.iter().any(|existing| existing.to_lowercase() == name.to_lowercase()) {
+            normalized.push(name.to_string());
+        }
+    }
+    normalized
+}
+
+/// IDs of the named tags, creating those that don't exist yet. Names are matched
+/// case-insensitively, so an existing tag keeps its spelling.
+pub async fn ensure(tx: &mut DbTransaction, names: &[String]) -> Result<Vec<i32>, String> {
+    let mut tag_ids = Vec::new();
+    for name in normalize(names) {
+        tx_execute(tx, "INSERT OR IGNORE INTO tags (name) VALUES (?1)", vec![Some(name.clone())])
+            .await
+            .map_err(|e| e.to_string())?;
+        let row = tx_query_one(tx, "SELECT id FROM tags WHERE name = ?1", vec![name])
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Failed to create tag".to_string())?;
+        tag_ids.push(row.get("id"));
+    }
+    Ok(tag_ids)
+}
+
+/// Replace the tags of an item
+pub async fn set_item_tags(tx: &mut DbTransaction, item_id: i32, names: &[String]) -> Result<(), String> {
+    let tag_ids = ensure(tx, names).await?;
+
+    tx_execute(tx, "DELETE FROM item_tags WHERE item_id = ?1", vec![Some(item_id.to_string())])
+        .await
+        .map_err(|e| e.to_string())?;
+    for tag_id in tag_ids {
+        tx_execute(
+            tx,
+            "INSERT INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+            vec![Some(item_id.to_string()), Some(tag_id.to_string())],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Replace the tags of a location
+pub async fn set_location_tags(tx: &mut DbTransaction, location_id: i32, names: &[String]) -> Result<(), String> {
+    let tag_ids = ensure(tx, names).await?;
+
+    tx_execute(tx, "DELETE FROM location_tags WHERE location_id = ?1", vec![Some(location_id.to_string())])
+        .await
+        .map_err(|e| e.to_string())?;
+    for tag_id in tag_ids {
+        tx_execute(
+            tx,
+            "INSERT INTO location_tags (location_id, tag_id) VALUES (?1, ?2)",
+            vec![Some(location_id.to_string()), Some(tag_id.to_string())],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}